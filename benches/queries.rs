@@ -0,0 +1,39 @@
+#[macro_use]
+extern crate criterion;
+extern crate free_ranges;
+
+use criterion::{black_box, Criterion};
+use free_ranges::{FreeRanges, Range};
+
+/// Every other block of 8 indices free across the first 160k indices
+fn fragmented() -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for block in 0..10_000 {
+        let min = block * 16;
+        ranges.set_range_free(Range { min, max: min + 7 });
+    }
+    ranges
+}
+
+fn sorted_queries(c: &mut Criterion) {
+    let ranges = fragmented();
+    let indices: Vec<usize> = (0..10_000).map(|i| i * 16 + i % 11).collect();
+
+    let mut group = c.benchmark_group("10k sorted queries");
+    group.bench_function("is_free per index", |b| {
+        b.iter(|| {
+            let indices = black_box(&indices);
+            indices
+                .iter()
+                .map(|&i| ranges.is_free(i))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("are_free_sorted", |b| {
+        b.iter(|| ranges.are_free_sorted(black_box(&indices)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sorted_queries);
+criterion_main!(benches);
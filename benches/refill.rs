@@ -0,0 +1,43 @@
+#[macro_use]
+extern crate criterion;
+extern crate free_ranges;
+
+use criterion::{black_box, Criterion};
+use free_ranges::{FreeRanges, Range};
+
+/// Frees every other block of 16 in the first 64K indices, leaving 2048
+/// ranges, so the set is well past the single inline range
+fn refill(ranges: &mut FreeRanges) {
+    for block in (0..4096).step_by(2) {
+        let min = block * 16;
+        ranges.set_range_free(Range { min, max: min + 15 });
+    }
+}
+
+/// Repeated reset cycles: clearing and refilling one set, which has to
+/// allocate the tree's nodes again every time, against cloning a filled
+/// template, which allocates them in one pass
+fn clear_then_refill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reset 2048 ranges");
+    group.bench_function("clear then refill", |b| {
+        let mut ranges = FreeRanges::new();
+        b.iter(|| {
+            ranges.clear();
+            refill(&mut ranges);
+            black_box(ranges.range_count())
+        })
+    });
+    group.bench_function("clone_from template", |b| {
+        let mut template = FreeRanges::new();
+        refill(&mut template);
+        let mut ranges = FreeRanges::new();
+        b.iter(|| {
+            ranges.clone_from(&template);
+            black_box(ranges.range_count())
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, clear_then_refill);
+criterion_main!(benches);
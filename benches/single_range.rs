@@ -0,0 +1,51 @@
+#[macro_use]
+extern crate criterion;
+extern crate free_ranges;
+
+use criterion::{black_box, Criterion};
+use free_ranges::{FreeRanges, Range};
+
+/// Bump-allocates 64 blocks from a fresh arena and frees them again. With
+/// `extra` a far-away range is freed first, so the arena holds two ranges
+/// and goes through the tree the whole time
+fn arena_cycle(extra: bool) -> FreeRanges {
+    let mut arena = FreeRanges::with_initial_range(Range {
+        min: 0,
+        max: 1 << 20,
+    });
+    if extra {
+        arena.set_range_free(Range {
+            min: 1 << 30,
+            max: 1 << 31,
+        });
+    }
+    let mut blocks = Vec::with_capacity(64);
+    for _ in 0..64 {
+        blocks.push(arena.allocate(black_box(16)).unwrap());
+    }
+    for &block in blocks.iter().rev() {
+        arena.set_range_free(block);
+    }
+    arena
+}
+
+fn single_range(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fresh arena");
+    group.bench_function("one range (inline)", |b| b.iter(|| arena_cycle(false)));
+    group.bench_function("two ranges (tree)", |b| b.iter(|| arena_cycle(true)));
+    group.finish();
+
+    let mut group = c.benchmark_group("new pool");
+    group.bench_function("with_initial_range", |b| {
+        b.iter(|| {
+            FreeRanges::with_initial_range(Range {
+                min: 0,
+                max: black_box(1 << 20),
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, single_range);
+criterion_main!(benches);
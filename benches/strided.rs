@@ -0,0 +1,43 @@
+#[macro_use]
+extern crate criterion;
+extern crate free_ranges;
+
+use criterion::{black_box, BatchSize, Criterion};
+use free_ranges::{FreeRanges, Range};
+
+const WINDOW: Range = Range {
+    min: 0,
+    max: 999_999,
+};
+
+/// Marking every 64th index of a fully free 1M index window as used
+fn strided_reservation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("every 64th of 1M indices");
+    group.sample_size(20);
+    group.bench_function("set_used loop", |b| {
+        b.iter_batched(
+            || FreeRanges::with_initial_range(WINDOW),
+            |mut ranges| {
+                for index in (WINDOW.min..=WINDOW.max).step_by(64) {
+                    ranges.set_used(black_box(index));
+                }
+                ranges
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("set_used_strided", |b| {
+        b.iter_batched(
+            || FreeRanges::with_initial_range(WINDOW),
+            |mut ranges| {
+                ranges.set_used_strided(black_box(WINDOW), 64, 0);
+                ranges
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, strided_reservation);
+criterion_main!(benches);
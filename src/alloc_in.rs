@@ -0,0 +1,293 @@
+use std::cmp;
+use std::fmt;
+use std::iter;
+use std::slice;
+
+use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::vec::Vec;
+
+use super::{FreeRanges, Range};
+
+/// A set of free ranges whose storage comes from the allocator `A`, for
+/// subsystems where every allocation must come from an arena or another
+/// custom allocator.
+///
+/// The ranges are kept in a sorted Vec instead of a BTreeSet, since only
+/// nightly lets a BTreeSet use another allocator. Lookups are binary
+/// searches, but freeing or using an index in the middle shifts the ranges
+/// above it, so this suits sets of up to a few thousand ranges. Clones and
+/// the halves made by `split_off` allocate from a clone of the allocator.
+pub struct FreeRangesIn<A: Allocator = Global> {
+    free_list: Vec<Range, A>,
+}
+
+impl FreeRanges {
+    /// Starts an empty set whose storage comes from `alloc`, see
+    /// `FreeRangesIn`
+    #[inline]
+    pub fn new_in<A: Allocator>(alloc: A) -> FreeRangesIn<A> {
+        FreeRangesIn::new_in(alloc)
+    }
+}
+
+impl<A: Allocator> FreeRangesIn<A> {
+    /// Starts an empty set. Nothing is allocated until a range is freed
+    #[inline]
+    pub fn new_in(alloc: A) -> Self {
+        FreeRangesIn {
+            free_list: Vec::new_in(alloc),
+        }
+    }
+
+    /// Starts with every index in `range` free
+    pub fn with_initial_range_in(range: Range, alloc: A) -> Self {
+        let mut ranges = FreeRangesIn::new_in(alloc);
+        ranges.set_range_free(range);
+        ranges
+    }
+
+    /// Copies the ranges of `ranges` into storage from `alloc`
+    pub fn from_ranges_in(ranges: &FreeRanges, alloc: A) -> Self {
+        let mut free_list = Vec::with_capacity_in(ranges.range_count(), alloc);
+        free_list.extend(ranges.free_ranges().cloned());
+        FreeRangesIn { free_list }
+    }
+
+    /// The allocator the ranges are stored in
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        self.free_list.allocator()
+    }
+
+    /// Copies the ranges into a FreeRanges, which uses the global allocator
+    #[inline]
+    pub fn to_free_ranges(&self) -> FreeRanges {
+        FreeRanges::from_inner_unchecked(self.free_list.iter().cloned().collect())
+    }
+
+    /// Iterator over all of the free ranges, lowest first
+    #[inline]
+    pub fn free_ranges(&self) -> slice::Iter<'_, Range> {
+        self.free_list.iter()
+    }
+
+    /// The number of contiguous free ranges
+    #[inline]
+    pub fn range_count(&self) -> usize {
+        self.free_list.len()
+    }
+
+    /// Returns true if `index` is free
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        self.find(index).is_ok()
+    }
+
+    /// Returns true if every index in `range` is free
+    #[inline]
+    pub fn is_range_free(&self, range: Range) -> bool {
+        match self.find(range.min) {
+            Ok(i) => self.free_list[i].max >= range.max,
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the first free value if one exists
+    #[inline]
+    pub fn first(&self) -> Option<usize> {
+        self.free_list.first().map(|r| r.min)
+    }
+
+    /// Returns the last free value if one exists
+    #[inline]
+    pub fn last(&self) -> Option<usize> {
+        self.free_list.last().map(|r| r.max)
+    }
+
+    /// Marks a specific index as free. Returns false if it was already free
+    #[inline]
+    pub fn set_free(&mut self, index: usize) -> bool {
+        self.set_range_free(Range::id(index))
+    }
+
+    /// Marks every index in `range` as free, merging it with the ranges it
+    /// overlaps or touches. Returns false if all of it was already free
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        if range.empty() || self.is_range_free(range) {
+            return false;
+        }
+
+        let lo = self
+            .free_list
+            .partition_point(|r| r.max.saturating_add(1) < range.min);
+        let hi = self
+            .free_list
+            .partition_point(|r| r.min <= range.max.saturating_add(1));
+        let merged = if lo < hi {
+            Range {
+                min: cmp::min(range.min, self.free_list[lo].min),
+                max: cmp::max(range.max, self.free_list[hi - 1].max),
+            }
+        } else {
+            range
+        };
+        self.free_list.splice(lo..hi, iter::once(merged));
+        true
+    }
+
+    /// Marks a free index as used. Returns false if the index was not free
+    #[inline]
+    pub fn set_used(&mut self, index: usize) -> bool {
+        self.set_range_used(Range::id(index))
+    }
+
+    /// Marks every index in `range` as used, trimming any free ranges that
+    /// overlap it. Returns false if none of them were free
+    pub fn set_range_used(&mut self, range: Range) -> bool {
+        if range.empty() {
+            return false;
+        }
+
+        let lo = self.free_list.partition_point(|r| r.max < range.min);
+        let hi = self.free_list.partition_point(|r| r.min <= range.max);
+        if lo == hi {
+            return false;
+        }
+
+        let (first, last) = (self.free_list[lo], self.free_list[hi - 1]);
+        let below = Some(Range {
+            min: first.min,
+            max: range.min.wrapping_sub(1),
+        })
+        .filter(|_| first.min < range.min);
+        let above = Some(Range {
+            min: range.max.wrapping_add(1),
+            max: last.max,
+        })
+        .filter(|_| last.max > range.max);
+        self.free_list
+            .splice(lo..hi, below.into_iter().chain(above));
+        true
+    }
+
+    /// Marks the first index in the free list as used and returns it
+    pub fn set_first_used(&mut self) -> Option<usize> {
+        let first = self.first()?;
+        self.carve(0, Range::id(first));
+        Some(first)
+    }
+
+    /// Marks the first `size` contiguous free indices as used and returns
+    /// them, taking them from the front of the first range that fits
+    pub fn allocate(&mut self, size: usize) -> Option<Range> {
+        let span = size.checked_sub(1)?;
+        let i = self.free_list.iter().position(|r| r.max - r.min >= span)?;
+        let min = self.free_list[i].min;
+        let carved = Range {
+            min,
+            max: min + span,
+        };
+        self.carve(i, carved);
+        Some(carved)
+    }
+
+    /// Marks every index as used. The Vec keeps its capacity
+    #[inline]
+    pub fn clear(&mut self) {
+        self.free_list.clear();
+    }
+
+    /// Splits the set in two at `at`, leaving the indices below `at` in
+    /// `self` and returning the ones at or above it in storage from a clone
+    /// of the allocator
+    pub fn split_off(&mut self, at: usize) -> Self
+    where
+        A: Clone,
+    {
+        let i = self.free_list.partition_point(|r| r.max < at);
+        let mut higher = self.free_list.split_off(i);
+        if let Some(straddling) = higher.first_mut() {
+            if straddling.min < at {
+                self.free_list.push(Range {
+                    min: straddling.min,
+                    max: at - 1,
+                });
+                straddling.min = at;
+            }
+        }
+        FreeRangesIn { free_list: higher }
+    }
+
+    /// Appends every range of `higher`, which must lie entirely above the
+    /// ranges in `self` (as produced by `split_off`), merging the two
+    /// ranges that meet at the boundary if they are adjacent
+    pub fn join<B: Allocator>(&mut self, higher: FreeRangesIn<B>) {
+        let mut higher = higher.free_list.into_iter();
+        if let (Some(last), Some(first)) = (self.free_list.last_mut(), higher.as_slice().first()) {
+            debug_assert!(last.max < first.min, "joined ranges must lie above self");
+            if last.max + 1 == first.min {
+                last.max = first.max;
+                higher.next();
+            }
+        }
+        self.free_list.extend(higher);
+    }
+
+    /// The range holding `index`, or where a range holding it would go
+    #[inline]
+    fn find(&self, index: usize) -> Result<usize, usize> {
+        self.free_list.binary_search_by(|r| {
+            if r.max < index {
+                cmp::Ordering::Less
+            } else if r.min > index {
+                cmp::Ordering::Greater
+            } else {
+                cmp::Ordering::Equal
+            }
+        })
+    }
+
+    /// Marks `carved` as used, which must be a prefix of the range at `i`
+    #[inline]
+    fn carve(&mut self, i: usize, carved: Range) {
+        if carved.max == self.free_list[i].max {
+            self.free_list.remove(i);
+        } else {
+            self.free_list[i].min = carved.max + 1;
+        }
+    }
+}
+
+impl<A: Allocator + Clone> Clone for FreeRangesIn<A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        FreeRangesIn {
+            free_list: self.free_list.clone(),
+        }
+    }
+}
+
+impl<A: Allocator + Default> Default for FreeRangesIn<A> {
+    #[inline]
+    fn default() -> Self {
+        FreeRangesIn::new_in(A::default())
+    }
+}
+
+impl<A: Allocator> fmt::Debug for FreeRangesIn<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("FreeRangesIn")
+            .field("free_list", &&self.free_list[..])
+            .finish()
+    }
+}
+
+impl<'a, A: Allocator> IntoIterator for &'a FreeRangesIn<A> {
+    type Item = &'a Range;
+    type IntoIter = slice::Iter<'a, Range>;
+
+    #[inline]
+    fn into_iter(self) -> slice::Iter<'a, Range> {
+        self.free_ranges()
+    }
+}
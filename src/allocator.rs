@@ -0,0 +1,58 @@
+use std::alloc::Layout;
+
+use super::{FreeRanges, Range};
+
+/// Suballocates byte offsets within a buffer of fixed length, using a
+/// FreeRanges with one index per byte
+#[derive(Debug, Clone)]
+pub struct FreeRangesAllocator {
+    ranges: FreeRanges,
+    len: usize,
+}
+
+impl FreeRangesAllocator {
+    /// Starts with every byte of a `len` byte buffer free
+    pub fn new(len: usize) -> Self {
+        let mut ranges = FreeRanges::new();
+        if let Some(all) = Range::from_len(0, len) {
+            ranges.set_range_free(all);
+        }
+        FreeRangesAllocator { ranges, len }
+    }
+
+    /// The length of the buffer in bytes
+    #[inline]
+    pub fn buffer_len(&self) -> usize {
+        self.len
+    }
+
+    /// The bytes which are still free
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    /// Finds room for `layout` and returns its offset into the buffer.
+    /// Zero sized layouts take no space and always get offset 0
+    pub fn allocate(&mut self, layout: Layout) -> Option<usize> {
+        if layout.size() == 0 {
+            return Some(0);
+        }
+        self.ranges
+            .allocate_aligned(layout.size(), layout.align())
+            .map(|range| range.min)
+    }
+
+    /// Returns the block at `offset` which was allocated with `layout`.
+    /// Passing anything else leaves the free space inconsistent
+    pub fn deallocate(&mut self, offset: usize, layout: Layout) {
+        if let Some(range) = Range::from_len(offset, layout.size()) {
+            debug_assert!(
+                range.max < self.len && !self.ranges.free_list.contains(&range),
+                "deallocated block {:?} was not allocated",
+                range
+            );
+            self.ranges.set_range_free(range);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use super::{check_canonical, FreeRanges, Range};
+
+/// Encoded as `min` followed by `max`, each as a u64
+impl BorshSerialize for Range {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.min.serialize(writer)?;
+        self.max.serialize(writer)
+    }
+}
+
+impl BorshDeserialize for Range {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let min = usize::deserialize_reader(reader)?;
+        let max = usize::deserialize_reader(reader)?;
+        Ok(Range { min, max })
+    }
+}
+
+/// Encoded like a `Vec<Range>`: a u32 count followed by the ranges in
+/// ascending order
+impl BorshSerialize for FreeRanges {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let len = u32::try_from(self.free_list.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many ranges"))?;
+        len.serialize(writer)?;
+        for range in &self.free_list {
+            range.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rejects encodings whose ranges are empty, out of order, overlapping or
+/// adjacent, rather than normalizing them, so every set has exactly one
+/// accepted encoding
+impl BorshDeserialize for FreeRanges {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = u32::deserialize_reader(reader)?;
+        let mut ranges = Vec::new();
+        for _ in 0..len {
+            ranges.push(Range::deserialize_reader(reader)?);
+        }
+
+        check_canonical(ranges.iter().cloned())
+            .map_err(|violation| io::Error::new(io::ErrorKind::InvalidData, violation))?;
+        Ok(FreeRanges::from_inner_unchecked(
+            ranges.into_iter().collect::<BTreeSet<_>>(),
+        ))
+    }
+}
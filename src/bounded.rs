@@ -0,0 +1,103 @@
+use std::collections::btree_set::Iter;
+
+use super::{FreeRanges, Range};
+
+/// A FreeRanges whose indices are limited to `0..=BOUND` at compile time.
+/// Indices past `BOUND` are rejected instead of being tracked, so a fixed
+/// size pool can't hand out or accept anything outside of itself.
+#[derive(Debug, Clone, Default)]
+pub struct BoundedFreeRanges<const BOUND: usize> {
+    ranges: FreeRanges,
+}
+
+impl<const BOUND: usize> BoundedFreeRanges<BOUND> {
+    /// Every index this set can hold
+    pub const DOMAIN: Range = Range { min: 0, max: BOUND };
+
+    /// Starts empty with no indices free
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts with `0..=BOUND` free
+    #[inline]
+    pub fn with_all_free() -> Self {
+        BoundedFreeRanges {
+            ranges: FreeRanges::with_initial_range(Self::DOMAIN),
+        }
+    }
+
+    /// The free indices
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    /// Iterator over the free ranges, lowest first
+    #[inline]
+    pub fn free_ranges(&self) -> Iter<'_, Range> {
+        self.ranges.free_ranges()
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        self.ranges.is_free(index)
+    }
+
+    /// Marks `index` as free. Returns false if it was already free or is
+    /// past `BOUND`
+    #[inline]
+    pub fn set_free(&mut self, index: usize) -> bool {
+        index <= BOUND && self.ranges.set_free(index)
+    }
+
+    /// Marks every index in `range` as free. Returns false if they were
+    /// all free already, or without changing anything if the range goes
+    /// past `BOUND`
+    #[inline]
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        range.max <= BOUND && self.ranges.set_range_free(range)
+    }
+
+    /// Marks a free index as used. Returns false if the index was not free
+    #[inline]
+    pub fn set_used(&mut self, index: usize) -> bool {
+        self.ranges.set_used(index)
+    }
+
+    #[inline]
+    pub fn first(&self) -> Option<usize> {
+        self.ranges.first()
+    }
+
+    #[inline]
+    pub fn set_first_used(&mut self) -> Option<usize> {
+        self.ranges.set_first_used()
+    }
+
+    #[inline]
+    pub fn last(&self) -> Option<usize> {
+        self.ranges.last()
+    }
+
+    #[inline]
+    pub fn set_last_used(&mut self) -> Option<usize> {
+        self.ranges.set_last_used()
+    }
+
+    /// Marks the first `size` contiguous free indices as used and returns
+    /// them, like `FreeRanges::allocate`
+    #[inline]
+    pub fn allocate(&mut self, size: usize) -> Option<Range> {
+        self.ranges.allocate(size)
+    }
+
+    /// The number of free indices. This can't overflow unless `BOUND` is
+    /// usize::MAX, in which case it saturates
+    pub fn free_count(&self) -> usize {
+        self.ranges.free_list.iter().fold(0usize, |n, r| {
+            n.saturating_add(r.max - r.min).saturating_add(1)
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+
+use super::{FreeRanges, Range};
+
+/// Builds the initial state of a pool: a domain which starts out free,
+/// reserved regions punched out of it, and extra ranges freed afterwards
+#[derive(Debug, Clone, Default)]
+pub struct FreeRangesBuilder {
+    domain: Option<Range>,
+    reserved: Vec<Range>,
+    extra_free: Vec<Range>,
+}
+
+impl FreeRangesBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the indices which start out free
+    #[inline]
+    pub fn domain<R: Into<Range>>(mut self, domain: R) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Sets the domain to `0..=bound`, the same as `domain(0..=bound)`
+    #[inline]
+    pub fn with_bound(self, bound: usize) -> Self {
+        self.domain(Range { min: 0, max: bound })
+    }
+
+    /// Marks a region of the domain as used. Reservations may overlap each
+    /// other, but must lie inside the domain
+    #[inline]
+    pub fn reserve<R: Into<Range>>(mut self, range: R) -> Self {
+        self.reserved.push(range.into());
+        self
+    }
+
+    /// Marks a range as free after the reservations have been applied, even
+    /// if it overlaps one of them or lies outside the domain
+    #[inline]
+    pub fn free_extra<R: Into<Range>>(mut self, range: R) -> Self {
+        self.extra_free.push(range.into());
+        self
+    }
+
+    pub fn build(self) -> Result<FreeRanges, BuildError> {
+        let domain = self.domain.ok_or(BuildError::MissingDomain)?;
+        if domain.empty() {
+            return Err(BuildError::EmptyDomain(domain));
+        }
+        for (call, &range) in self.reserved.iter().enumerate() {
+            if range.empty() || range.min < domain.min || range.max > domain.max {
+                return Err(BuildError::ReserveOutsideDomain {
+                    call,
+                    range,
+                    domain,
+                });
+            }
+        }
+        for (call, &range) in self.extra_free.iter().enumerate() {
+            if range.empty() {
+                return Err(BuildError::EmptyFreeExtra { call, range });
+            }
+        }
+
+        let mut ranges = FreeRanges::with_initial_range(domain);
+        for &range in &self.reserved {
+            ranges.set_range_used(range);
+        }
+        for &range in &self.extra_free {
+            ranges.set_range_free(range);
+        }
+        Ok(ranges)
+    }
+}
+
+/// Why a `FreeRangesBuilder` could not build. `call` counts the calls to
+/// the named builder method from 0, in the order they were made
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// `domain` was never called
+    MissingDomain,
+    /// The domain has its min above its max
+    EmptyDomain(Range),
+    /// A `reserve` call is empty or reaches outside the domain
+    ReserveOutsideDomain {
+        call: usize,
+        range: Range,
+        domain: Range,
+    },
+    /// A `free_extra` call has its min above its max
+    EmptyFreeExtra { call: usize, range: Range },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::MissingDomain => write!(fmt, "domain() was never called"),
+            BuildError::EmptyDomain(domain) => write!(fmt, "domain({:?}) is empty", domain),
+            BuildError::ReserveOutsideDomain {
+                call,
+                range,
+                domain,
+            } => write!(
+                fmt,
+                "reserve #{} ({:?}) is not inside the domain {:?}",
+                call, range, domain
+            ),
+            BuildError::EmptyFreeExtra { call, range } => {
+                write!(fmt, "free_extra #{} ({:?}) is empty", call, range)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
@@ -0,0 +1,116 @@
+use std::ops::RangeInclusive;
+
+use super::{FreeRanges, Range};
+
+const SURROGATE_MIN: usize = 0xD800;
+const SURROGATE_MAX: usize = 0xDFFF;
+
+/// A FreeRanges of Unicode scalar values, so free codepoints can be managed
+/// as `char`s. The surrogates D800–DFFF aren't chars and are never free.
+#[derive(Debug, Clone, Default)]
+pub struct CharRanges {
+    ranges: FreeRanges,
+}
+
+impl CharRanges {
+    /// Starts with no free chars
+    #[inline]
+    pub fn new() -> Self {
+        CharRanges {
+            ranges: FreeRanges::new(),
+        }
+    }
+
+    /// Starts with every char free
+    pub fn with_all_free() -> Self {
+        let mut chars = CharRanges::new();
+        chars.set_range_free('\0'..=char::MAX);
+        chars
+    }
+
+    /// Starts with the Private Use Area of the Basic Multilingual Plane free,
+    /// U+E000–U+F8FF
+    pub fn with_bmp_private_use() -> Self {
+        let mut chars = CharRanges::new();
+        chars.set_range_free('\u{E000}'..='\u{F8FF}');
+        chars
+    }
+
+    /// Starts with every Private Use Area free: U+E000–U+F8FF along with
+    /// planes 15 and 16, minus their last two noncharacters
+    pub fn with_private_use() -> Self {
+        let mut chars = CharRanges::with_bmp_private_use();
+        chars.set_range_free('\u{F0000}'..='\u{FFFFD}');
+        chars.set_range_free('\u{100000}'..='\u{10FFFD}');
+        chars
+    }
+
+    /// The free codepoints. None of them are surrogates.
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    #[inline]
+    pub fn is_free(&self, c: char) -> bool {
+        self.ranges.is_free(c as usize)
+    }
+
+    #[inline]
+    pub fn set_free(&mut self, c: char) -> bool {
+        self.ranges.set_free(c as usize)
+    }
+
+    /// Frees every char in `range`, skipping the surrogates if it straddles
+    /// them
+    pub fn set_range_free(&mut self, range: RangeInclusive<char>) {
+        let (min, max) = (*range.start() as usize, *range.end() as usize);
+        if min > max {
+            return;
+        }
+        if min < SURROGATE_MIN {
+            self.ranges.set_range_free(Range {
+                min,
+                max: max.min(SURROGATE_MIN - 1),
+            });
+        }
+        if max > SURROGATE_MAX {
+            self.ranges.set_range_free(Range {
+                min: min.max(SURROGATE_MAX + 1),
+                max,
+            });
+        }
+    }
+
+    #[inline]
+    pub fn set_used(&mut self, c: char) -> bool {
+        self.ranges.set_used(c as usize)
+    }
+
+    /// The lowest free char
+    #[inline]
+    pub fn first_free(&self) -> Option<char> {
+        self.ranges.first().map(to_char)
+    }
+
+    /// Marks the lowest free char as used and returns it
+    #[inline]
+    pub fn set_first_used(&mut self) -> Option<char> {
+        self.ranges.set_first_used().map(to_char)
+    }
+
+    /// Iterator over the runs of free chars, lowest first
+    pub fn free_ranges(&self) -> impl Iterator<Item = RangeInclusive<char>> + '_ {
+        self.ranges
+            .free_list
+            .iter()
+            .map(|range| to_char(range.min)..=to_char(range.max))
+    }
+}
+
+/// Free ranges never touch the surrogates and never pass char::MAX, and the
+/// surrogate gap keeps the ranges on either side of it from merging
+#[inline]
+fn to_char(index: usize) -> char {
+    std::char::from_u32(index as u32).expect("free codepoint is not a char")
+}
@@ -0,0 +1,220 @@
+use super::{FreeRanges, Range};
+
+/// Keeps track of free indices in the ring `0..len`, where the last index
+/// wraps around to be adjacent to index 0.
+#[derive(Debug, Clone)]
+pub struct CircularFreeRanges {
+    ranges: FreeRanges,
+    len: usize,
+}
+
+impl CircularFreeRanges {
+    /// Starts empty with no indices in the ring free. Panics if `len` is 0
+    #[inline]
+    pub fn new(len: usize) -> Self {
+        assert!(len > 0, "a CircularFreeRanges ring must not be empty");
+        CircularFreeRanges {
+            ranges: FreeRanges::new(),
+            len,
+        }
+    }
+
+    /// Initializes the ring with every index in `0..len` already free
+    #[inline]
+    pub fn with_all_free(len: usize) -> Self {
+        let mut ring = CircularFreeRanges::new(len);
+        ring.ranges.set_range_free(Range {
+            min: 0,
+            max: len - 1,
+        });
+        ring
+    }
+
+    /// The number of indices in the ring
+    #[inline]
+    pub fn ring_len(&self) -> usize {
+        self.len
+    }
+
+    /// The underlying free ranges. These are never joined across the seam,
+    /// so a wrapped region shows up as one range ending at `len - 1` and
+    /// another starting at 0.
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        index < self.len && self.ranges.is_free(index)
+    }
+
+    /// Marks a specific index as free. Returns false if it was already
+    /// free or lies outside the ring
+    #[inline]
+    pub fn set_free(&mut self, index: usize) -> bool {
+        index < self.len && self.ranges.set_free(index)
+    }
+
+    /// Marks a range that does not cross the seam as free. Returns false if
+    /// it was already free or extends outside the ring
+    #[inline]
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        range.max < self.len && self.ranges.set_range_free(range)
+    }
+
+    /// Marks a free index as used. Returns false if the index was not free
+    #[inline]
+    pub fn set_used(&mut self, index: usize) -> bool {
+        index < self.len && self.ranges.set_used(index)
+    }
+
+    /// Returns the free region containing `index`. When the region wraps
+    /// past the end of the ring the first range is the part running up to
+    /// `len - 1` and the second is the part continuing from 0.
+    pub fn free_run_at(&self, index: usize) -> Option<(Range, Option<Range>)> {
+        if !self.is_free(index) {
+            return None;
+        }
+
+        let run = *self.ranges.free_list.get(&Range::id(index))?;
+        match self.seam() {
+            Some((tail, head)) if run == tail || run == head => Some((tail, Some(head))),
+            _ => Some((run, None)),
+        }
+    }
+
+    /// Iterator over the contiguous free regions of the ring. A region
+    /// which wraps around is joined into one item holding its part up to
+    /// `len - 1` and its part from 0, and is ordered by where it starts.
+    pub fn free_ranges(&self) -> impl Iterator<Item = (Range, Option<Range>)> + '_ {
+        let seam = self.seam();
+        self.ranges
+            .free_list
+            .iter()
+            .filter_map(move |&range| match seam {
+                Some((_, head)) if range == head => None,
+                Some((tail, head)) if range == tail => Some((tail, Some(head))),
+                _ => Some((range, None)),
+            })
+    }
+
+    /// Marks `len` indices starting at `start` as free, continuing from 0
+    /// if they run past the end of the ring. Returns false if they were all
+    /// already free or `start`/`len` do not fit in the ring
+    pub fn set_wrapping_range_free(&mut self, start: usize, len: usize) -> bool {
+        let (first, second) = match self.wrapping_parts(start, len) {
+            Some(parts) => parts,
+            None => return false,
+        };
+
+        let freed_first = self.ranges.set_range_free(first);
+        let freed_second = second.is_some_and(|second| self.ranges.set_range_free(second));
+        freed_first || freed_second
+    }
+
+    /// Marks `len` indices starting at `start` as used, continuing from 0 if
+    /// they run past the end of the ring. Nothing is changed and false is
+    /// returned unless every one of them was free
+    pub fn set_wrapping_range_used(&mut self, start: usize, len: usize) -> bool {
+        let (first, second) = match self.wrapping_parts(start, len) {
+            Some(parts) => parts,
+            None => return false,
+        };
+
+        let first_outer = match self.free_range_covering(first) {
+            Some(outer) => outer,
+            None => return false,
+        };
+        if second.is_some_and(|second| self.free_range_covering(second).is_none()) {
+            return false;
+        }
+
+        self.ranges.cut_out(first_outer, first);
+        // Both parts can lie in the same stored range, so look up what
+        // covers the second part again once the first is cut from it
+        if let Some(second) = second {
+            let second_outer = self
+                .free_range_covering(second)
+                .expect("the second part was free before the first was cut");
+            self.ranges.cut_out(second_outer, second);
+        }
+        true
+    }
+
+    /// Marks `size` contiguous indices as used and returns them, taking the
+    /// free region with the lowest starting index that fits. A region which
+    /// wraps around starts at its part near the end of the ring, so the
+    /// allocation is returned as two ranges if it had to cross the seam.
+    pub fn allocate(&mut self, size: usize) -> Option<(Range, Option<Range>)> {
+        if size == 0 || size > self.len {
+            return None;
+        }
+
+        let (first, second) = self
+            .free_ranges()
+            .find(|&(first, second)| run_len(first) + second.map_or(0, run_len) >= size)?;
+        if run_len(first) >= size {
+            return Some((self.carve_front(first, size), None));
+        }
+
+        let second = second?;
+        let rest = size - run_len(first);
+        self.ranges.free_list.remove(&first);
+        Some((first, Some(self.carve_front(second, rest))))
+    }
+
+    /// The pair of free ranges that touch across the seam, as (tail, head)
+    fn seam(&self) -> Option<(Range, Range)> {
+        let head = *self.ranges.free_list.iter().next()?;
+        let tail = *self.ranges.free_list.iter().next_back()?;
+        if head.min == 0 && tail.max == self.len - 1 && head != tail {
+            Some((tail, head))
+        } else {
+            None
+        }
+    }
+
+    /// Splits `len` indices from `start` into the part before the seam and
+    /// the part after it
+    fn wrapping_parts(&self, start: usize, len: usize) -> Option<(Range, Option<Range>)> {
+        if len == 0 || len > self.len || start >= self.len {
+            return None;
+        }
+
+        let until_seam = self.len - start;
+        if len <= until_seam {
+            return Some((Range::from_len(start, len)?, None));
+        }
+        Some((
+            Range {
+                min: start,
+                max: self.len - 1,
+            },
+            Some(Range::from_len(0, len - until_seam)?),
+        ))
+    }
+
+    /// The stored free range that `range` lies entirely within, if any
+    fn free_range_covering(&self, range: Range) -> Option<Range> {
+        self.ranges
+            .free_list
+            .get(&Range::id(range.min))
+            .cloned()
+            .filter(|outer| outer.max >= range.max)
+    }
+
+    fn carve_front(&mut self, range: Range, size: usize) -> Range {
+        let carved = Range {
+            min: range.min,
+            max: range.min + size - 1,
+        };
+        self.ranges.cut_out(range, carved);
+        carved
+    }
+}
+
+#[inline]
+fn run_len(range: Range) -> usize {
+    range.max - range.min + 1
+}
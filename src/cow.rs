@@ -0,0 +1,67 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use super::{FreeRanges, FreeRangesView};
+
+/// A FreeRanges behind an `Arc` which is only copied when it's mutated
+/// while shared.
+///
+/// Cloning it and taking snapshots are O(1), since they share the same
+/// storage. It derefs to a FreeRanges, and any call which takes it mutably
+/// first makes a private copy if a clone or snapshot still shares it, so
+/// those never see the change.
+#[derive(Debug, Clone, Default)]
+pub struct CowFreeRanges {
+    ranges: Arc<FreeRanges>,
+}
+
+impl CowFreeRanges {
+    #[inline]
+    pub fn new(ranges: FreeRanges) -> Self {
+        CowFreeRanges {
+            ranges: Arc::new(ranges),
+        }
+    }
+
+    /// A read-only view sharing the current storage, see `FreeRangesView`
+    #[inline]
+    pub fn snapshot(&self) -> FreeRangesView {
+        FreeRangesView::from_shared(self.ranges.clone())
+    }
+
+    /// Whether a clone or snapshot shares the storage, so the next mutation
+    /// will copy it
+    #[inline]
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.ranges) > 1
+    }
+
+    /// Unwraps the FreeRanges, copying it if the storage is shared
+    #[inline]
+    pub fn into_inner(self) -> FreeRanges {
+        Arc::try_unwrap(self.ranges).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+impl From<FreeRanges> for CowFreeRanges {
+    #[inline]
+    fn from(ranges: FreeRanges) -> Self {
+        CowFreeRanges::new(ranges)
+    }
+}
+
+impl Deref for CowFreeRanges {
+    type Target = FreeRanges;
+
+    #[inline]
+    fn deref(&self) -> &FreeRanges {
+        &self.ranges
+    }
+}
+
+impl DerefMut for CowFreeRanges {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut FreeRanges {
+        Arc::make_mut(&mut self.ranges)
+    }
+}
@@ -0,0 +1,174 @@
+use std::net::Ipv4Addr;
+
+use defmt::{write, Format, Formatter};
+
+use super::{
+    AllocError, BuildError, DiffConflict, FreeRanges, HistoryError, InvariantViolation,
+    Ipv4PoolError, NotOwned, OverflowError, ParseRangeError, Range, StrictFreeError,
+};
+
+/// How many ranges a FreeRanges prints before eliding the rest
+const MAX_LOGGED_RANGES: usize = 4;
+
+/// Printed as `min..=max`
+impl Format for Range {
+    fn format(&self, f: Formatter) {
+        write!(f, "{=usize}..={=usize}", self.min, self.max)
+    }
+}
+
+/// Printed as the range count followed by at most the first four ranges, so
+/// a log statement never walks the whole set
+impl Format for FreeRanges {
+    fn format(&self, f: Formatter) {
+        write!(f, "FreeRanges({=usize}) [", self.free_list.len());
+        for (i, range) in self.free_list.iter().take(MAX_LOGGED_RANGES).enumerate() {
+            if i > 0 {
+                write!(f, ", ");
+            }
+            write!(f, "{}", range);
+        }
+        if self.free_list.len() > MAX_LOGGED_RANGES {
+            write!(f, ", ..");
+        }
+        write!(f, "]")
+    }
+}
+
+fn format_addr(f: Formatter, address: Ipv4Addr) {
+    let [a, b, c, d] = address.octets();
+    write!(f, "{=u8}.{=u8}.{=u8}.{=u8}", a, b, c, d)
+}
+
+impl Format for StrictFreeError {
+    fn format(&self, f: Formatter) {
+        match *self {
+            StrictFreeError::EmptyRange(range) => write!(f, "range {} is empty", range),
+            StrictFreeError::AlreadyFree(range) => {
+                write!(f, "indices {} were already free", range)
+            }
+        }
+    }
+}
+
+impl Format for OverflowError {
+    fn format(&self, f: Formatter) {
+        write!(f, "scaling indices {} overflows usize", self.range)
+    }
+}
+
+impl Format for AllocError {
+    fn format(&self, f: Formatter) {
+        write!(
+            f,
+            "no free space for a block the size of {}",
+            self.preferred
+        )
+    }
+}
+
+impl Format for InvariantViolation {
+    fn format(&self, f: Formatter) {
+        match *self {
+            InvariantViolation::EmptyRange(r) => write!(f, "range {} is empty", r),
+            InvariantViolation::Overlapping(a, b) => write!(f, "ranges {} and {} overlap", a, b),
+            InvariantViolation::Adjacent(a, b) => {
+                write!(f, "ranges {} and {} are adjacent", a, b)
+            }
+        }
+    }
+}
+
+impl Format for ParseRangeError {
+    fn format(&self, f: Formatter) {
+        match *self {
+            ParseRangeError::Malformed => write!(f, "expected `N`, `N..=M` or `N-M`"),
+            ParseRangeError::Inverted { min, max } => {
+                write!(
+                    f,
+                    "range {=usize}..={=usize} ends before it starts",
+                    min, max
+                )
+            }
+        }
+    }
+}
+
+impl Format for BuildError {
+    fn format(&self, f: Formatter) {
+        match *self {
+            BuildError::MissingDomain => write!(f, "domain() was never called"),
+            BuildError::EmptyDomain(domain) => write!(f, "domain({}) is empty", domain),
+            BuildError::ReserveOutsideDomain {
+                call,
+                range,
+                domain,
+            } => write!(
+                f,
+                "reserve #{=usize} ({}) is not inside the domain {}",
+                call, range, domain
+            ),
+            BuildError::EmptyFreeExtra { call, range } => {
+                write!(f, "free_extra #{=usize} ({}) is empty", call, range)
+            }
+        }
+    }
+}
+
+impl Format for DiffConflict {
+    fn format(&self, f: Formatter) {
+        match *self {
+            DiffConflict::AlreadyFree(range) => {
+                write!(f, "indices {} to be freed were already free", range)
+            }
+            DiffConflict::AlreadyUsed(range) => {
+                write!(f, "indices {} to be used were already used", range)
+            }
+        }
+    }
+}
+
+impl Format for HistoryError {
+    fn format(&self, f: Formatter) {
+        match *self {
+            HistoryError::NothingToUndo => write!(f, "there is nothing to undo"),
+            HistoryError::NothingToRedo => write!(f, "there is nothing to redo"),
+        }
+    }
+}
+
+impl Format for Ipv4PoolError {
+    fn format(&self, f: Formatter) {
+        match *self {
+            Ipv4PoolError::InvalidPrefix(len) => write!(f, "/{=u8} is not a valid prefix", len),
+            Ipv4PoolError::EmptyRange(start, end) => {
+                format_addr(f, start);
+                write!(f, "..=");
+                format_addr(f, end);
+                write!(f, " ends before it starts")
+            }
+            Ipv4PoolError::OutsidePool(address) => {
+                format_addr(f, address);
+                write!(f, " is not in the pool")
+            }
+            Ipv4PoolError::NetworkOrBroadcast(address) => {
+                format_addr(f, address);
+                write!(f, " is the network or broadcast address")
+            }
+            Ipv4PoolError::AlreadyAvailable(address) => {
+                format_addr(f, address);
+                write!(f, " is already available")
+            }
+        }
+    }
+}
+
+impl Format for NotOwned {
+    fn format(&self, f: Formatter) {
+        write!(
+            f,
+            "indices {} are not the start or end of an allocation",
+            self.range
+        )
+    }
+}
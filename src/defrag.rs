@@ -0,0 +1,122 @@
+use std::cmp;
+
+use super::{FreeRanges, Range};
+
+/// Relocation of a used extent from one place to another
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub from: Range,
+    pub to: Range,
+}
+
+impl FreeRanges {
+    /// Proposes moves of the `movable` used extents which would leave a
+    /// contiguous free run of at least `target_len` indices. Extents in
+    /// `movable` must be disjoint; any which are not entirely used are
+    /// ignored. The plan is chosen greedily: it clears the window needing
+    /// the fewest moved indices, sending each extent in it to the first
+    /// free range outside the window that fits. Returns None if no such
+    /// plan exists, and an empty plan if the run is already free.
+    pub fn plan_defragmentation(&self, movable: &[Range], target_len: usize) -> Option<Vec<Move>> {
+        let span = match target_len.checked_sub(1) {
+            Some(span) => span,
+            None => return Some(Vec::new()),
+        };
+        if self.free_list.iter().any(|r| r.max - r.min >= span) {
+            return Some(Vec::new());
+        }
+
+        let mut movable: Vec<Range> = movable
+            .iter()
+            .cloned()
+            .filter(|&extent| !self.free_list.contains(&extent))
+            .collect();
+        movable.sort_by_key(|extent| extent.min);
+
+        // Any window worth clearing starts where a free range or a movable
+        // extent starts
+        let mut candidates: Vec<(usize, Range)> = self
+            .free_list
+            .iter()
+            .chain(movable.iter())
+            .filter_map(|r| Range::from_len(r.min, target_len))
+            .filter_map(|window| Some((self.clearing_cost(window, &movable)?, window)))
+            .collect();
+        candidates.sort_by_key(|&(cost, window)| (cost, window.min));
+
+        candidates
+            .into_iter()
+            .filter_map(|(_, window)| self.plan_clearing(window, &movable))
+            .next()
+    }
+
+    /// Carries out `moves` in order, freeing each source and using each
+    /// destination. Every source must be entirely used and every destination
+    /// entirely free (after the earlier moves) and of the same length.
+    /// Returns false and leaves the set untouched if any move is invalid.
+    pub fn apply_moves(&mut self, moves: &[Move]) -> bool {
+        for (applied, &m) in moves.iter().enumerate() {
+            let valid = m.from.max - m.from.min == m.to.max - m.to.min
+                && (m.from.max < m.to.min || m.to.max < m.from.min)
+                && !self.free_list.contains(&m.from)
+                && self.is_range_free(m.to);
+            if !valid {
+                for &undo in moves[..applied].iter().rev() {
+                    self.set_range_used(undo.from);
+                    self.set_range_free(undo.to);
+                }
+                return false;
+            }
+
+            self.set_range_used(m.to);
+            self.set_range_free(m.from);
+        }
+        true
+    }
+
+    /// How many indices of movable extents would have to move to clear
+    /// `window`, or None if it holds used indices which cannot move
+    fn clearing_cost(&self, window: Range, movable: &[Range]) -> Option<usize> {
+        let report = self.window_report(window);
+        let mut covered = 0;
+        let mut cost = 0;
+        for extent in movable
+            .iter()
+            .filter(|e| e.min <= window.max && e.max >= window.min)
+        {
+            covered += cmp::min(extent.max, window.max) - cmp::max(extent.min, window.min) + 1;
+            cost += (extent.max - extent.min).saturating_add(1);
+        }
+
+        if covered == report.used {
+            Some(cost)
+        } else {
+            None
+        }
+    }
+
+    fn plan_clearing(&self, window: Range, movable: &[Range]) -> Option<Vec<Move>> {
+        let mut scratch = self.clone();
+        scratch.set_range_used(window);
+
+        let mut extents: Vec<Range> = movable
+            .iter()
+            .cloned()
+            .filter(|e| e.min <= window.max && e.max >= window.min)
+            .collect();
+        extents.sort_by_key(|e| cmp::Reverse(e.max - e.min));
+
+        let mut moves = Vec::with_capacity(extents.len());
+        for from in extents {
+            let span = from.max - from.min;
+            let &outer = scratch.free_list.iter().find(|r| r.max - r.min >= span)?;
+            let to = Range {
+                min: outer.min,
+                max: outer.min + span,
+            };
+            scratch.cut_out(outer, to);
+            moves.push(Move { from, to });
+        }
+        Some(moves)
+    }
+}
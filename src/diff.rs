@@ -0,0 +1,111 @@
+use std::fmt;
+
+use super::{FreeRanges, Range};
+
+/// The changes which turn one FreeRanges into another, as produced by
+/// `FreeRanges::diff`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FreeRangesDiff {
+    /// Ranges which go from used to free, lowest first
+    pub freed: Vec<Range>,
+    /// Ranges which go from free to used, lowest first
+    pub used: Vec<Range>,
+}
+
+impl FreeRangesDiff {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.freed.is_empty() && self.used.is_empty()
+    }
+}
+
+/// Returned by `FreeRanges::apply_diff` when the diff doesn't match the
+/// current state
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiffConflict {
+    /// Part of a range the diff frees is already free
+    AlreadyFree(Range),
+    /// Part of a range the diff uses is already used
+    AlreadyUsed(Range),
+}
+
+impl fmt::Display for DiffConflict {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DiffConflict::AlreadyFree(range) => {
+                write!(fmt, "indices {:?} to be freed were already free", range)
+            }
+            DiffConflict::AlreadyUsed(range) => {
+                write!(fmt, "indices {:?} to be used were already used", range)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiffConflict {}
+
+impl FreeRanges {
+    /// The changes which turn `self` into `target`
+    pub fn diff(&self, target: &FreeRanges) -> FreeRangesDiff {
+        FreeRangesDiff {
+            freed: target
+                .free_list
+                .iter()
+                .flat_map(|&range| self.used_runs_within(range))
+                .collect(),
+            used: self
+                .free_list
+                .iter()
+                .flat_map(|&range| target.used_runs_within(range))
+                .collect(),
+        }
+    }
+
+    /// Applies `diff` if every range it frees is currently used and every
+    /// range it uses is currently free. Otherwise nothing changes and the
+    /// first conflict is returned, checking the freed ranges before the
+    /// used ones
+    pub fn apply_diff(&mut self, diff: &FreeRangesDiff) -> Result<(), DiffConflict> {
+        for &range in &diff.freed {
+            if let Some(&free) = self.ranges_between(range.min, range.max).next() {
+                return Err(DiffConflict::AlreadyFree(clip(free, range)));
+            }
+        }
+        for &range in &diff.used {
+            if let Some(&used) = self.used_runs_within(range).first() {
+                return Err(DiffConflict::AlreadyUsed(used));
+            }
+        }
+
+        self.apply_diff_lossy(diff);
+        Ok(())
+    }
+
+    /// Applies whatever parts of `diff` match the current state, returning
+    /// the parts which were skipped: freed ranges which were already free
+    /// and used ranges which were already used
+    pub fn apply_diff_lossy(&mut self, diff: &FreeRangesDiff) -> FreeRangesDiff {
+        let mut skipped = FreeRangesDiff::default();
+        for &range in &diff.freed {
+            skipped.freed.extend(
+                self.ranges_between(range.min, range.max)
+                    .map(|&free| clip(free, range)),
+            );
+            self.set_range_free(range);
+        }
+        for &range in &diff.used {
+            skipped.used.extend(self.used_runs_within(range));
+            self.set_range_used(range);
+        }
+        skipped
+    }
+}
+
+/// The part of `range` inside `window`
+#[inline]
+fn clip(range: Range, window: Range) -> Range {
+    Range {
+        min: range.min.max(window.min),
+        max: range.max.min(window.max),
+    }
+}
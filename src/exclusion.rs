@@ -0,0 +1,161 @@
+use std::collections::btree_set;
+use std::collections::BTreeSet;
+
+use super::{FreeRanges, Range};
+
+impl FreeRanges {
+    /// Hides `range` from allocation without marking it as used. Frees and
+    /// queries like `is_free` still see the indices in it as they are, but
+    /// `set_first_used`, `set_last_used` and the `allocate` methods skip
+    /// them until they are unexcluded.
+    ///
+    /// Allocation steps around the excluded ranges as it searches, so a
+    /// mask costs a lookup per free range visited rather than a copy of
+    /// the set.
+    pub fn exclude(&mut self, range: Range) {
+        let probe = range.saturating_grow(1);
+        let mut combined = range;
+        while let Some(&neighbor) = self.excluded.get(&probe) {
+            self.excluded.remove(&neighbor);
+            combined = combined.merge(neighbor);
+        }
+        self.excluded.insert(combined);
+    }
+
+    /// Makes the indices in `range` available to allocation again
+    pub fn unexclude(&mut self, range: Range) {
+        while let Some(&overlap) = self.excluded.get(&range) {
+            self.excluded.remove(&overlap);
+            if overlap.min < range.min {
+                self.excluded.insert(Range {
+                    min: overlap.min,
+                    max: range.min - 1,
+                });
+            }
+            if overlap.max > range.max {
+                self.excluded.insert(Range {
+                    min: range.max + 1,
+                    max: overlap.max,
+                });
+            }
+        }
+    }
+
+    /// Iterator over the excluded ranges, lowest first
+    #[inline]
+    pub fn excluded_ranges(&self) -> btree_set::Iter<'_, Range> {
+        self.excluded.iter()
+    }
+
+    /// Iterator over the free ranges with the excluded parts cut out, which
+    /// is what allocation gets to pick from
+    #[inline]
+    pub fn effective_free_ranges(&self) -> impl Iterator<Item = Range> + '_ {
+        self.allocatable(self.free_list.iter())
+    }
+
+    /// The parts of the stored `ranges` outside of the excluded ranges.
+    /// Every allocation searches through this, so nothing is placed in
+    /// excluded space. Each piece lies inside one stored range; `take`
+    /// marks a block carved from one as used
+    pub(crate) fn allocatable<'a, I>(
+        &'a self,
+        ranges: I,
+    ) -> impl DoubleEndedIterator<Item = Range> + Clone + 'a
+    where
+        I: IntoIterator<Item = &'a Range>,
+        I::IntoIter: DoubleEndedIterator + Clone + 'a,
+    {
+        ranges
+            .into_iter()
+            .flat_map(move |&range| Pieces::new(range, &self.excluded))
+    }
+
+    /// Whether every index in `range` is free and none of them excluded
+    #[inline]
+    pub(crate) fn is_range_allocatable(&self, range: Range) -> bool {
+        self.is_range_free(range) && !self.excluded.contains(&range)
+    }
+
+    /// Marks `carved` as used, which must lie inside one stored range
+    pub(crate) fn take(&mut self, carved: Range) {
+        let &range = self
+            .free_list
+            .get(&carved)
+            .expect("allocated from a range that isn't free");
+        self.cut_out(range, carved);
+    }
+}
+
+/// The parts of a range not covered by any of a set of disjoint holes, in
+/// either direction
+#[derive(Clone)]
+pub(crate) struct Pieces<'a> {
+    /// What's left of the range between the holes taken from either end
+    rest: Option<(usize, usize)>,
+    holes: btree_set::Range<'a, Range>,
+}
+
+impl<'a> Pieces<'a> {
+    #[inline]
+    pub fn new(range: Range, holes: &'a BTreeSet<Range>) -> Self {
+        Pieces {
+            rest: Some((range.min, range.max)),
+            holes: holes.range(Range::id(range.min)..=Range::id(range.max)),
+        }
+    }
+}
+
+impl<'a> Iterator for Pieces<'a> {
+    type Item = Range;
+
+    fn next(&mut self) -> Option<Range> {
+        loop {
+            let (min, max) = self.rest?;
+            let hole = match self.holes.next() {
+                Some(hole) => hole,
+                None => {
+                    self.rest = None;
+                    return Some(Range { min, max });
+                }
+            };
+            self.rest = hole
+                .max
+                .checked_add(1)
+                .filter(|&after| after <= max)
+                .map(|after| (after, max));
+            if hole.min > min {
+                return Some(Range {
+                    min,
+                    max: hole.min - 1,
+                });
+            }
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for Pieces<'a> {
+    fn next_back(&mut self) -> Option<Range> {
+        loop {
+            let (min, max) = self.rest?;
+            let hole = match self.holes.next_back() {
+                Some(hole) => hole,
+                None => {
+                    self.rest = None;
+                    return Some(Range { min, max });
+                }
+            };
+            self.rest = hole
+                .min
+                .checked_sub(1)
+                .filter(|&before| before >= min)
+                .map(|before| (min, before));
+            if hole.max < max {
+                return Some(Range {
+                    min: hole.max + 1,
+                    max,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! C bindings, enabled by the `ffi` feature. A header can be generated
+//! with `cbindgen --config cbindgen.toml --crate free-ranges`, and a static
+//! library built with `cargo rustc --release --features ffi --crate-type staticlib`.
+//!
+//! Every function taking a `free_ranges_t` pointer accepts null and returns
+//! `FREE_RANGES_NULL_POINTER` for it; any other pointer must have come from
+//! `free_ranges_new` or `free_ranges_new_all_free` and not yet been passed
+//! to `free_ranges_destroy`. Output pointers may be null when the caller
+//! does not need the value, except where noted. Panics never cross the
+//! boundary: they are reported as `FREE_RANGES_PANIC`, after which the
+//! handle should only be destroyed.
+
+#![allow(non_camel_case_types, clippy::missing_safety_doc)]
+
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use super::{FreeRanges, Range, ScanToken};
+
+/// Opaque handle to a FreeRanges
+pub struct free_ranges_t {
+    inner: FreeRanges,
+}
+
+/// An inclusive range of free indices
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct free_ranges_range_t {
+    pub min: usize,
+    pub max: usize,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum free_ranges_status_t {
+    /// The call succeeded
+    FREE_RANGES_OK = 0,
+    /// Iteration filled the buffer and more ranges remain
+    FREE_RANGES_MORE = 1,
+    /// There was no free index to return
+    FREE_RANGES_NOT_FOUND = 2,
+    /// A required pointer was null
+    FREE_RANGES_NULL_POINTER = 3,
+    /// An argument was out of range, such as a range with min > max
+    FREE_RANGES_INVALID_ARGUMENT = 4,
+    /// The call panicked
+    FREE_RANGES_PANIC = 5,
+}
+
+use self::free_ranges_status_t::*;
+
+unsafe fn with_handle<F>(handle: *mut free_ranges_t, f: F) -> free_ranges_status_t
+where
+    F: FnOnce(&mut FreeRanges) -> free_ranges_status_t,
+{
+    if handle.is_null() {
+        return FREE_RANGES_NULL_POINTER;
+    }
+    let ranges = &mut (*handle).inner;
+    panic::catch_unwind(AssertUnwindSafe(|| f(ranges))).unwrap_or(FREE_RANGES_PANIC)
+}
+
+/// Like `with_handle`, for the entry points which only read the set and
+/// so take a const handle
+unsafe fn with_handle_ref<F>(handle: *const free_ranges_t, f: F) -> free_ranges_status_t
+where
+    F: FnOnce(&FreeRanges) -> free_ranges_status_t,
+{
+    if handle.is_null() {
+        return FREE_RANGES_NULL_POINTER;
+    }
+    let ranges = &(*handle).inner;
+    panic::catch_unwind(AssertUnwindSafe(|| f(ranges))).unwrap_or(FREE_RANGES_PANIC)
+}
+
+unsafe fn write<T>(out: *mut T, value: T) {
+    if !out.is_null() {
+        *out = value;
+    }
+}
+
+fn new_handle(inner: fn() -> FreeRanges) -> *mut free_ranges_t {
+    panic::catch_unwind(|| Box::into_raw(Box::new(free_ranges_t { inner: inner() })))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Creates a handle with no indices free. Returns null on failure
+#[no_mangle]
+pub extern "C" fn free_ranges_new() -> *mut free_ranges_t {
+    new_handle(FreeRanges::new)
+}
+
+/// Creates a handle with every index free. Returns null on failure
+#[no_mangle]
+pub extern "C" fn free_ranges_new_all_free() -> *mut free_ranges_t {
+    new_handle(FreeRanges::with_all_free)
+}
+
+/// Destroys a handle. Null is ignored
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_destroy(handle: *mut free_ranges_t) {
+    if !handle.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(handle))));
+    }
+}
+
+/// Marks `index` as free. `out_changed` receives false if it already was
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_set_free(
+    handle: *mut free_ranges_t,
+    index: usize,
+    out_changed: *mut bool,
+) -> free_ranges_status_t {
+    with_handle(handle, |ranges| {
+        write(out_changed, ranges.set_free(index));
+        FREE_RANGES_OK
+    })
+}
+
+/// Marks `index` as used. `out_changed` receives false if it was not free
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_set_used(
+    handle: *mut free_ranges_t,
+    index: usize,
+    out_changed: *mut bool,
+) -> free_ranges_status_t {
+    with_handle(handle, |ranges| {
+        write(out_changed, ranges.set_used(index));
+        FREE_RANGES_OK
+    })
+}
+
+/// Marks `min...max` as free. `out_changed` receives false if it all
+/// already was
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_set_range_free(
+    handle: *mut free_ranges_t,
+    min: usize,
+    max: usize,
+    out_changed: *mut bool,
+) -> free_ranges_status_t {
+    with_handle(handle, |ranges| {
+        if min > max {
+            return FREE_RANGES_INVALID_ARGUMENT;
+        }
+        write(out_changed, ranges.set_range_free(Range { min, max }));
+        FREE_RANGES_OK
+    })
+}
+
+/// Writes whether `index` is free to `out_free`, which must not be null
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_is_free(
+    handle: *const free_ranges_t,
+    index: usize,
+    out_free: *mut bool,
+) -> free_ranges_status_t {
+    if out_free.is_null() {
+        return FREE_RANGES_NULL_POINTER;
+    }
+    with_handle_ref(handle, |ranges| {
+        *out_free = ranges.is_free(index);
+        FREE_RANGES_OK
+    })
+}
+
+unsafe fn write_index(out_index: *mut usize, index: Option<usize>) -> free_ranges_status_t {
+    match index {
+        Some(index) => {
+            write(out_index, index);
+            FREE_RANGES_OK
+        }
+        None => FREE_RANGES_NOT_FOUND,
+    }
+}
+
+/// Writes the lowest free index to `out_index`
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_first(
+    handle: *const free_ranges_t,
+    out_index: *mut usize,
+) -> free_ranges_status_t {
+    with_handle_ref(handle, |ranges| write_index(out_index, ranges.first()))
+}
+
+/// Writes the highest free index to `out_index`
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_last(
+    handle: *const free_ranges_t,
+    out_index: *mut usize,
+) -> free_ranges_status_t {
+    with_handle_ref(handle, |ranges| write_index(out_index, ranges.last()))
+}
+
+/// Marks the lowest free index as used and writes it to `out_index`
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_set_first_used(
+    handle: *mut free_ranges_t,
+    out_index: *mut usize,
+) -> free_ranges_status_t {
+    with_handle(handle, |ranges| {
+        write_index(out_index, ranges.set_first_used())
+    })
+}
+
+/// Copies up to `capacity` free ranges into `out_ranges`, starting from
+/// `*cursor`, and writes how many were copied to `out_written`. `cursor`
+/// must not be null and should point to 0 for the first call. Returns
+/// `FREE_RANGES_MORE` with `*cursor` advanced if ranges remain, or
+/// `FREE_RANGES_OK` once the last range has been copied.
+#[no_mangle]
+pub unsafe extern "C" fn free_ranges_iterate(
+    handle: *const free_ranges_t,
+    cursor: *mut usize,
+    out_ranges: *mut free_ranges_range_t,
+    capacity: usize,
+    out_written: *mut usize,
+) -> free_ranges_status_t {
+    if cursor.is_null() || (out_ranges.is_null() && capacity > 0) {
+        return FREE_RANGES_NULL_POINTER;
+    }
+    with_handle_ref(handle, |ranges| {
+        let (page, next) = ranges.scan_from(Some(ScanToken::from_raw(*cursor)), capacity);
+        for (i, range) in page.iter().enumerate() {
+            *out_ranges.add(i) = free_ranges_range_t {
+                min: range.min,
+                max: range.max,
+            };
+        }
+        write(out_written, page.len());
+        match next {
+            Some(next) => {
+                *cursor = next.into_raw();
+                FREE_RANGES_MORE
+            }
+            None => FREE_RANGES_OK,
+        }
+    })
+}
@@ -0,0 +1,57 @@
+use std::cell::RefCell;
+use std::mem;
+use std::ops::Deref;
+
+use super::{FreeRanges, Range};
+
+/// A block allocated by `FreeRanges::allocate_guarded` which is freed again
+/// when the guard is dropped, including while unwinding from a panic.
+///
+/// The guard only borrows the RefCell while it allocates and while it
+/// frees, so the set can be used normally in between. Dropping a guard
+/// while the set is mutably borrowed panics, just like `borrow_mut` does.
+#[derive(Debug)]
+pub struct AllocGuard<'a> {
+    ranges: &'a RefCell<FreeRanges>,
+    range: Range,
+}
+
+impl FreeRanges {
+    /// Allocates `len` contiguous indices like `allocate`, returning a guard
+    /// which frees them when dropped. This takes the set in a RefCell so
+    /// that the guard can get back to it later
+    pub fn allocate_guarded(ranges: &RefCell<FreeRanges>, len: usize) -> Option<AllocGuard<'_>> {
+        let range = ranges.borrow_mut().allocate(len)?;
+        Some(AllocGuard { ranges, range })
+    }
+}
+
+impl<'a> AllocGuard<'a> {
+    /// Keeps the block used and returns it, leaving freeing it up to the
+    /// caller
+    #[inline]
+    pub fn leak(self) -> Range {
+        let range = self.range;
+        mem::forget(self);
+        range
+    }
+
+    /// Frees the block now instead of at the end of the scope
+    #[inline]
+    pub fn free(self) {}
+}
+
+impl<'a> Deref for AllocGuard<'a> {
+    type Target = Range;
+
+    #[inline]
+    fn deref(&self) -> &Range {
+        &self.range
+    }
+}
+
+impl<'a> Drop for AllocGuard<'a> {
+    fn drop(&mut self) {
+        self.ranges.borrow_mut().set_range_free(self.range);
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+use super::{FreeRanges, Range};
+
+impl FreeRanges {
+    /// Formats the free ranges in hexadecimal, one per line, see
+    /// `HexRanges`
+    #[inline]
+    pub fn display_hex(&self) -> HexRanges<'_> {
+        self.display_hex_within(Range {
+            min: 0,
+            max: usize::MAX,
+        })
+    }
+
+    /// Formats the free ranges overlapping `window` in hexadecimal, one per
+    /// line and clipped to the window, see `HexRanges`
+    #[inline]
+    pub fn display_hex_within(&self, window: Range) -> HexRanges<'_> {
+        HexRanges {
+            ranges: self,
+            window,
+        }
+    }
+}
+
+/// Prints free ranges like `0x1_0000_0000..=0x1_0000_0fff`, one per line
+/// with no trailing newline. Digits are grouped in fours from the right.
+/// Display and LowerHex use lowercase digits, UpperHex uppercase ones
+#[derive(Copy, Clone)]
+pub struct HexRanges<'a> {
+    ranges: &'a FreeRanges,
+    window: Range,
+}
+
+impl<'a> HexRanges<'a> {
+    fn write(&self, fmt: &mut fmt::Formatter, upper: bool) -> fmt::Result {
+        if self.window.empty() {
+            return Ok(());
+        }
+        let ranges = self.ranges.ranges_between(self.window.min, self.window.max);
+        for (i, range) in ranges.enumerate() {
+            if i > 0 {
+                fmt.write_str("\n")?;
+            }
+            let clipped = Range {
+                min: range.min.max(self.window.min),
+                max: range.max.min(self.window.max),
+            };
+            write_range(fmt, clipped, true, upper)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for HexRanges<'a> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.write(fmt, false)
+    }
+}
+
+impl<'a> fmt::LowerHex for HexRanges<'a> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.write(fmt, false)
+    }
+}
+
+impl<'a> fmt::UpperHex for HexRanges<'a> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.write(fmt, true)
+    }
+}
+
+/// Prints `min..=max` in hexadecimal with the digits grouped in fours, and
+/// a `0x` prefix on both ends with `{:#x}`
+impl fmt::LowerHex for Range {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write_range(fmt, *self, fmt.alternate(), false)
+    }
+}
+
+/// Like the LowerHex impl, but with uppercase digits
+impl fmt::UpperHex for Range {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write_range(fmt, *self, fmt.alternate(), true)
+    }
+}
+
+fn write_range(fmt: &mut fmt::Formatter, range: Range, prefix: bool, upper: bool) -> fmt::Result {
+    write_index(fmt, range.min, prefix, upper)?;
+    fmt.write_str("..=")?;
+    write_index(fmt, range.max, prefix, upper)
+}
+
+fn write_index(fmt: &mut fmt::Formatter, index: usize, prefix: bool, upper: bool) -> fmt::Result {
+    let digits = if upper {
+        format!("{:X}", index)
+    } else {
+        format!("{:x}", index)
+    };
+    if prefix {
+        fmt.write_str("0x")?;
+    }
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 4 == 0 {
+            fmt.write_str("_")?;
+        }
+        write!(fmt, "{}", digit)?;
+    }
+    Ok(())
+}
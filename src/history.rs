@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::{FreeRanges, Range};
+
+/// A FreeRanges which can undo and redo the changes made through it.
+///
+/// History is off until `enable_history` is called. From then on every
+/// call which changes the set is recorded as its own action, holding the
+/// exact ranges it took out of and put into the tree, so undoing it
+/// restores the ranges as they were rather than just the same indices.
+/// `commit_action` groups the calls made since the previous commit into a
+/// single labelled action. Recording a new action after an undo discards
+/// everything which could have been redone.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFreeRanges {
+    ranges: FreeRanges,
+    depth: usize,
+    undo: VecDeque<Action>,
+    redo: Vec<Action>,
+    uncommitted: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Action {
+    label: Option<String>,
+    changes: Vec<Change>,
+}
+
+/// The ranges one call removed from the tree and the ones it inserted
+#[derive(Debug, Clone)]
+struct Change {
+    before: Vec<Range>,
+    after: Vec<Range>,
+}
+
+impl HistoryFreeRanges {
+    /// Wraps `ranges` with history turned off
+    #[inline]
+    pub fn new(ranges: FreeRanges) -> Self {
+        HistoryFreeRanges {
+            ranges,
+            ..Default::default()
+        }
+    }
+
+    /// Keeps up to `depth` actions to undo, dropping the oldest ones past
+    /// that. A depth of 0 turns history off and forgets it
+    pub fn enable_history(&mut self, depth: usize) {
+        self.depth = depth;
+        while self.undo.len() > depth {
+            self.undo.pop_front();
+        }
+        self.uncommitted = self.uncommitted.min(self.undo.len());
+        if depth == 0 {
+            self.redo.clear();
+        }
+    }
+
+    /// The free space
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> FreeRanges {
+        self.ranges
+    }
+
+    /// Whether there is an action to undo
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Whether there is an action to redo
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn set_free(&mut self, index: usize) -> bool {
+        self.record(Range::id(index), |ranges| ranges.set_free(index))
+    }
+
+    pub fn set_used(&mut self, index: usize) -> bool {
+        self.record(Range::id(index), |ranges| ranges.set_used(index))
+    }
+
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        self.record(range, |ranges| ranges.set_range_free(range))
+    }
+
+    pub fn set_range_used(&mut self, range: Range) -> bool {
+        self.record(range, |ranges| ranges.set_range_used(range))
+    }
+
+    /// Allocates like `FreeRanges::allocate`
+    pub fn allocate(&mut self, size: usize) -> Option<Range> {
+        let range = self.ranges.allocate(size)?;
+        if self.depth == 0 {
+            return Some(range);
+        }
+        // Put the block back so the change can be captured around it
+        self.ranges.set_range_free(range);
+        self.set_range_used(range);
+        Some(range)
+    }
+
+    /// Groups the calls made since the previous commit, undo or redo into
+    /// one action labelled `label`. Returns false if there were none
+    pub fn commit_action<S: Into<String>>(&mut self, label: S) -> bool {
+        if self.uncommitted == 0 {
+            return false;
+        }
+        let start = self.undo.len() - self.uncommitted;
+        let changes = self
+            .undo
+            .drain(start..)
+            .flat_map(|action| action.changes)
+            .collect();
+        self.undo.push_back(Action {
+            label: Some(label.into()),
+            changes,
+        });
+        self.uncommitted = 0;
+        true
+    }
+
+    /// Reverts the latest action and returns its label, which is None for
+    /// a single call that was never committed
+    pub fn undo(&mut self) -> Result<Option<String>, HistoryError> {
+        let action = self.undo.pop_back().ok_or(HistoryError::NothingToUndo)?;
+        for change in action.changes.iter().rev() {
+            self.swap(&change.after, &change.before);
+        }
+        self.uncommitted = 0;
+        let label = action.label.clone();
+        self.redo.push(action);
+        Ok(label)
+    }
+
+    /// Reapplies the latest undone action and returns its label
+    pub fn redo(&mut self) -> Result<Option<String>, HistoryError> {
+        let action = self.redo.pop().ok_or(HistoryError::NothingToRedo)?;
+        for change in &action.changes {
+            self.swap(&change.before, &change.after);
+        }
+        self.uncommitted = 0;
+        let label = action.label.clone();
+        self.undo.push_back(action);
+        Ok(label)
+    }
+
+    /// Runs `op`, which only touches stored ranges overlapping or touching
+    /// `range`, and records what it changed as a new action
+    fn record<T, F: FnOnce(&mut FreeRanges) -> T>(&mut self, range: Range, op: F) -> T {
+        if self.depth == 0 {
+            return op(&mut self.ranges);
+        }
+
+        let window = (range.min.saturating_sub(1), range.max.saturating_add(1));
+        let mut before: Vec<Range> = self
+            .ranges
+            .ranges_between(window.0, window.1)
+            .cloned()
+            .collect();
+        let result = op(&mut self.ranges);
+        let mut after: Vec<Range> = self
+            .ranges
+            .ranges_between(window.0, window.1)
+            .cloned()
+            .collect();
+
+        let same = |a: &Range, b: &Range| a.min == b.min && a.max == b.max;
+        let kept: Vec<Range> = before
+            .iter()
+            .filter(|a| after.iter().any(|b| same(a, b)))
+            .cloned()
+            .collect();
+        before.retain(|a| !kept.iter().any(|b| same(a, b)));
+        after.retain(|a| !kept.iter().any(|b| same(a, b)));
+        if before.is_empty() && after.is_empty() {
+            return result;
+        }
+
+        self.redo.clear();
+        self.undo.push_back(Action {
+            label: None,
+            changes: vec![Change { before, after }],
+        });
+        self.uncommitted += 1;
+        if self.undo.len() > self.depth {
+            self.undo.pop_front();
+            self.uncommitted = self.uncommitted.min(self.undo.len());
+        }
+        result
+    }
+
+    /// Takes `remove` out of the tree and puts `insert` in
+    fn swap(&mut self, remove: &[Range], insert: &[Range]) {
+        for range in remove {
+            self.ranges.free_list.remove(range);
+        }
+        self.ranges.free_list.extend(insert.iter().cloned());
+    }
+}
+
+impl From<FreeRanges> for HistoryFreeRanges {
+    #[inline]
+    fn from(ranges: FreeRanges) -> Self {
+        HistoryFreeRanges::new(ranges)
+    }
+}
+
+/// Returned by `HistoryFreeRanges::undo` and `redo` when there is nothing
+/// left to apply
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HistoryError {
+    NothingToUndo,
+    NothingToRedo,
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HistoryError::NothingToUndo => write!(fmt, "there is nothing to undo"),
+            HistoryError::NothingToRedo => write!(fmt, "there is nothing to redo"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
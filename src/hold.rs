@@ -0,0 +1,160 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{FreeRanges, Range};
+
+/// Identifies a hold placed by `HoldingFreeRanges::hold`. Ids are never
+/// reused by the same set.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HoldId(u64);
+
+/// A FreeRanges where ranges can be held for a while before they are either
+/// confirmed as used or given back.
+///
+/// Held indices are neither free nor used: allocation never hands them out
+/// and freeing them is ignored until the hold is confirmed, cancelled, or
+/// swept. Deadlines are plain numbers in whatever clock the caller uses.
+#[derive(Debug, Clone)]
+pub struct HoldingFreeRanges {
+    ranges: FreeRanges,
+    held: BTreeSet<Range>,
+    holds: BTreeMap<HoldId, (Range, u64)>,
+    next_id: u64,
+}
+
+impl HoldingFreeRanges {
+    /// Starts with `ranges` as the free space and nothing held
+    #[inline]
+    pub fn new(ranges: FreeRanges) -> Self {
+        HoldingFreeRanges {
+            ranges,
+            held: BTreeSet::new(),
+            holds: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// The free space, not counting held ranges
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    /// Holds `range` until `deadline`. Every index in it has to be free, so
+    /// holds never overlap each other or used indices; returns None otherwise.
+    pub fn hold(&mut self, range: Range, deadline: u64) -> Option<HoldId> {
+        if range.empty() || !self.ranges.is_range_free(range) {
+            return None;
+        }
+        self.ranges.set_range_used(range);
+        self.held.insert(range);
+
+        let id = HoldId(self.next_id);
+        self.next_id += 1;
+        self.holds.insert(id, (range, deadline));
+        Some(id)
+    }
+
+    /// Turns a hold into a normal allocation. Returns the range, or None if
+    /// the hold has already ended.
+    pub fn confirm(&mut self, id: HoldId) -> Option<Range> {
+        let (range, _) = self.holds.remove(&id)?;
+        self.held.remove(&range);
+        Some(range)
+    }
+
+    /// Gives a held range back to the free space. Returns the range, or
+    /// None if the hold has already ended.
+    pub fn cancel(&mut self, id: HoldId) -> Option<Range> {
+        let (range, _) = self.holds.remove(&id)?;
+        self.held.remove(&range);
+        self.ranges.set_range_free(range);
+        Some(range)
+    }
+
+    /// Cancels every hold whose deadline is at or before `now`. Returns how
+    /// many expired.
+    pub fn sweep(&mut self, now: u64) -> usize {
+        let expired: Vec<HoldId> = self
+            .holds
+            .iter()
+            .filter(|&(_, &(_, deadline))| deadline <= now)
+            .map(|(&id, _)| id)
+            .collect();
+        for &id in &expired {
+            self.cancel(id);
+        }
+        expired.len()
+    }
+
+    /// Iterator over every active hold with its range and deadline, oldest
+    /// hold first
+    #[inline]
+    pub fn held_ranges(&self) -> impl Iterator<Item = (HoldId, Range, u64)> + '_ {
+        self.holds
+            .iter()
+            .map(|(&id, &(range, deadline))| (id, range, deadline))
+    }
+
+    #[inline]
+    pub fn is_held(&self, index: usize) -> bool {
+        self.held.contains(&Range::id(index))
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        self.ranges.is_free(index)
+    }
+
+    /// Frees `index` unless it is held. Returns true if it changed.
+    #[inline]
+    pub fn set_free(&mut self, index: usize) -> bool {
+        !self.is_held(index) && self.ranges.set_free(index)
+    }
+
+    /// Frees every index in `range` that isn't held. Returns true if any of
+    /// them changed
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        let mut changed = false;
+        let mut cursor = range.min;
+        let overlaps = self.held.range(Range::id(range.min)..=Range::id(range.max));
+        for held in overlaps {
+            if held.min > cursor {
+                changed |= self.ranges.set_range_free(Range {
+                    min: cursor,
+                    max: held.min - 1,
+                });
+            }
+            match held.max.checked_add(1) {
+                Some(next) => cursor = next,
+                None => return changed,
+            }
+        }
+        if cursor <= range.max {
+            changed |= self.ranges.set_range_free(Range {
+                min: cursor,
+                max: range.max,
+            });
+        }
+        changed
+    }
+
+    #[inline]
+    pub fn set_used(&mut self, index: usize) -> bool {
+        self.ranges.set_used(index)
+    }
+
+    #[inline]
+    pub fn first(&self) -> Option<usize> {
+        self.ranges.first()
+    }
+
+    #[inline]
+    pub fn set_first_used(&mut self) -> Option<usize> {
+        self.ranges.set_first_used()
+    }
+
+    #[inline]
+    pub fn allocate(&mut self, size: usize) -> Option<Range> {
+        self.ranges.allocate(size)
+    }
+}
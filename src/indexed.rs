@@ -0,0 +1,223 @@
+use std::cmp;
+use std::mem;
+
+use super::{FreeRanges, Range};
+
+const WORD_BITS: usize = 64;
+
+/// A FreeRanges with an optional bitmap of one bit per index over a
+/// bounded domain, so `is_free` and `is_range_free` inside that domain are
+/// answered from the bitmap instead of probing the tree.
+///
+/// Every mutation goes through this type, which keeps both in sync. The
+/// bitmap only mirrors the domain: mutations may touch indices outside it,
+/// and queries there are answered from the ranges as before. Ordered
+/// queries and iteration are served by `ranges`.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedFreeRanges {
+    ranges: FreeRanges,
+    index: Option<BitmapIndex>,
+}
+
+#[derive(Debug, Clone)]
+struct BitmapIndex {
+    domain: Range,
+    words: Vec<u64>,
+}
+
+impl IndexedFreeRanges {
+    /// Wraps `ranges` without building an index
+    #[inline]
+    pub fn new(ranges: FreeRanges) -> Self {
+        IndexedFreeRanges {
+            ranges,
+            index: None,
+        }
+    }
+
+    /// Builds the bitmap for `domain` from the current ranges, replacing
+    /// any previous one. It takes a bit per index, so the domain should be
+    /// bounded to a few million indices. An empty domain disables the index
+    pub fn enable_bitmap_index(&mut self, domain: Range) {
+        if domain.empty() {
+            self.index = None;
+            return;
+        }
+        let len = domain.max - domain.min;
+        let mut index = BitmapIndex {
+            domain,
+            words: vec![0; len / WORD_BITS + 1],
+        };
+        for &range in self.ranges.ranges_between(domain.min, domain.max) {
+            index.update(range, true);
+        }
+        self.index = Some(index);
+    }
+
+    /// Drops the bitmap, leaving only the ranges
+    #[inline]
+    pub fn disable_bitmap_index(&mut self) {
+        self.index = None;
+    }
+
+    /// The domain covered by the bitmap, if there is one
+    #[inline]
+    pub fn bitmap_domain(&self) -> Option<Range> {
+        self.index.as_ref().map(|index| index.domain)
+    }
+
+    /// The bytes of heap memory held by the bitmap, or 0 without one. The
+    /// ranges themselves aren't counted
+    #[inline]
+    pub fn memory_usage(&self) -> usize {
+        self.index
+            .as_ref()
+            .map_or(0, |index| index.words.capacity() * mem::size_of::<u64>())
+    }
+
+    /// The free space
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> FreeRanges {
+        self.ranges
+    }
+
+    /// Checks one index, from the bitmap when it's inside the domain
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        match self.index {
+            Some(ref bitmap) if bitmap.contains(Range::id(index)) => {
+                bitmap.all_set(Range::id(index))
+            }
+            _ => self.ranges.is_free(index),
+        }
+    }
+
+    /// Checks a whole range, scanning the bitmap a word at a time when it's
+    /// inside the domain
+    #[inline]
+    pub fn is_range_free(&self, range: Range) -> bool {
+        match self.index {
+            Some(ref bitmap) if bitmap.contains(range) => bitmap.all_set(range),
+            _ => self.ranges.is_range_free(range),
+        }
+    }
+
+    pub fn set_free(&mut self, index: usize) -> bool {
+        self.update(Range::id(index), true);
+        self.ranges.set_free(index)
+    }
+
+    pub fn set_used(&mut self, index: usize) -> bool {
+        self.update(Range::id(index), false);
+        self.ranges.set_used(index)
+    }
+
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        self.update(range, true);
+        self.ranges.set_range_free(range)
+    }
+
+    pub fn set_range_used(&mut self, range: Range) -> bool {
+        self.update(range, false);
+        self.ranges.set_range_used(range)
+    }
+
+    #[inline]
+    pub fn first(&self) -> Option<usize> {
+        self.ranges.first()
+    }
+
+    /// Marks the first free index as used like `FreeRanges::set_first_used`
+    pub fn set_first_used(&mut self) -> Option<usize> {
+        let index = self.ranges.set_first_used()?;
+        self.update(Range::id(index), false);
+        Some(index)
+    }
+
+    /// Allocates like `FreeRanges::allocate`
+    pub fn allocate(&mut self, size: usize) -> Option<Range> {
+        let range = self.ranges.allocate(size)?;
+        self.update(range, false);
+        Some(range)
+    }
+
+    #[inline]
+    fn update(&mut self, range: Range, free: bool) {
+        if let Some(ref mut bitmap) = self.index {
+            bitmap.update(range, free);
+        }
+    }
+}
+
+impl From<FreeRanges> for IndexedFreeRanges {
+    #[inline]
+    fn from(ranges: FreeRanges) -> Self {
+        IndexedFreeRanges::new(ranges)
+    }
+}
+
+impl BitmapIndex {
+    #[inline]
+    fn contains(&self, range: Range) -> bool {
+        !range.empty() && range.min >= self.domain.min && range.max <= self.domain.max
+    }
+
+    /// The bits of `range` inside the domain, as a word and bit position
+    /// for each end. None if they don't overlap
+    fn bits(&self, range: Range) -> Option<((usize, usize), (usize, usize))> {
+        let min = cmp::max(range.min, self.domain.min);
+        let max = cmp::min(range.max, self.domain.max);
+        if range.empty() || min > max {
+            return None;
+        }
+        let (lo, hi) = (min - self.domain.min, max - self.domain.min);
+        Some((
+            (lo / WORD_BITS, lo % WORD_BITS),
+            (hi / WORD_BITS, hi % WORD_BITS),
+        ))
+    }
+
+    /// Sets or clears the bits of the part of `range` inside the domain
+    fn update(&mut self, range: Range, free: bool) {
+        let ((first, lo), (last, hi)) = match self.bits(range) {
+            Some(bits) => bits,
+            None => return,
+        };
+        for word in first..=last {
+            let from = if word == first { lo } else { 0 };
+            let to = if word == last { hi } else { WORD_BITS - 1 };
+            let mask = mask(from, to);
+            if free {
+                self.words[word] |= mask;
+            } else {
+                self.words[word] &= !mask;
+            }
+        }
+    }
+
+    /// Whether every bit of `range` is set. `range` must be inside the
+    /// domain
+    fn all_set(&self, range: Range) -> bool {
+        let ((first, lo), (last, hi)) = match self.bits(range) {
+            Some(bits) => bits,
+            None => return false,
+        };
+        (first..=last).all(|word| {
+            let from = if word == first { lo } else { 0 };
+            let to = if word == last { hi } else { WORD_BITS - 1 };
+            let mask = mask(from, to);
+            self.words[word] & mask == mask
+        })
+    }
+}
+
+/// The bits `from..=to` of a word
+#[inline]
+fn mask(from: usize, to: usize) -> u64 {
+    (u64::MAX >> (WORD_BITS - 1 - to)) & (u64::MAX << from)
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::ops::RangeInclusive;
+
+use super::{FreeRanges, Range};
+
+/// A pool of IPv4 addresses to lease out, backed by a FreeRanges over the
+/// numeric value of each address, so neighbouring addresses are adjacent
+/// indices and leases come out lowest address first
+#[derive(Debug, Clone)]
+pub struct Ipv4Pool {
+    available: FreeRanges,
+    bounds: Range,
+    /// Whether the network and broadcast addresses are kept out of the pool
+    excludes_ends: bool,
+}
+
+impl Ipv4Pool {
+    /// Makes every address of the subnet `addr/prefix_len` available except
+    /// its network and broadcast addresses. /31 and /32 subnets have no such
+    /// addresses, so all of their addresses are available.
+    pub fn from_cidr(addr: Ipv4Addr, prefix_len: u8) -> Result<Self, Ipv4PoolError> {
+        let mut pool = Ipv4Pool::from_cidr_all(addr, prefix_len)?;
+        if prefix_len < 31 {
+            pool.available.set_used(pool.bounds.min);
+            pool.available.set_used(pool.bounds.max);
+            pool.excludes_ends = true;
+        }
+        Ok(pool)
+    }
+
+    /// Makes every address of the subnet `addr/prefix_len` available,
+    /// including its network and broadcast addresses
+    pub fn from_cidr_all(addr: Ipv4Addr, prefix_len: u8) -> Result<Self, Ipv4PoolError> {
+        if prefix_len > 32 {
+            return Err(Ipv4PoolError::InvalidPrefix(prefix_len));
+        }
+
+        let mask = u32::MAX
+            .checked_shl(32 - u32::from(prefix_len))
+            .unwrap_or(0);
+        let network = u32::from(addr) & mask;
+        Ok(Ipv4Pool::with_bounds(Range {
+            min: network as usize,
+            max: (network | !mask) as usize,
+        }))
+    }
+
+    /// Makes every address from `range.start()` to `range.end()` available
+    pub fn from_range(range: RangeInclusive<Ipv4Addr>) -> Result<Self, Ipv4PoolError> {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return Err(Ipv4PoolError::EmptyRange(start, end));
+        }
+        Ok(Ipv4Pool::with_bounds(Range {
+            min: index(start),
+            max: index(end),
+        }))
+    }
+
+    fn with_bounds(bounds: Range) -> Self {
+        Ipv4Pool {
+            available: FreeRanges::with_initial_range(bounds),
+            bounds,
+            excludes_ends: false,
+        }
+    }
+
+    /// The lowest and highest address the pool covers
+    #[inline]
+    pub fn bounds(&self) -> (Ipv4Addr, Ipv4Addr) {
+        (addr(self.bounds.min), addr(self.bounds.max))
+    }
+
+    /// Takes the lowest available address
+    #[inline]
+    pub fn lease(&mut self) -> Option<Ipv4Addr> {
+        self.available.set_first_used().map(addr)
+    }
+
+    /// Makes a leased or reserved address available again. The network
+    /// and broadcast addresses of a pool built with `from_cidr` can't be
+    /// released, since they were never available
+    pub fn release(&mut self, address: Ipv4Addr) -> Result<(), Ipv4PoolError> {
+        let index = index(address);
+        if !self.bounds.contains(index) {
+            return Err(Ipv4PoolError::OutsidePool(address));
+        }
+        if self.excludes_ends && (index == self.bounds.min || index == self.bounds.max) {
+            return Err(Ipv4PoolError::NetworkOrBroadcast(address));
+        }
+        if !self.available.set_free(index) {
+            return Err(Ipv4PoolError::AlreadyAvailable(address));
+        }
+        Ok(())
+    }
+
+    /// Takes a specific address out of the pool. Returns false if it was
+    /// not available
+    #[inline]
+    pub fn reserve(&mut self, address: Ipv4Addr) -> bool {
+        self.available.set_used(index(address))
+    }
+
+    /// Takes every available address in `range` out of the pool. Returns
+    /// false if none of them were available
+    pub fn reserve_range(&mut self, range: RangeInclusive<Ipv4Addr>) -> bool {
+        let (start, end) = range.into_inner();
+        start <= end
+            && self.available.set_range_used(Range {
+                min: index(start),
+                max: index(end),
+            })
+    }
+
+    #[inline]
+    pub fn is_available(&self, address: Ipv4Addr) -> bool {
+        self.available.is_free(index(address))
+    }
+
+    /// Iterator over the runs of available addresses, as inclusive pairs
+    pub fn available_ranges(&self) -> impl Iterator<Item = (Ipv4Addr, Ipv4Addr)> + '_ {
+        self.available
+            .free_list
+            .iter()
+            .map(|range| (addr(range.min), addr(range.max)))
+    }
+}
+
+#[inline]
+fn index(address: Ipv4Addr) -> usize {
+    u32::from(address) as usize
+}
+
+#[inline]
+fn addr(index: usize) -> Ipv4Addr {
+    Ipv4Addr::from(index as u32)
+}
+
+/// Why an `Ipv4Pool` could not be built or an address released
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Ipv4PoolError {
+    /// A CIDR prefix length above 32
+    InvalidPrefix(u8),
+    /// An address range which ends before it starts
+    EmptyRange(Ipv4Addr, Ipv4Addr),
+    /// An address outside the pool's subnet or range
+    OutsidePool(Ipv4Addr),
+    /// The network or broadcast address of a pool built with `from_cidr`
+    NetworkOrBroadcast(Ipv4Addr),
+    /// An address released while it was still available
+    AlreadyAvailable(Ipv4Addr),
+}
+
+impl fmt::Display for Ipv4PoolError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Ipv4PoolError::InvalidPrefix(len) => write!(fmt, "/{} is not a valid prefix", len),
+            Ipv4PoolError::EmptyRange(start, end) => {
+                write!(fmt, "{}..={} ends before it starts", start, end)
+            }
+            Ipv4PoolError::OutsidePool(address) => write!(fmt, "{} is not in the pool", address),
+            Ipv4PoolError::NetworkOrBroadcast(address) => {
+                write!(fmt, "{} is the network or broadcast address", address)
+            }
+            Ipv4PoolError::AlreadyAvailable(address) => {
+                write!(fmt, "{} is already available", address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Ipv4PoolError {}
@@ -0,0 +1,165 @@
+use std::cmp;
+use std::iter;
+
+use super::{FreeRanges, Range};
+
+/// The number of buffered frees which triggers a flush by default
+const DEFAULT_THRESHOLD: usize = 32;
+
+/// A FreeRanges which holds freed indices in a small buffer and merges
+/// them into the tree in batches, to avoid splitting and re-merging the
+/// same range when allocations and frees keep hitting one boundary.
+///
+/// Up to `threshold` freed indices may sit in the buffer at a time. The
+/// methods here all take the buffer into account, but the inner FreeRanges
+/// only sees those indices once they are flushed, either when the buffer
+/// fills up or when `flush` or `ranges` is called.
+#[derive(Debug, Clone)]
+pub struct LazyFreeRanges {
+    ranges: FreeRanges,
+    pending: Vec<usize>,
+    threshold: usize,
+}
+
+impl FreeRanges {
+    /// Starts an empty set which buffers single-index frees, see
+    /// `LazyFreeRanges`
+    #[inline]
+    pub fn with_lazy_merge() -> LazyFreeRanges {
+        LazyFreeRanges::new(FreeRanges::new(), DEFAULT_THRESHOLD)
+    }
+}
+
+impl LazyFreeRanges {
+    /// Wraps `ranges`, flushing whenever `threshold` frees are buffered.
+    /// A threshold of 0 is treated as 1
+    #[inline]
+    pub fn new(ranges: FreeRanges, threshold: usize) -> Self {
+        LazyFreeRanges {
+            ranges,
+            pending: Vec::new(),
+            threshold: cmp::max(threshold, 1),
+        }
+    }
+
+    /// Merges every buffered free into the tree
+    pub fn flush(&mut self) {
+        self.pending.sort_unstable();
+        let mut run: Option<Range> = None;
+        for &index in &self.pending {
+            match run {
+                Some(ref mut run) if run.max + 1 == index => run.max = index,
+                _ => {
+                    if let Some(run) = run {
+                        self.ranges.set_range_free(run);
+                    }
+                    run = Some(Range::id(index));
+                }
+            }
+        }
+        if let Some(run) = run {
+            self.ranges.set_range_free(run);
+        }
+        self.pending.clear();
+    }
+
+    /// Flushes and returns the up to date free ranges
+    #[inline]
+    pub fn ranges(&mut self) -> &FreeRanges {
+        self.flush();
+        &self.ranges
+    }
+
+    /// Flushes and returns the inner FreeRanges
+    #[inline]
+    pub fn into_inner(mut self) -> FreeRanges {
+        self.flush();
+        self.ranges
+    }
+
+    /// Iterator over the free ranges as a flush would leave them, lowest
+    /// first, without flushing. This sorts a copy of the buffer
+    pub fn free_ranges(&self) -> impl Iterator<Item = Range> + '_ {
+        let mut pending = self.pending.clone();
+        pending.sort_unstable();
+        let mut pending = pending.into_iter().map(Range::id).peekable();
+        let mut stored = self.ranges.free_list.iter().cloned().peekable();
+        let mut lowest = iter::from_fn(move || match (stored.peek(), pending.peek()) {
+            (Some(a), Some(b)) if b.min < a.min => pending.next(),
+            (Some(_), _) => stored.next(),
+            (None, _) => pending.next(),
+        })
+        .peekable();
+
+        let barriers = &self.ranges.barriers;
+        iter::from_fn(move || {
+            let mut run = lowest.next()?;
+            while let Some(next) =
+                lowest.next_if(|next| run.max + 1 == next.min && !barriers.contains(&run.max))
+            {
+                run.max = next.max;
+            }
+            Some(run)
+        })
+    }
+
+    /// The number of frees waiting to be merged
+    #[inline]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        self.ranges.is_free(index) || self.pending.contains(&index)
+    }
+
+    /// Marks a specific index as free, buffering it until the next flush.
+    /// Returns false if it was already free
+    pub fn set_free(&mut self, index: usize) -> bool {
+        if self.is_free(index) {
+            return false;
+        }
+
+        self.pending.push(index);
+        if self.pending.len() >= self.threshold {
+            self.flush();
+        }
+        true
+    }
+
+    /// Marks a range as free. This goes straight to the tree
+    #[inline]
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        self.pending.retain(|&index| !range.contains(index));
+        self.ranges.set_range_free(range)
+    }
+
+    /// Marks a free index as used. Returns false if the index was not free
+    pub fn set_used(&mut self, index: usize) -> bool {
+        match self.pending.iter().position(|&p| p == index) {
+            Some(at) => {
+                self.pending.swap_remove(at);
+                true
+            }
+            None => self.ranges.set_used(index),
+        }
+    }
+
+    /// Returns the first free value if one exists
+    #[inline]
+    pub fn first(&self) -> Option<usize> {
+        let pending = self.pending.iter().cloned().min();
+        match (self.ranges.first(), pending) {
+            (Some(a), Some(b)) => Some(cmp::min(a, b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Marks the first free index as used and returns it
+    pub fn set_first_used(&mut self) -> Option<usize> {
+        let first = self.first()?;
+        self.set_used(first);
+        Some(first)
+    }
+}
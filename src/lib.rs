@@ -1,11 +1,109 @@
+#[cfg(feature = "allocator-api2")]
+extern crate allocator_api2;
+#[cfg(feature = "borsh")]
+extern crate borsh;
+#[cfg(feature = "defmt")]
+extern crate defmt;
+#[cfg(feature = "rand")]
+extern crate rand;
+
 use std::cmp::{self, Ordering};
 use std::collections::btree_set::{self, Iter};
 use std::collections::BTreeSet;
 use std::fmt;
+use std::iter;
+use std::ops::{self, Bound, RangeBounds};
+use std::str::FromStr;
+
+use range_set::RangeSet;
+
+#[doc(hidden)]
+pub use macros::__macro_support;
+
+#[cfg(feature = "allocator-api2")]
+pub use alloc_in::FreeRangesIn;
+pub use allocator::FreeRangesAllocator;
+pub use bounded::BoundedFreeRanges;
+pub use builder::{BuildError, FreeRangesBuilder};
+pub use chars::CharRanges;
+pub use circular::CircularFreeRanges;
+pub use cow::CowFreeRanges;
+pub use defrag::Move;
+pub use diff::{DiffConflict, FreeRangesDiff};
+pub use guard::AllocGuard;
+pub use hex::HexRanges;
+pub use history::{HistoryError, HistoryFreeRanges};
+pub use hold::{HoldId, HoldingFreeRanges};
+pub use indexed::IndexedFreeRanges;
+pub use ipv4::{Ipv4Pool, Ipv4PoolError};
+pub use lazy::LazyFreeRanges;
+pub use owned::{NotOwned, OwnedRanges};
+pub use plan::{AllocRequest, PlanResult};
+pub use range_allocator::RangeAllocator;
+pub use sharded::ShardedFreeRanges;
+pub use signed::SignedFreeRanges;
+pub use slot_pool::SlotPool;
+pub use sub_pool::SubPool;
+pub use tagged::TaggedFreeRanges;
+pub use tracked::TrackedFreeRanges;
+pub use view::FreeRangesView;
 
+#[cfg(feature = "allocator-api2")]
+mod alloc_in;
+mod allocator;
+#[cfg(feature = "borsh")]
+mod borsh_support;
+mod bounded;
+mod builder;
+mod chars;
+mod circular;
+mod cow;
+#[cfg(feature = "defmt")]
+mod defmt_support;
+mod defrag;
+mod diff;
+mod exclusion;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod guard;
+mod hex;
+mod history;
+mod hold;
+mod indexed;
+mod ipv4;
+mod lazy;
+mod macros;
+mod owned;
+mod plan;
+mod range_allocator;
+mod range_set;
+mod set_ops;
+mod sharded;
+mod signed;
+mod slot_pool;
+mod sub_pool;
+mod tagged;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod tracked;
+mod view;
+
+/// Keeps track of which indices are free as a sorted set of contiguous
+/// ranges. A set holding at most one range stores it inline and does not
+/// allocate; the ranges move into a BTreeSet when a second one is added,
+/// and stay there until `clear` or `shrink_to_fit`.
+/// `free_ranges`, `into_iter` and `as_inner` hand out BTreeSet iterators and
+/// the tree itself, which for an inline range means building a one-node
+/// tree on first use. The tree nodes always come from the global allocator,
+/// since only nightly lets a BTreeSet use another one; with the
+/// `allocator-api2` feature, `FreeRanges::new_in` builds a `FreeRangesIn`
+/// whose storage comes from a given allocator instead.
 #[derive(Debug, Clone, Default)]
 pub struct FreeRanges {
-    free_list: BTreeSet<Range>,
+    free_list: RangeSet,
+    barriers: BTreeSet<usize>,
+    excluded: BTreeSet<Range>,
+    tiers: Vec<Range>,
 }
 
 impl FreeRanges {
@@ -20,7 +118,7 @@ impl FreeRanges {
     pub fn with_all_free() -> FreeRanges {
         FreeRanges::with_initial_range(Range {
             min: 0,
-            max: std::usize::MAX,
+            max: usize::MAX,
         })
     }
 
@@ -34,29 +132,77 @@ impl FreeRanges {
 
     /// Iterator over all of the contiguous free ranges
     #[inline]
-    pub fn free_ranges(&self) -> Iter<Range> {
-        self.free_list.iter()
+    pub fn free_ranges(&self) -> Iter<'_, Range> {
+        self.free_list.tree().iter()
     }
 
     /// Iterator over all of the ranges starting at a specific index.
     /// It will include the first range that contains the index if it
     /// exists.
     #[inline]
-    pub fn free_ranges_after(&self, start: usize) -> btree_set::Range<Range> {
-        self.free_list.range(Range::id(start)..)
+    pub fn free_ranges_after(&self, start: usize) -> btree_set::Range<'_, Range> {
+        self.free_list.tree().range(Range::id(start)..)
     }
 
     /// Iterator over all of the ranges ending at a specific index.
     /// It will include the last range that contains the index if it
     /// exists.
     #[inline]
-    pub fn free_ranges_before(&self, end: usize) -> btree_set::Range<Range> {
+    pub fn free_ranges_before(&self, end: usize) -> btree_set::Range<'_, Range> {
         use std::collections::Bound;
         self.free_list
+            .tree()
             .range((Bound::Unbounded, Bound::Included(Range::id(end))))
     }
 
-    /// Marks a specific index as free
+    /// Iterator over all of the ranges overlapping `start...end`, including
+    /// ones which only touch either end. The ranges are not clipped.
+    /// Panics if `start` is greater than `end`.
+    #[inline]
+    pub fn free_ranges_between(&self, start: usize, end: usize) -> btree_set::Range<'_, Range> {
+        self.free_list
+            .tree()
+            .range(Range::id(start)..=Range::id(end))
+    }
+
+    /// `free_ranges_after` for use inside the crate. The public iterators
+    /// borrow a tree, which an inline range has to build, so the internal
+    /// searches go through these instead
+    #[inline]
+    fn ranges_after(&self, start: usize) -> range_set::Iter<'_> {
+        self.free_list.range(Range::id(start)..)
+    }
+
+    /// `free_ranges_before` for use inside the crate
+    #[inline]
+    fn ranges_before(&self, end: usize) -> range_set::Iter<'_> {
+        self.free_list.range(..=Range::id(end))
+    }
+
+    /// `free_ranges_between` for use inside the crate
+    #[inline]
+    fn ranges_between(&self, start: usize, end: usize) -> range_set::Iter<'_> {
+        self.free_list.range(Range::id(start)..=Range::id(end))
+    }
+
+    /// The number of contiguous free ranges
+    #[inline]
+    pub fn range_count(&self) -> usize {
+        self.free_list.len()
+    }
+
+    /// Counts the free ranges which overlap `window`, without walking
+    /// the ranges outside of it
+    #[inline]
+    pub fn count_ranges_in(&self, window: Range) -> usize {
+        self.ranges_after(window.min)
+            .take_while(|r| r.min <= window.max)
+            .count()
+    }
+
+    /// Marks a specific index as free. The set has no upper bound, so any
+    /// index up to and including usize::MAX can be freed, past the highest
+    /// one seen so far or not
     #[inline]
     pub fn set_free(&mut self, index: usize) -> bool {
         if self.free_list.contains(&Range::id(index)) {
@@ -71,16 +217,8 @@ impl FreeRanges {
 
     #[inline]
     pub fn set_range_free(&mut self, range: Range) -> bool {
-        let front_check = self.free_list.get(&Range::id(range.min)).cloned();
-        let back_check = self.free_list.get(&Range::id(range.max)).cloned();
-
-        match (front_check, back_check) {
-            (Some(front_check), Some(back_check)) => {
-                if front_check == back_check {
-                    return false;
-                }
-            }
-            _ => (),
+        if self.is_range_free(range) {
+            return false;
         }
 
         self.do_set_free(range);
@@ -89,39 +227,29 @@ impl FreeRanges {
     }
 
     fn do_set_free(&mut self, range: Range) {
-        let range_front = if range.min > 0 {
-            range.push_front()
-        } else {
-            range
-        };
-        let range_back = range.push_back();
-        let combine_front = self.free_list.get(&range_front).cloned();
-        let combine_back = self.free_list.get(&range_back).cloned();
-
-        match (combine_front, combine_back) {
-            (Some(front_range), Some(back_range)) => {
-                let combined = front_range.merge(range).merge(back_range);
-
-                self.free_list.remove(&front_range);
-                self.free_list.remove(&back_range);
-                self.free_list.insert(combined);
-            }
-            (Some(front_range), None) => {
-                let combined = front_range.merge(range);
+        // Probe one index past each end so that adjacent ranges are merged
+        // along with any ranges the new one overlaps
+        let probe = range.saturating_grow(1);
+        let mut combined = range;
+        while let Some(&neighbor) = self.free_list.get(&probe) {
+            self.free_list.remove(&neighbor);
+            combined = combined.merge(neighbor);
+        }
 
-                self.free_list.remove(&front_range);
-                self.free_list.insert(combined);
-            }
-            (None, Some(back_range)) => {
-                let combined = back_range.merge(range);
+        self.insert_cut(combined);
+    }
 
-                self.free_list.remove(&back_range);
-                self.free_list.insert(combined);
-            }
-            (None, None) => {
-                self.free_list.insert(range);
-            }
+    /// Inserts `range`, cut into pieces at any barriers inside it
+    fn insert_cut(&mut self, range: Range) {
+        let mut min = range.min;
+        for &barrier in self.barriers.range(range.min..range.max) {
+            self.free_list.insert(Range { min, max: barrier });
+            min = barrier + 1;
         }
+        self.free_list.insert(Range {
+            min,
+            max: range.max,
+        });
     }
 
     /// Marks a free index as used. Returns false if the index was not free
@@ -131,12 +259,9 @@ impl FreeRanges {
 
         if let Some(&intersecting) = self.free_list.get(&range) {
             self.free_list.remove(&intersecting);
-            let (left, right) = intersecting.split(index);
-            if !left.empty() {
-                self.free_list.insert(left);
-            }
-            if !right.empty() {
-                self.free_list.insert(right);
+            if let Some((left, right)) = intersecting.split_at(index) {
+                self.free_list.extend(left);
+                self.free_list.extend(right);
             }
             true
         } else {
@@ -153,16 +278,9 @@ impl FreeRanges {
     /// Marks the first index in the free list as used and returns it
     #[inline]
     pub fn set_first_used(&mut self) -> Option<usize> {
-        if let Some(&first) = self.free_list.iter().nth(0) {
-            self.free_list.remove(&first);
-            let range = first.pop_front();
-            if !range.empty() {
-                self.free_list.insert(range);
-            }
-            return Some(first.min);
-        }
-
-        None
+        let first = self.allocatable(self.free_list.iter()).next()?.min;
+        self.take(Range::id(first));
+        Some(first)
     }
 
     /// Returns the first free value if one exists
@@ -174,18 +292,9 @@ impl FreeRanges {
     /// Marks the first index in the free list as used and returns it
     #[inline]
     pub fn set_last_used(&mut self) -> Option<usize> {
-        if let Some(&last) = self.free_list.iter().rev().nth(0) {
-            self.free_list.remove(&last);
-            if last.max != 0 {
-                let range = last.pop_back();
-                if !range.empty() {
-                    self.free_list.insert(range);
-                }
-            }
-            return Some(last.max);
-        }
-
-        None
+        let last = self.allocatable(self.free_list.iter()).next_back()?.max;
+        self.take(Range::id(last));
+        Some(last)
     }
 
     #[inline]
@@ -198,120 +307,2154 @@ impl FreeRanges {
     #[inline]
     pub fn is_free(&self, index: usize) -> bool {
         let range = Range::id(index);
-        self.free_list.get(&range).is_some()
+        self.free_list.contains(&range)
     }
 
+    /// Marks every index as used. A BTreeSet can't keep its nodes around
+    /// once emptied, so this frees all of them and refilling the set past a
+    /// single range allocates them again. Barriers and exclusions are kept.
+    /// To reset to the same ranges over and over, `clone_from` a filled
+    /// template instead, which is far cheaper than refilling (see the
+    /// `refill` benchmark)
     #[inline]
     pub fn clear(&mut self) {
         self.free_list.clear();
     }
-}
 
-const EMPTY_RANGE: Range = Range { min: 1, max: 0 };
+    /// Moves the ranges back into inline storage, freeing the tree, if at
+    /// most one is left. Removing ranges keeps the tree so that a set going
+    /// back and forth between one and two ranges doesn't rebuild it every
+    /// time, so call this once a set has settled down to a single range
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.free_list.shrink_to_fit();
+    }
 
-#[derive(Copy, Clone)]
-pub struct Range {
-    pub min: usize,
-    pub max: usize,
-}
+    /// Marks `size` contiguous indices as used at a random position, picking
+    /// uniformly among the free ranges that fit and then uniformly among the
+    /// offsets inside the chosen range
+    #[cfg(feature = "rand")]
+    pub fn allocate_random<R: rand::Rng + ?Sized>(
+        &mut self,
+        size: usize,
+        rng: &mut R,
+    ) -> Option<Range> {
+        if size == 0 {
+            return None;
+        }
 
-impl fmt::Debug for Range {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "({}...{})", self.min, self.max)
+        let fitting: Vec<Range> = self
+            .allocatable(self.free_list.iter())
+            .filter(|r| r.max - r.min >= size - 1)
+            .collect();
+        if fitting.is_empty() {
+            return None;
+        }
+
+        let range = fitting[rng.gen_range(0..fitting.len())];
+        let offset = rng.gen_range(0..=range.max - range.min - (size - 1));
+        let carved = Range {
+            min: range.min + offset,
+            max: range.min + offset + (size - 1),
+        };
+        self.take(carved);
+        Some(carved)
     }
-}
 
-impl Range {
+    /// Marks `size` contiguous indices as used at a random position, picking
+    /// uniformly among every place the block fits. Unlike `allocate_random`
+    /// each free range is weighted by the number of positions it offers
+    #[cfg(feature = "rand")]
     #[inline]
-    pub fn id(id: usize) -> Self {
-        Range { min: id, max: id }
+    pub fn allocate_randomized<R: rand::Rng + ?Sized>(
+        &mut self,
+        size: usize,
+        rng: &mut R,
+    ) -> Option<Range> {
+        self.allocate_randomized_aligned(size, 1, rng)
     }
 
-    #[inline]
-    pub fn empty(self) -> bool {
-        self.min > self.max
+    /// Like `allocate_randomized`, but only picks among the positions
+    /// starting on a multiple of `align`. An `align` of 0 is treated as 1
+    #[cfg(feature = "rand")]
+    pub fn allocate_randomized_aligned<R: rand::Rng + ?Sized>(
+        &mut self,
+        size: usize,
+        align: usize,
+        rng: &mut R,
+    ) -> Option<Range> {
+        let span = size.checked_sub(1)?;
+        let align = align.max(1);
+        // The first start in each range and how many starts it has
+        let starts = |range: &Range| -> Option<(usize, u128)> {
+            let first = align_up(range.min, align)?;
+            let last = range.max.checked_sub(span)?;
+            if first > last {
+                return None;
+            }
+            Some((first, ((last - first) / align) as u128 + 1))
+        };
+
+        let pieces = self.allocatable(self.free_list.iter());
+        let total: u128 = pieces
+            .clone()
+            .filter_map(|r| starts(&r).map(|(_, count)| count))
+            .sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut pick = rng.gen_range(0..total);
+        let first = pieces.into_iter().find_map(|range| {
+            let (first, count) = starts(&range)?;
+            if pick < count {
+                Some(first + pick as usize * align)
+            } else {
+                pick -= count;
+                None
+            }
+        })?;
+        let carved = Range {
+            min: first,
+            max: first + span,
+        };
+        self.take(carved);
+        Some(carved)
     }
 
-    #[inline]
-    pub fn push_front(mut self) -> Self {
-        self.min -= 1;
-        self
+    /// Builds a set over `0...bound` where roughly `used_fraction` of the
+    /// indices are used, laid out in alternating used and free runs. Used
+    /// runs average `mean_run_len` indices, and free runs are sized so the
+    /// expected used fraction comes out as requested.
+    #[cfg(feature = "rand")]
+    pub fn fill_random<R: rand::Rng + ?Sized>(
+        bound: usize,
+        used_fraction: f64,
+        mean_run_len: usize,
+        rng: &mut R,
+    ) -> FreeRanges {
+        if used_fraction >= 1.0 {
+            return FreeRanges::new();
+        }
+        if used_fraction.is_nan() || used_fraction <= 0.0 {
+            return FreeRanges::with_initial_range(Range { min: 0, max: bound });
+        }
+
+        let mean_used = mean_run_len.max(1) as f64;
+        let mean_free = mean_used * (1.0 - used_fraction) / used_fraction;
+        let mut ranges = FreeRanges::new();
+        let mut used = rng.gen_bool(used_fraction);
+        let mut cursor: usize = 0;
+        loop {
+            let mean = if used { mean_used } else { mean_free };
+            let len = (rng.gen::<f64>() * 2.0 * mean).round() as usize;
+            if len > 0 {
+                let end = cmp::min(cursor.saturating_add(len - 1), bound);
+                if !used {
+                    ranges.set_range_free(Range {
+                        min: cursor,
+                        max: end,
+                    });
+                }
+                if end == bound {
+                    return ranges;
+                }
+                cursor = end + 1;
+            }
+            used = !used;
+        }
+    }
+
+    /// The length of the longest run of free indices which starts on a
+    /// multiple of `align`, or 0 if there is none. An `align` of 0 is treated
+    /// as 1, and a run covering every index reports `usize::MAX`
+    pub fn max_aligned_free(&self, align: usize) -> usize {
+        self.largest_aligned_block(align)
+            .map_or(0, |block| (block.max - block.min).saturating_add(1))
+    }
+
+    /// The largest block which starts on a multiple of `align`, made by
+    /// rounding the start of each free range up to the alignment. Ties go
+    /// to the lowest address, and an `align` of 0 is treated as 1
+    pub fn largest_aligned_block(&self, align: usize) -> Option<Range> {
+        let mut largest: Option<Range> = None;
+        for range in &self.free_list {
+            let min = match align_up(range.min, align) {
+                Some(min) if min <= range.max => min,
+                _ => continue,
+            };
+            if largest.is_none_or(|l| l.max - l.min < range.max - min) {
+                largest = Some(Range {
+                    min,
+                    max: range.max,
+                });
+            }
+        }
+        largest
+    }
+
+    /// Iterator over the free indices congruent to `remainder` modulo
+    /// `modulus`, skipping straight to the first such index in each range.
+    /// A `modulus` of 0 only matches `remainder` itself
+    pub fn free_indices_stride(
+        &self,
+        modulus: usize,
+        remainder: usize,
+    ) -> impl Iterator<Item = usize> + '_ {
+        self.free_list.iter().flat_map(move |&range| {
+            let first = first_congruent(range, modulus, remainder);
+            first.into_iter().flat_map(move |first| {
+                let end = if modulus == 0 { first } else { range.max };
+                (first..=end).step_by(modulus.max(1))
+            })
+        })
+    }
+
+    /// Marks the first free index congruent to `remainder` modulo `modulus`
+    /// as used and returns it
+    pub fn set_first_used_stride(&mut self, modulus: usize, remainder: usize) -> Option<usize> {
+        let index = self
+            .allocatable(self.free_list.iter())
+            .find_map(|range| first_congruent(range, modulus, remainder))?;
+        self.take(Range::id(index));
+        Some(index)
+    }
+
+    /// Checks every index in `sorted_indices` at once by walking the free
+    /// ranges alongside them. The indices must be sorted in ascending order;
+    /// if they are not, an index smaller than one before it may be reported
+    /// as used even when it is free
+    pub fn are_free_sorted(&self, sorted_indices: &[usize]) -> Vec<bool> {
+        self.sorted_membership(sorted_indices).collect()
+    }
+
+    /// Returns true if every index in `sorted_indices` is free, stopping at
+    /// the first used one. Has the same sorting requirement as `are_free_sorted`
+    pub fn all_free_sorted(&self, sorted_indices: &[usize]) -> bool {
+        self.sorted_membership(sorted_indices).all(|free| free)
+    }
+
+    /// Returns true if any index in `sorted_indices` is free, stopping at
+    /// the first free one. Has the same sorting requirement as `are_free_sorted`
+    pub fn any_free_sorted(&self, sorted_indices: &[usize]) -> bool {
+        self.sorted_membership(sorted_indices).any(|free| free)
+    }
+
+    /// Returns true if every index in `indices` is free. The indices are
+    /// sorted first and then checked in one walk over the ranges
+    pub fn are_free(&self, indices: &[usize]) -> bool {
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        self.all_free_sorted(&sorted)
+    }
+
+    /// Whether each index in `indices` is free, in the order given. Like
+    /// `are_free` this sorts the indices and walks the ranges once
+    pub fn which_free(&self, indices: &[usize]) -> Vec<bool> {
+        let mut order: Vec<usize> = (0..indices.len()).collect();
+        order.sort_unstable_by_key(|&i| indices[i]);
+        let sorted: Vec<usize> = order.iter().map(|&i| indices[i]).collect();
+
+        let mut free = vec![false; indices.len()];
+        for (&i, is_free) in order.iter().zip(self.sorted_membership(&sorted)) {
+            free[i] = is_free;
+        }
+        free
+    }
+
+    /// Splits the set in two at `at`, leaving the indices below `at` in
+    /// `self` and returning the ones at or above it
+    pub fn split_off(&mut self, at: usize) -> FreeRanges {
+        let mut lower = std::mem::take(&mut self.free_list).into_tree();
+        let higher = split_set(&mut lower, at);
+        self.free_list = RangeSet::from(lower);
+        FreeRanges {
+            free_list: RangeSet::from(higher),
+            barriers: self.barriers.split_off(&at),
+            excluded: split_set(&mut self.excluded, at),
+            tiers: self.tiers.clone(),
+        }
+    }
+
+    /// Appends every range of `higher`, which must lie entirely above the
+    /// ranges in `self` (as produced by `split_off`), merging the two
+    /// ranges that meet at the boundary if they are adjacent
+    pub fn join(&mut self, mut higher: FreeRanges) {
+        let last = self.free_list.iter().next_back().cloned();
+        let first = higher.free_list.iter().next().cloned();
+        if let (Some(last), Some(first)) = (last, first) {
+            debug_assert!(last.max < first.min, "joined ranges must lie above self");
+            if last.max + 1 == first.min && !self.barriers.contains(&last.max) {
+                self.free_list.remove(&last);
+                higher.free_list.remove(&first);
+                self.free_list.insert(last.merge(first));
+            }
+        }
+
+        self.free_list.append(&mut higher.free_list);
+        self.barriers.append(&mut higher.barriers);
+        self.excluded.append(&mut higher.excluded);
+    }
+
+    /// Summarizes how much of `window` is free, in a single pass over the
+    /// free ranges which overlap it
+    pub fn window_report(&self, window: Range) -> WindowReport {
+        let mut builder = WindowReportBuilder::new(window);
+        for &range in self.ranges_after(window.min) {
+            if range.min > window.max {
+                break;
+            }
+            builder.add(range);
+        }
+        builder.finish()
+    }
+
+    /// Produces a `window_report` for every window. When the windows are
+    /// sorted and disjoint they share a single sweep over the free ranges,
+    /// otherwise each window is looked up separately
+    pub fn window_reports(&self, windows: &[Range]) -> Vec<WindowReport> {
+        let sweepable = windows.windows(2).all(|w| w[0].max < w[1].min);
+        let first = match windows.first() {
+            Some(first) if sweepable => first,
+            _ => return windows.iter().map(|&w| self.window_report(w)).collect(),
+        };
+
+        let mut ranges = self.ranges_after(first.min).peekable();
+        let mut reports = Vec::with_capacity(windows.len());
+        for &window in windows {
+            let mut builder = WindowReportBuilder::new(window);
+            while let Some(&&range) = ranges.peek() {
+                if range.min > window.max {
+                    break;
+                }
+                if range.max >= window.min {
+                    builder.add(range);
+                }
+                if range.max > window.max {
+                    break;
+                }
+                ranges.next();
+            }
+            reports.push(builder.finish());
+        }
+        reports
+    }
+
+    /// Splits `domain` into `buckets` equal buckets and gives the fraction
+    /// of each which is free, in a single pass over the free ranges. When
+    /// the domain doesn't divide evenly the last bucket also takes the
+    /// leftover indices, and when there are more buckets than indices there
+    /// is one bucket per index instead. An empty domain or zero buckets
+    /// gives an empty profile
+    pub fn density_profile(&self, domain: Range, buckets: usize) -> Vec<f32> {
+        if domain.empty() || buckets == 0 {
+            return Vec::new();
+        }
+        let len = (domain.max - domain.min) as u128 + 1;
+        let buckets = cmp::min(buckets as u128, len);
+        let width = len / buckets;
+        let last = buckets - 1;
+        let bucket_of = |offset: u128| cmp::min(offset / width, last);
+
+        let mut free = vec![0u128; buckets as usize];
+        for range in self.ranges_between(domain.min, domain.max) {
+            let start = (cmp::max(range.min, domain.min) - domain.min) as u128;
+            let end = (cmp::min(range.max, domain.max) - domain.min) as u128;
+            for bucket in bucket_of(start)..=bucket_of(end) {
+                let lo = cmp::max(start, bucket * width);
+                let hi = if bucket == last {
+                    end
+                } else {
+                    cmp::min(end, (bucket + 1) * width - 1)
+                };
+                free[bucket as usize] += hi - lo + 1;
+            }
+        }
+
+        free.iter()
+            .enumerate()
+            .map(|(bucket, &free)| {
+                let size = if bucket as u128 == last {
+                    len - last * width
+                } else {
+                    width
+                };
+                (free as f64 / size as f64) as f32
+            })
+            .collect()
+    }
+
+    /// The nearest free ranges lying entirely below and entirely above
+    /// `index`. A range containing `index` is excluded from both sides
+    pub fn neighbors(&self, index: usize) -> (Option<Range>, Option<Range>) {
+        use std::collections::Bound;
+        let probe = Range::id(index);
+        let before = self.free_list.range(..probe).next_back().cloned();
+        let after = self
+            .free_list
+            .range((Bound::Excluded(probe), Bound::Unbounded))
+            .next()
+            .cloned();
+        (before, after)
+    }
+
+    /// Removes and returns the smallest free range holding at least
+    /// `min_size` indices, taking the whole range so that no fragment is
+    /// ever left behind. Ties go to the lowest address
+    pub fn allocate_whole(&mut self, min_size: usize) -> Option<Range> {
+        let needed_span = min_size.saturating_sub(1);
+        let mut best: Option<Range> = None;
+        for range in self.allocatable(self.free_list.iter()) {
+            let span = range.max - range.min;
+            if span >= needed_span && best.is_none_or(|b| b.max - b.min > span) {
+                best = Some(range);
+            }
+        }
+
+        let best = best?;
+        self.take(best);
+        Some(best)
+    }
+
+    /// Marks every index in `range` as used, trimming any free ranges that
+    /// overlap it. Returns false if none of them were free
+    pub fn set_range_used(&mut self, range: Range) -> bool {
+        let mut changed = false;
+        while let Some(&overlap) = self.free_list.get(&range) {
+            self.free_list.remove(&overlap);
+            if overlap.min < range.min {
+                self.free_list.insert(Range {
+                    min: overlap.min,
+                    max: range.min - 1,
+                });
+            }
+            if overlap.max > range.max {
+                self.free_list.insert(Range {
+                    min: range.max + 1,
+                    max: overlap.max,
+                });
+            }
+            changed = true;
+        }
+        changed
     }
 
+    /// Returns true if every index in `range` is free
     #[inline]
-    pub fn push_back(mut self) -> Self {
-        self.max += 1;
-        self
+    pub fn is_range_free(&self, range: Range) -> bool {
+        self.free_run(range.min)
+            .is_some_and(|outer| outer.max >= range.max)
+    }
+
+    /// The free range holding `index`, extended over the ranges a barrier
+    /// splits it from, since those are free indices all the same
+    fn free_run(&self, index: usize) -> Option<Range> {
+        let mut run = *self.free_list.get(&Range::id(index))?;
+        if self.barriers.is_empty() {
+            return Some(run);
+        }
+        let touching = |i: Option<usize>| i.and_then(|i| self.free_list.get(&Range::id(i)));
+        while let Some(&below) = touching(run.min.checked_sub(1)) {
+            run.min = below.min;
+        }
+        while let Some(&above) = touching(run.max.checked_add(1)) {
+            run.max = above.max;
+        }
+        Some(run)
+    }
+
+    /// Marks the first `size` contiguous free indices as used and returns
+    /// them, taking them from the front of the first range that fits
+    pub fn allocate(&mut self, size: usize) -> Option<Range> {
+        let span = size.checked_sub(1)?;
+        let range = self
+            .allocatable(self.free_list.iter())
+            .find(|r| r.max - r.min >= span)?;
+        let carved = Range {
+            min: range.min,
+            max: range.min + span,
+        };
+        self.take(carved);
+        Some(carved)
     }
 
+    /// Consumes the set and returns the sorted ranges backing it.
+    ///
+    /// This exposes the internal representation and may change or go away
+    /// if the storage behind FreeRanges changes.
     #[inline]
-    pub fn pop_front(mut self) -> Self {
-        self.min += 1;
-        self
+    pub fn into_inner(self) -> BTreeSet<Range> {
+        self.free_list.into_tree()
     }
 
+    /// Borrows the sorted ranges backing the set, for queries like
+    /// `as_inner().range(..)` that the other methods don't cover. The set
+    /// can't be borrowed mutably since the ranges must stay disjoint.
+    ///
+    /// This exposes the internal representation and may change or go away
+    /// if the storage behind FreeRanges changes.
     #[inline]
-    pub fn pop_back(mut self) -> Self {
-        self.max -= 1;
-        self
+    pub fn as_inner(&self) -> &BTreeSet<Range> {
+        self.free_list.tree()
     }
 
+    /// Builds a FreeRanges from a set of ranges, checking that none of them
+    /// are empty and that they are disjoint and not adjacent to each other.
+    ///
+    /// This exposes the internal representation and may change or go away
+    /// if the storage behind FreeRanges changes.
+    pub fn from_inner(set: BTreeSet<Range>) -> Result<FreeRanges, InvariantViolation> {
+        check_canonical(set.iter().cloned())?;
+        Ok(FreeRanges::from_inner_unchecked(set))
+    }
+
+    /// Builds a FreeRanges from a set of ranges without checking them.
+    /// Every other method assumes the ranges are non-empty, disjoint and
+    /// not adjacent, and will misbehave if they are not.
+    ///
+    /// This exposes the internal representation and may change or go away
+    /// if the storage behind FreeRanges changes.
     #[inline]
-    pub fn merge(self, other: Self) -> Self {
-        Range {
-            min: cmp::min(self.min, other.min),
-            max: cmp::max(self.max, other.max),
+    pub fn from_inner_unchecked(set: BTreeSet<Range>) -> FreeRanges {
+        FreeRanges {
+            free_list: RangeSet::from(set),
+            barriers: BTreeSet::new(),
+            excluded: BTreeSet::new(),
+            tiers: Vec::new(),
         }
     }
 
-    #[inline]
-    pub fn contains(&self, value: usize) -> bool {
-        value >= self.min && value <= self.max
+    /// Returns up to `limit` free ranges in address order starting after
+    /// `token`, plus a token to resume from, or None once the scan is done.
+    ///
+    /// The token only records a position, so the set may change between
+    /// calls. Ranges freed behind the position are not seen by the rest of
+    /// the scan, and a range which grew across the position is returned
+    /// clipped to start after it, so no index is ever returned twice.
+    pub fn scan_from(
+        &self,
+        token: Option<ScanToken>,
+        limit: usize,
+    ) -> (Vec<Range>, Option<ScanToken>) {
+        let start = token.map_or(0, |token| token.next);
+        let mut ranges = self.ranges_after(start).map(|&range| Range {
+            min: cmp::max(range.min, start),
+            max: range.max,
+        });
+
+        let page: Vec<Range> = ranges.by_ref().take(limit).collect();
+        let next = match page.last() {
+            Some(last) => last.max.checked_add(1),
+            None => Some(start),
+        };
+        match next {
+            Some(next) if ranges.next().is_some() => (page, Some(ScanToken { next })),
+            _ => (page, None),
+        }
+    }
+
+    /// Marks the first `size` contiguous free indices starting on a multiple
+    /// of `align` as used and returns them. An `align` of 0 is treated as 1
+    pub fn allocate_aligned(&mut self, size: usize, align: usize) -> Option<Range> {
+        let span = size.checked_sub(1)?;
+        let carved = self.allocatable(self.free_list.iter()).find_map(|range| {
+            let min = align_up(range.min, align)?;
+            let max = min.checked_add(span)?;
+            if max <= range.max {
+                Some(Range { min, max })
+            } else {
+                None
+            }
+        })?;
+        self.take(carved);
+        Some(carved)
+    }
+
+    /// Returns the first pair of consecutive stored ranges which overlap or
+    /// touch, and so should never coexist in a valid set. Ranges touching
+    /// at a barrier are kept apart on purpose and aren't reported
+    pub fn first_overlap(&self) -> Option<(Range, Range)> {
+        self.free_list
+            .iter()
+            .zip(self.free_list.iter().skip(1))
+            .map(|(&a, &b)| (a, b))
+            .find(|&(a, b)| {
+                a.max >= b.min || (a.max + 1 == b.min && !self.barriers.contains(&a.max))
+            })
+    }
+
+    /// The longest run of used indices lying between two free ranges.
+    /// Ranges split by a barrier have no used indices between them.
+    /// Ties go to the lowest address
+    pub fn longest_used_run(&self) -> Option<Range> {
+        let gaps = self
+            .free_list
+            .iter()
+            .zip(self.free_list.iter().skip(1))
+            .filter(|(a, b)| a.max + 1 < b.min)
+            .map(|(a, b)| Range {
+                min: a.max + 1,
+                max: b.min - 1,
+            });
+        longest(gaps)
     }
 
+    /// The longest run of used indices inside `domain`, counting the used
+    /// indices before the first and after the last free range in it.
+    /// Ties go to the lowest address
+    pub fn longest_used_run_within(&self, domain: Range) -> Option<Range> {
+        longest(self.used_runs_within(domain))
+    }
+
+    /// Marks `size` contiguous indices as used, trying each of `alignments`
+    /// in every free range and taking the placement which skips the fewest
+    /// indices to reach an aligned start. Ties go to the lowest address and
+    /// then to the earliest alignment listed. Returns the indices along
+    /// with the alignment that was used
+    pub fn allocate_best_alignment(
+        &mut self,
+        size: usize,
+        alignments: &[usize],
+    ) -> Option<(Range, usize)> {
+        let span = size.checked_sub(1)?;
+        let mut best: Option<(usize, Range, usize)> = None;
+        for range in self.allocatable(self.free_list.iter()) {
+            for &align in alignments {
+                let min = match align_up(range.min, align) {
+                    Some(min) => min,
+                    None => continue,
+                };
+                let max = match min.checked_add(span) {
+                    Some(max) if max <= range.max => max,
+                    _ => continue,
+                };
+                let padding = min - range.min;
+                if best.is_none_or(|(best_padding, ..)| padding < best_padding) {
+                    best = Some((padding, Range { min, max }, align));
+                }
+            }
+        }
+
+        let (_, carved, align) = best?;
+        self.take(carved);
+        Some((carved, align))
+    }
+
+    /// Returns true if no index is free
     #[inline]
-    pub fn split(self, middle: usize) -> (Range, Range) {
-        if middle == 0 {
-            return (EMPTY_RANGE, self.pop_front());
+    pub fn is_exhausted(&self) -> bool {
+        self.free_list.is_empty()
+    }
+
+    /// Allocates everything that is left, returning every free range and
+    /// leaving the set exhausted. Free indices inside excluded ranges can't
+    /// be allocated, so they are neither returned nor taken
+    pub fn drain_all(&mut self) -> Vec<Range> {
+        let drained: Vec<Range> = self.allocatable(self.free_list.iter()).collect();
+        for &range in &drained {
+            self.take(range);
         }
+        drained
+    }
 
-        let left = Range {
-            min: self.min,
-            max: middle - 1,
+    /// Extends a domain of `current_len` indices by `additional`, marking
+    /// the new indices free (merging them with a free range ending at
+    /// `current_len - 1`) and returning them. Panics if `additional` is 0
+    /// or the new length would overflow
+    pub fn grow(&mut self, current_len: usize, additional: usize) -> Range {
+        assert!(additional > 0, "grow needs at least one additional index");
+        let range = match Range::from_len(current_len, additional) {
+            Some(range) => range,
+            None => panic!(
+                "growing a domain of {} indices by {} overflows usize",
+                current_len, additional
+            ),
         };
-        let right = Range {
-            min: middle + 1,
-            max: self.max,
+        debug_assert!(
+            !self.free_list.contains(&range),
+            "indices past the end of a domain of {} were already free",
+            current_len
+        );
+
+        self.set_range_free(range);
+        range
+    }
+
+    /// Shrinks a domain of `current_len` indices down to `new_len`, dropping
+    /// the free indices past the new end. Returns false and changes nothing
+    /// if any index in `new_len..current_len` is in use
+    pub fn shrink(&mut self, current_len: usize, new_len: usize) -> bool {
+        let removed = match Range::from_len(new_len, current_len.saturating_sub(new_len)) {
+            Some(removed) => removed,
+            None => return true,
         };
-        (left, right)
+        if !self.is_range_free(removed) {
+            return false;
+        }
+
+        self.set_range_used(removed);
+        true
     }
-}
 
-impl PartialEq for Range {
+    /// Returns a copy of the set with every index in `range` marked used,
+    /// leaving `self` untouched
+    pub fn without(&self, range: Range) -> FreeRanges {
+        let mut copy = self.clone();
+        copy.set_range_used(range);
+        copy
+    }
+
+    /// The number of free indices in the run ending at `domain_end`, ignoring
+    /// any part of it past `domain_end`. The run continues across barriers.
+    /// Saturates at `usize::MAX` when every index up to `usize::MAX` is free
     #[inline]
-    fn eq(&self, other: &Self) -> bool {
-        self.cmp(other) == Ordering::Equal
+    pub fn trailing_free_len(&self, domain_end: usize) -> usize {
+        self.free_run(domain_end)
+            .map_or(0, |r| (domain_end - r.min).saturating_add(1))
     }
-}
 
-impl Eq for Range {}
+    /// The number of free indices in the run starting at 0, which continues
+    /// across barriers. Saturates at `usize::MAX` when every index is free
+    #[inline]
+    pub fn leading_free_len(&self) -> usize {
+        self.free_run(0).map_or(0, |r| r.max.saturating_add(1))
+    }
 
-impl PartialOrd for Range {
+    /// Marks `range` as free only if none of it was free already. On failure
+    /// nothing is changed and the error holds the first part of `range`
+    /// which was already free, or the range itself if it is empty
+    pub fn set_range_free_strict(&mut self, range: Range) -> Result<(), StrictFreeError> {
+        if range.empty() {
+            return Err(StrictFreeError::EmptyRange(range));
+        }
+        if let Some(overlap) = self.ranges_between(range.min, range.max).next() {
+            return Err(StrictFreeError::AlreadyFree(Range {
+                min: cmp::max(overlap.min, range.min),
+                max: cmp::min(overlap.max, range.max),
+            }));
+        }
+
+        self.do_set_free(range);
+        Ok(())
+    }
+
+    /// The lowest free range, if any
     #[inline]
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    pub fn first_range(&self) -> Option<Range> {
+        self.free_list.iter().next().cloned()
     }
-}
 
-impl Ord for Range {
+    /// The `n`th free range in address order. Like `Iterator::nth`, this
+    /// walks the `n` ranges before it, so to page through the ranges keep
+    /// an iterator (or use `scan_from`) rather than calling this per range
     #[inline]
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.contains(other.min) || self.contains(other.max) || other.contains(self.min)
-            || other.contains(self.max)
-        {
-            return Ordering::Equal;
+    pub fn nth_range(&self, n: usize) -> Option<Range> {
+        self.free_list.iter().nth(n).cloned()
+    }
+
+    /// Returns true if some free range holds at least `size` indices,
+    /// stopping at the first range that fits
+    #[inline]
+    pub fn can_allocate(&self, size: usize) -> bool {
+        match size.checked_sub(1) {
+            Some(span) => self.free_list.iter().any(|r| r.max - r.min >= span),
+            None => true,
+        }
+    }
+
+    /// Removes the smallest free range and returns it, so the space it
+    /// covered is no longer available. Ties go to the lowest range.
+    pub fn evict_smallest(&mut self) -> Option<Range> {
+        let &smallest = self.free_list.iter().min_by_key(|r| r.max - r.min)?;
+        self.free_list.remove(&smallest);
+        Some(smallest)
+    }
+
+    /// Iterator over the free ranges as `(start, len)` pairs, lowest first.
+    /// A range covering every index is one longer than usize::MAX, so its
+    /// length saturates and it comes out as `(0, usize::MAX)`.
+    #[inline]
+    pub fn free_offsets(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.free_list
+            .iter()
+            .map(|r| (r.min, (r.max - r.min).saturating_add(1)))
+    }
+
+    /// Marks `count` separate runs of `each_len` indices as used and returns
+    /// them, or changes nothing and returns None if they don't all fit.
+    ///
+    /// Free ranges are tried first-fit, and a range long enough for several
+    /// runs supplies them back to back from its front before the next range
+    /// is tried.
+    pub fn allocate_multi(&mut self, count: usize, each_len: usize) -> Option<Vec<Range>> {
+        let span = each_len.checked_sub(1)?;
+        let mut takes = Vec::new();
+        let mut needed = count;
+        for range in self.allocatable(self.free_list.iter()) {
+            if needed == 0 {
+                break;
+            }
+            if range.max - range.min < span {
+                continue;
+            }
+            // Only the full domain with runs of 1 fits more than usize::MAX
+            let taken = ((range.max - range.min - span) / each_len)
+                .checked_add(1)
+                .map_or(needed, |fits| fits.min(needed));
+            takes.push((range, taken));
+            needed -= taken;
+        }
+        if needed > 0 {
+            return None;
         }
 
-        self.min.cmp(&other.min)
+        let mut placed = Vec::with_capacity(count);
+        for (range, taken) in takes {
+            let mut start = range.min;
+            for _ in 0..taken {
+                placed.push(Range {
+                    min: start,
+                    max: start + span,
+                });
+                start = start.wrapping_add(each_len);
+            }
+            let carved = Range {
+                min: range.min,
+                max: range.min + ((taken - 1) * each_len + span),
+            };
+            self.take(carved);
+        }
+        Some(placed)
+    }
+
+    /// The only free range, if the free indices form exactly one run
+    #[inline]
+    pub fn is_single_range(&self) -> Option<Range> {
+        if self.range_count() == 1 {
+            self.first_range()
+        } else {
+            None
+        }
+    }
+
+    /// The free ranges which end right before `range` and start right after
+    /// it, i.e. the ones freeing `range` would merge with
+    pub fn adjacent_free(&self, range: Range) -> (Option<Range>, Option<Range>) {
+        let before = range.min.checked_sub(1).and_then(|prev| {
+            self.free_list
+                .get(&Range::id(prev))
+                .filter(|r| r.max == prev)
+                .cloned()
+        });
+        let after = range.max.checked_add(1).and_then(|next| {
+            self.free_list
+                .get(&Range::id(next))
+                .filter(|r| r.min == next)
+                .cloned()
+        });
+        (before, after)
+    }
+
+    /// Keeps `between` and `between + 1` from ever being merged into one
+    /// free range, splitting the range which currently spans them. Returns
+    /// false if the barrier was already there or `between` is usize::MAX
+    pub fn add_barrier(&mut self, between: usize) -> bool {
+        if between == usize::MAX || !self.barriers.insert(between) {
+            return false;
+        }
+        if let Some(&spanning) = self.free_list.get(&Range::id(between)) {
+            if spanning.max > between {
+                self.free_list.remove(&spanning);
+                self.insert_cut(spanning);
+            }
+        }
+        true
+    }
+
+    /// Removes a barrier added by `add_barrier`, merging the free ranges
+    /// on either side of it if both are there. Returns false if there was
+    /// no barrier at `between`
+    pub fn remove_barrier(&mut self, between: usize) -> bool {
+        if !self.barriers.remove(&between) {
+            return false;
+        }
+        let left = self.free_list.get(&Range::id(between)).cloned();
+        let right = self.free_list.get(&Range::id(between + 1)).cloned();
+        if let (Some(left), Some(right)) = (left, right) {
+            if left != right {
+                self.free_list.remove(&left);
+                self.free_list.remove(&right);
+                self.free_list.insert(left.merge(right));
+            }
+        }
+        true
+    }
+
+    /// Iterator over the barriers, each being the index before the split
+    #[inline]
+    pub fn barriers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.barriers.iter().cloned()
+    }
+
+    /// Marks `window.min + offset` and every `step`th index after it within
+    /// `window` as used, splitting each affected range once. A step of 0
+    /// marks only the first of those indices. Returns how many were free
+    pub fn set_used_strided(&mut self, window: Range, step: usize, offset: usize) -> usize {
+        let start = match window.min.checked_add(offset) {
+            Some(start) if start <= window.max => start,
+            _ => return 0,
+        };
+        let remainder = if step == 0 { start } else { start % step };
+
+        let affected: Vec<Range> = self
+            .free_list
+            .range(Range::id(start)..=Range::id(window.max))
+            .cloned()
+            .collect();
+        let mut marked = 0;
+        for range in affected {
+            let within = Range {
+                min: cmp::max(range.min, start),
+                max: cmp::min(range.max, window.max),
+            };
+            let mut next = first_congruent(within, step, remainder);
+            if next.is_none() {
+                continue;
+            }
+
+            self.free_list.remove(&range);
+            let mut min = Some(range.min);
+            while let Some(index) = next.filter(|&index| index <= within.max) {
+                if let Some(min) = min.filter(|&min| min < index) {
+                    self.free_list.insert(Range {
+                        min,
+                        max: index - 1,
+                    });
+                }
+                marked += 1;
+                min = index.checked_add(1);
+                next = if step == 0 {
+                    None
+                } else {
+                    index.checked_add(step)
+                };
+            }
+            if let Some(min) = min.filter(|&min| min <= range.max) {
+                self.free_list.insert(Range {
+                    min,
+                    max: range.max,
+                });
+            }
+        }
+        marked
+    }
+
+    /// The largest power of two dividing the first free index. Index 0 is
+    /// aligned to every power of two, so it reports the largest one a usize
+    /// can hold
+    #[inline]
+    pub fn first_alignment(&self) -> Option<usize> {
+        self.first()
+            .map(|first| 1 << cmp::min(first.trailing_zeros(), usize::BITS - 1))
+    }
+
+    /// Marks `preferred` as used if all of it is free, or else the first
+    /// free run of the same length. Nothing changes if neither fits
+    pub fn allocate_preferring(&mut self, preferred: Range) -> Result<Placement, AllocError> {
+        let error = AllocError { preferred };
+        if preferred.empty() {
+            return Err(error);
+        }
+        if self.is_range_allocatable(preferred) {
+            self.set_range_used(preferred);
+            return Ok(Placement::AtPreferred(preferred));
+        }
+
+        let len = (preferred.max - preferred.min)
+            .checked_add(1)
+            .ok_or(error)?;
+        self.allocate(len).map(Placement::Relocated).ok_or(error)
+    }
+
+    /// The number of separate runs of used indices in `0..=bound`, which is
+    /// the number of ranges its complement would have
+    pub fn gap_count(&self, bound: usize) -> usize {
+        let mut gaps = 0;
+        let mut next = Some(0);
+        for range in self.ranges_between(0, bound) {
+            let cursor = match next {
+                Some(cursor) => cursor,
+                None => break,
+            };
+            if range.min > cursor {
+                gaps += 1;
+            }
+            next = range.max.checked_add(1);
+        }
+        if next.is_some_and(|cursor| cursor <= bound) {
+            gaps += 1;
+        }
+        gaps
+    }
+
+    /// Iterator over the runs of used indices in `0..=bound` that are at
+    /// most `max_gap` long, lowest first. These are the fragments worth
+    /// relocating to merge the free ranges around them
+    pub fn small_gaps(&self, bound: usize, max_gap: usize) -> impl Iterator<Item = Range> {
+        self.used_runs_within(Range { min: 0, max: bound })
+            .into_iter()
+            .filter(move |gap| gap.max - gap.min < max_gap)
+    }
+
+    /// Every run of used indices inside `domain`, lowest first
+    fn used_runs_within(&self, domain: Range) -> Vec<Range> {
+        let mut gaps = Vec::new();
+        let mut next = Some(domain.min);
+        for range in self.ranges_between(domain.min, domain.max) {
+            let cursor = match next {
+                Some(cursor) => cursor,
+                None => break,
+            };
+            if range.min > cursor {
+                gaps.push(Range {
+                    min: cursor,
+                    max: range.min - 1,
+                });
+            }
+            next = range.max.checked_add(1);
+        }
+        if let Some(cursor) = next {
+            if cursor <= domain.max {
+                gaps.push(Range {
+                    min: cursor,
+                    max: domain.max,
+                });
+            }
+        }
+        gaps
+    }
+
+    /// Iterator over the free ranges starting at or before `bound`, each
+    /// paired with the number of used indices after it up to the next free
+    /// range, or up to and including `bound` for the last one
+    pub fn free_ranges_with_gap_after(
+        &self,
+        bound: usize,
+    ) -> impl Iterator<Item = (Range, usize)> + '_ {
+        let mut ranges = self.ranges_before(bound).peekable();
+        iter::from_fn(move || {
+            let range = *ranges.next()?;
+            let gap_end = match ranges.peek() {
+                Some(next) => next.min - 1,
+                None => bound,
+            };
+            Some((range, gap_end.saturating_sub(range.max)))
+        })
+    }
+
+    /// Marks `size` contiguous indices as used, carving them from the front
+    /// of the smallest free range they fit in so that small ranges are used
+    /// up before large ones. Ties go to the lowest range
+    pub fn allocate_fill_small(&mut self, size: usize) -> Option<Range> {
+        let span = size.checked_sub(1)?;
+        let range = self
+            .allocatable(self.free_list.iter())
+            .filter(|r| r.max - r.min >= span)
+            .min_by_key(|r| r.max - r.min)?;
+        let carved = Range {
+            min: range.min,
+            max: range.min + span,
+        };
+        self.take(carved);
+        Some(carved)
+    }
+
+    /// Allocates like `allocate`, also returning the index just past the
+    /// block as a cursor for `allocate_from`
+    #[inline]
+    pub fn allocate_with_cursor(&mut self, size: usize) -> Option<(Range, usize)> {
+        self.allocate_from(0, size)
+    }
+
+    /// Next-fit allocation: marks the first `size` contiguous free indices
+    /// as used, looking at the ranges from the one containing or following
+    /// `cursor` onwards and then wrapping around to the lowest. Returns the
+    /// block along with the cursor to pass in next time
+    pub fn allocate_from(&mut self, cursor: usize, size: usize) -> Option<(Range, usize)> {
+        let span = size.checked_sub(1)?;
+        let range = self
+            .allocatable(self.ranges_after(cursor))
+            .chain(self.allocatable(self.ranges_before(cursor)))
+            .find(|r| r.max - r.min >= span)?;
+        let carved = Range {
+            min: range.min,
+            max: range.min + span,
+        };
+        self.take(carved);
+        Some((carved, carved.max.wrapping_add(1)))
+    }
+
+    /// The Jaccard similarity of the two sets of free indices, the size of
+    /// their intersection over the size of their union. Two empty sets are
+    /// identical, so that gives 1.0
+    pub fn jaccard(&self, other: &FreeRanges) -> f64 {
+        let len = |r: &Range| (r.max - r.min) as u128 + 1;
+        let mut intersection = 0u128;
+        let mut ours = self.free_list.iter().peekable();
+        let mut theirs = other.free_list.iter().peekable();
+        while let (Some(&&a), Some(&&b)) = (ours.peek(), theirs.peek()) {
+            let overlap = Range {
+                min: cmp::max(a.min, b.min),
+                max: cmp::min(a.max, b.max),
+            };
+            if !overlap.empty() {
+                intersection += len(&overlap);
+            }
+            if a.max < b.max {
+                ours.next();
+            } else {
+                theirs.next();
+            }
+        }
+
+        let total: u128 = self.free_list.iter().chain(&other.free_list).map(len).sum();
+        let union = total - intersection;
+        if union == 0 {
+            1.0
+        } else {
+            intersection as f64 / union as f64
+        }
+    }
+
+    /// Sets the windows `allocate_tiered` and `set_first_used_tiered` try
+    /// in order before falling back to the whole set. Windows may overlap
+    /// each other and don't need to line up with the free ranges
+    #[inline]
+    pub fn set_preference_tiers(&mut self, tiers: Vec<Range>) {
+        self.tiers = tiers;
+    }
+
+    /// The windows set by `set_preference_tiers`
+    #[inline]
+    pub fn preference_tiers(&self) -> &[Range] {
+        &self.tiers
+    }
+
+    /// Marks the first `size` contiguous free indices inside the first
+    /// preference tier that has room as used, or anywhere if none of them
+    /// do. Returns the block along with the index of the tier it came from,
+    /// or None for the fallback
+    pub fn allocate_tiered(&mut self, size: usize) -> Option<(Range, Option<usize>)> {
+        for i in 0..self.tiers.len() {
+            if let Some(range) = self.allocate_within(self.tiers[i], size) {
+                return Some((range, Some(i)));
+            }
+        }
+        self.allocate(size).map(|range| (range, None))
+    }
+
+    /// Marks a single index as used, trying the tiers like `allocate_tiered`
+    #[inline]
+    pub fn set_first_used_tiered(&mut self) -> Option<(usize, Option<usize>)> {
+        self.allocate_tiered(1)
+            .map(|(range, tier)| (range.min, tier))
+    }
+
+    /// Marks the first `size` contiguous free indices lying inside `window`
+    /// as used
+    fn allocate_within(&mut self, window: Range, size: usize) -> Option<Range> {
+        let span = size.checked_sub(1)?;
+        let carved = self
+            .allocatable(self.ranges_between(window.min, window.max))
+            .find_map(|range| {
+                let min = cmp::max(range.min, window.min);
+                let max = min.checked_add(span)?;
+                if max <= cmp::min(range.max, window.max) {
+                    Some(Range { min, max })
+                } else {
+                    None
+                }
+            })?;
+        self.take(carved);
+        Some(carved)
+    }
+
+    /// Removes and returns the free ranges which freeing `range` would
+    /// merge with at either end: the one containing `range.min - 1` and the
+    /// one containing `range.max + 1`. A single range covering both is
+    /// returned as `front`. Free ranges lying inside `range` stay put
+    pub fn coalescing_neighbors(&mut self, range: Range) -> CoalesceInfo {
+        let front = range
+            .min
+            .checked_sub(1)
+            .and_then(|prev| self.free_list.take(&Range::id(prev)));
+        let back = range
+            .max
+            .checked_add(1)
+            .and_then(|next| self.free_list.take(&Range::id(next)));
+        CoalesceInfo { front, back }
+    }
+
+    /// Returns a new set with every free range shrunk by `g` at both ends,
+    /// dropping the ranges which end up empty. Ends at 0 or usize::MAX stay
+    /// where they are, as if the free space went on past them, which makes
+    /// this the inverse of `dilate`
+    pub fn erode(&self, g: usize) -> FreeRanges {
+        let mut eroded = FreeRanges::new();
+        for range in &self.free_list {
+            let min = if range.min == 0 {
+                Some(0)
+            } else {
+                range.min.checked_add(g)
+            };
+            let max = if range.max == usize::MAX {
+                Some(usize::MAX)
+            } else {
+                range.max.checked_sub(g)
+            };
+            if let (Some(min), Some(max)) = (min, max) {
+                if min <= max {
+                    eroded.set_range_free(Range { min, max });
+                }
+            }
+        }
+        eroded
+    }
+
+    /// Returns a new set with every free range extended by `g` at both ends,
+    /// clamped to `0..=usize::MAX`, merging the ranges which now touch
+    pub fn dilate(&self, g: usize) -> FreeRanges {
+        let mut dilated = FreeRanges::new();
+        for range in &self.free_list {
+            dilated.set_range_free(range.saturating_grow(g));
+        }
+        dilated
+    }
+
+    /// Marks `size` contiguous indices at the back of the largest free range
+    /// as used, keeping its front free for larger requests. Ties go to the
+    /// lowest range
+    pub fn allocate_tail_of_largest(&mut self, size: usize) -> Option<Range> {
+        let span = size.checked_sub(1)?;
+        let range =
+            longest(self.allocatable(self.free_list.iter())).filter(|r| r.max - r.min >= span)?;
+        let carved = Range {
+            min: range.max - span,
+            max: range.max,
+        };
+        self.take(carved);
+        Some(carved)
+    }
+
+    /// Converts to a finer unit `factor` times smaller, so index `i` becomes
+    /// indices `i * factor` up to `(i + 1) * factor - 1`. Returns an error
+    /// naming the first range which would go past usize::MAX.
+    ///
+    /// Panics if `factor` is 0.
+    pub fn scale_up(&self, factor: usize) -> Result<FreeRanges, OverflowError> {
+        assert!(factor > 0, "cannot scale by a factor of 0");
+        let mut scaled = FreeRanges::new();
+        for &range in &self.free_list {
+            let min = range.min.checked_mul(factor);
+            let max = range
+                .max
+                .checked_mul(factor)
+                .and_then(|max| max.checked_add(factor - 1));
+            match (min, max) {
+                (Some(min), Some(max)) => {
+                    scaled.set_range_free(Range { min, max });
+                }
+                _ => return Err(OverflowError { range }),
+            }
+        }
+        Ok(scaled)
+    }
+
+    /// Converts to a coarser unit `factor` times larger, where coarse index
+    /// `i` covers fine indices `i * factor` up to `(i + 1) * factor - 1`.
+    /// `Rounding::Inward` keeps only the coarse indices whose fine indices
+    /// are all free, and `Rounding::Outward` keeps every one with any free.
+    ///
+    /// ```text
+    /// factor 4     | 0 1 2 3 | 4 5 6 7 | 8 9 10 11 | 12 13 14 15 |
+    /// free         |         |   5 ... | ......... | ... 13      |
+    /// Inward       |         |         |     2     |             |
+    /// Outward      |         |    1    |     2     |      3      |
+    /// ```
+    ///
+    /// Panics if `factor` is 0.
+    pub fn scale_down(&self, factor: usize, rounding: Rounding) -> FreeRanges {
+        assert!(factor > 0, "cannot scale by a factor of 0");
+        let mut scaled = FreeRanges::new();
+        // In u128 so that the end just past usize::MAX can be represented
+        let factor = factor as u128;
+        for &range in &self.free_list {
+            let (start, end) = (range.min as u128, range.max as u128 + 1);
+            let (min, end) = match rounding {
+                Rounding::Inward => (start.div_ceil(factor), end / factor),
+                Rounding::Outward => (start / factor, end.div_ceil(factor)),
+            };
+            if min < end {
+                scaled.set_range_free(Range {
+                    min: min as usize,
+                    max: (end - 1) as usize,
+                });
+            }
+        }
+        scaled
+    }
+
+    /// Returns true if every range is non-empty and each one ends at least
+    /// two indices before the next starts, except where a barrier keeps
+    /// them apart. Only sets built with `from_inner_unchecked` can fail this
+    pub fn is_canonical(&self) -> bool {
+        let mut prev: Option<Range> = None;
+        self.free_list.iter().all(|&range| {
+            let canonical = range.min <= range.max
+                && prev.is_none_or(|prev| {
+                    prev.max < range.min
+                        && (prev.max + 1 < range.min || self.barriers.contains(&prev.max))
+                });
+            prev = Some(range);
+            canonical
+        })
+    }
+
+    /// Allocates like `allocate`, returning the block as a start and a
+    /// length rather than an inclusive Range
+    #[inline]
+    pub fn allocate_alloc(&mut self, len: usize) -> Option<Allocation> {
+        self.allocate(len).map(|range| Allocation {
+            offset: range.min,
+            len,
+        })
+    }
+
+    /// Like `set_range_free`, but takes any std range of indices. Does
+    /// nothing and returns false if the range holds no indices
+    pub fn set_free_bounds<R: RangeBounds<usize>>(&mut self, bounds: R) -> bool {
+        match Range::from_bounds(bounds) {
+            Some(range) => self.set_range_free(range),
+            None => false,
+        }
+    }
+
+    /// Like `set_range_used`, but takes any std range of indices. Does
+    /// nothing and returns false if the range holds no indices
+    pub fn set_used_bounds<R: RangeBounds<usize>>(&mut self, bounds: R) -> bool {
+        match Range::from_bounds(bounds) {
+            Some(range) => self.set_range_used(range),
+            None => false,
+        }
+    }
+
+    /// Iterator over the free indices inside any std range of indices,
+    /// lowest first
+    pub fn free_indices_bounds<R: RangeBounds<usize>>(
+        &self,
+        bounds: R,
+    ) -> impl Iterator<Item = usize> + '_ {
+        Range::from_bounds(bounds)
+            .into_iter()
+            .flat_map(move |window| {
+                self.ranges_between(window.min, window.max)
+                    .flat_map(move |range| {
+                        cmp::max(range.min, window.min)..=cmp::min(range.max, window.max)
+                    })
+            })
+    }
+
+    /// Marks every free index outside any std range of indices as used, so
+    /// only the free indices inside it are kept. A range holding no indices
+    /// keeps nothing
+    pub fn retain_bounds<R: RangeBounds<usize>>(&mut self, bounds: R) {
+        let window = match Range::from_bounds(bounds) {
+            Some(window) => window,
+            None => return self.clear(),
+        };
+        if window.min > 0 {
+            self.set_range_used(Range {
+                min: 0,
+                max: window.min - 1,
+            });
+        }
+        if window.max < usize::MAX {
+            self.set_range_used(Range {
+                min: window.max + 1,
+                max: usize::MAX,
+            });
+        }
+    }
+
+    /// Marks the first `size` contiguous free indices which don't straddle
+    /// a multiple of `boundary` as used and returns them. When the front of
+    /// a free range would cross one, the block starts at that multiple
+    /// instead. A `boundary` of 0 places no restriction
+    pub fn allocate_no_cross(&mut self, size: usize, boundary: usize) -> Option<Range> {
+        let span = size.checked_sub(1)?;
+        if boundary != 0 && span >= boundary {
+            return None;
+        }
+        let carved = self.allocatable(self.free_list.iter()).find_map(|range| {
+            let mut min = range.min;
+            let mut max = min.checked_add(span)?;
+            if boundary != 0 && min / boundary != max / boundary {
+                min = align_up(min, boundary)?;
+                max = min.checked_add(span)?;
+            }
+            if max <= range.max {
+                Some(Range { min, max })
+            } else {
+                None
+            }
+        })?;
+        self.take(carved);
+        Some(carved)
+    }
+
+    /// Like `set_range_free`, but only frees the part of `range` inside
+    /// `0..=bound`. Returns false if none of that part was used, including
+    /// when `range` lies entirely past `bound`
+    pub fn set_range_free_clamped(&mut self, range: Range, bound: usize) -> bool {
+        let clipped = Range {
+            min: range.min,
+            max: cmp::min(range.max, bound),
+        };
+        if clipped.empty() {
+            return false;
+        }
+        self.set_range_free(clipped)
+    }
+
+    /// Iterator over the free ranges trimmed to run from their first to
+    /// their last multiple of `align`, which is where aligned blocks can
+    /// start. Ranges holding no multiple of `align` are skipped. An `align`
+    /// of 0 is treated as 1
+    pub fn aligned_free_ranges(&self, align: usize) -> impl Iterator<Item = Range> + '_ {
+        let align = align.max(1);
+        self.free_list.iter().filter_map(move |range| {
+            let min = align_up(range.min, align)?;
+            let max = range.max - range.max % align;
+            if min <= max {
+                Some(Range { min, max })
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Replaces the stored `range` with whatever is left of it around `carved`
+    fn cut_out(&mut self, range: Range, carved: Range) {
+        self.free_list.remove(&range);
+        if carved.min > range.min {
+            self.free_list.insert(Range {
+                min: range.min,
+                max: carved.min - 1,
+            });
+        }
+        if carved.max < range.max {
+            self.free_list.insert(Range {
+                min: carved.max + 1,
+                max: range.max,
+            });
+        }
+    }
+
+    fn sorted_membership<'a>(
+        &'a self,
+        sorted_indices: &'a [usize],
+    ) -> impl Iterator<Item = bool> + 'a {
+        debug_assert!(
+            sorted_indices.windows(2).all(|w| w[0] <= w[1]),
+            "indices must be sorted"
+        );
+
+        let mut ranges = self.free_list.iter().peekable();
+        sorted_indices.iter().map(move |&index| {
+            while let Some(range) = ranges.peek() {
+                if range.max >= index {
+                    return range.min <= index;
+                }
+                ranges.next();
+            }
+            false
+        })
+    }
+}
+
+impl IntoIterator for FreeRanges {
+    type Item = Range;
+    type IntoIter = btree_set::IntoIter<Range>;
+
+    /// Consumes the set, yielding every contiguous free range in order
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.free_list.into_tree().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FreeRanges {
+    type Item = &'a Range;
+    type IntoIter = Iter<'a, Range>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.free_ranges()
+    }
+}
+
+/// A position to resume `FreeRanges::scan_from` at. It can be turned into
+/// a plain number with `into_raw` for handing out over an API
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ScanToken {
+    next: usize,
+}
+
+impl ScanToken {
+    /// Rebuilds a token from a value returned by `into_raw`
+    #[inline]
+    pub fn from_raw(raw: usize) -> Self {
+        ScanToken { next: raw }
+    }
+
+    /// The first index the scan will look at when resumed
+    #[inline]
+    pub fn into_raw(self) -> usize {
+        self.next
+    }
+}
+
+/// Returned by `FreeRanges::set_range_free_strict` when the range can't
+/// be freed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StrictFreeError {
+    /// The range has its min above its max
+    EmptyRange(Range),
+    /// The first part of the range which was already free
+    AlreadyFree(Range),
+}
+
+impl fmt::Display for StrictFreeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StrictFreeError::EmptyRange(range) => write!(fmt, "range {:?} is empty", range),
+            StrictFreeError::AlreadyFree(range) => {
+                write!(fmt, "indices {:?} were already free", range)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StrictFreeError {}
+
+/// Returned by `FreeRanges::scale_up` when a range can't be scaled without
+/// going past usize::MAX
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OverflowError {
+    /// The first range which overflowed
+    pub range: Range,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "scaling indices {:?} overflows usize", self.range)
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+/// How `FreeRanges::scale_down` treats coarse indices which are only partly
+/// free
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Only keep coarse indices which are entirely free
+    Inward,
+    /// Keep coarse indices which are free at all
+    Outward,
+}
+
+/// The free ranges taken out by `FreeRanges::coalescing_neighbors`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CoalesceInfo {
+    /// The range overlapping or ending right before the start
+    pub front: Option<Range>,
+    /// The range overlapping or starting right after the end
+    pub back: Option<Range>,
+}
+
+/// A block as a start and a length, covering `offset..offset + len`, as
+/// returned by `FreeRanges::allocate_alloc`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Allocation {
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Allocation {
+    /// The index just past the block. This overflows for a block ending at
+    /// usize::MAX
+    #[inline]
+    pub fn end(self) -> usize {
+        self.offset + self.len
+    }
+}
+
+/// Where `FreeRanges::allocate_preferring` put a block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The preferred range was free and is now used
+    AtPreferred(Range),
+    /// The preferred range wasn't free, so a range of the same length was
+    /// used instead
+    Relocated(Range),
+}
+
+impl Placement {
+    /// The range which was marked as used
+    #[inline]
+    pub fn range(self) -> Range {
+        match self {
+            Placement::AtPreferred(range) | Placement::Relocated(range) => range,
+        }
+    }
+}
+
+/// Returned by `FreeRanges::allocate_preferring` when there was no free
+/// range as long as the preferred one
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AllocError {
+    /// The range which was asked for
+    pub preferred: Range,
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "no free space for a block the size of {:?}",
+            self.preferred
+        )
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+/// A way in which a set of ranges fails to be a valid FreeRanges
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// A range whose min is greater than its max
+    EmptyRange(Range),
+    /// Two ranges which overlap or are out of order
+    Overlapping(Range, Range),
+    /// Two ranges which touch and should have been merged into one
+    Adjacent(Range, Range),
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvariantViolation::EmptyRange(r) => write!(fmt, "range {:?} is empty", r),
+            InvariantViolation::Overlapping(a, b) => {
+                write!(fmt, "ranges {:?} and {:?} overlap", a, b)
+            }
+            InvariantViolation::Adjacent(a, b) => {
+                write!(fmt, "ranges {:?} and {:?} are adjacent", a, b)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+/// Checks that ranges coming in ascending order are non-empty, disjoint
+/// and not adjacent
+fn check_canonical<I: IntoIterator<Item = Range>>(ranges: I) -> Result<(), InvariantViolation> {
+    let mut prev: Option<Range> = None;
+    for range in ranges {
+        if range.min > range.max {
+            return Err(InvariantViolation::EmptyRange(range));
+        }
+        if let Some(prev) = prev {
+            if prev.max >= range.min {
+                return Err(InvariantViolation::Overlapping(prev, range));
+            }
+            if prev.max + 1 == range.min {
+                return Err(InvariantViolation::Adjacent(prev, range));
+            }
+        }
+        prev = Some(range);
+    }
+    Ok(())
+}
+
+/// Occupancy of a single window, as returned by `FreeRanges::window_report`.
+/// The counts saturate at `usize::MAX`, which can only happen for a window
+/// covering every index. An empty window (min above max) reports nothing
+/// free or used
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WindowReport {
+    /// The window this report describes
+    pub window: Range,
+    /// How many indices in the window are free
+    pub free: usize,
+    /// How many indices in the window are used
+    pub used: usize,
+    /// How many separate free ranges lie (at least partly) in the window
+    pub fragments: usize,
+    /// The largest free block inside the window, clipped to it
+    pub largest_free: Option<Range>,
+}
+
+struct WindowReportBuilder {
+    window: Range,
+    free_spans: usize,
+    fragments: usize,
+    largest_free: Option<Range>,
+}
+
+impl WindowReportBuilder {
+    fn new(window: Range) -> Self {
+        WindowReportBuilder {
+            window,
+            free_spans: 0,
+            fragments: 0,
+            largest_free: None,
+        }
+    }
+
+    fn add(&mut self, range: Range) {
+        if self.window.empty() {
+            return;
+        }
+        let clipped = Range {
+            min: cmp::max(range.min, self.window.min),
+            max: cmp::min(range.max, self.window.max),
+        };
+        let span = clipped.max - clipped.min;
+        self.free_spans += span;
+        self.fragments += 1;
+        if self.largest_free.is_none_or(|l| l.max - l.min < span) {
+            self.largest_free = Some(clipped);
+        }
+    }
+
+    fn finish(self) -> WindowReport {
+        let window_span = self.window.max.wrapping_sub(self.window.min);
+        let (free, used) = if self.window.empty() {
+            (0, 0)
+        } else if self.fragments == 0 {
+            (0, window_span.saturating_add(1))
+        } else {
+            (
+                self.free_spans.saturating_add(self.fragments),
+                window_span - self.free_spans - (self.fragments - 1),
+            )
+        };
+
+        WindowReport {
+            window: self.window,
+            free,
+            used,
+            fragments: self.fragments,
+            largest_free: self.largest_free,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Range {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl From<ops::RangeInclusive<usize>> for Range {
+    /// Converts `min..=max`. An empty std range gives an empty Range
+    #[inline]
+    fn from(range: ops::RangeInclusive<usize>) -> Self {
+        let (min, max) = range.into_inner();
+        Range { min, max }
+    }
+}
+
+impl fmt::Debug for Range {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "({}...{})", self.min, self.max)
+    }
+}
+
+impl Range {
+    #[inline]
+    pub fn id(id: usize) -> Self {
+        Range { min: id, max: id }
+    }
+
+    /// Creates the range covering `len` indices starting at `start`.
+    /// Returns None if `len` is 0 or the range would run past `usize::MAX`
+    #[inline]
+    pub fn from_len(start: usize, len: usize) -> Option<Self> {
+        let max = start.checked_add(len.checked_sub(1)?)?;
+        Some(Range { min: start, max })
+    }
+
+    /// Converts any std range of indices. Unbounded ends become 0 and
+    /// `usize::MAX`. Returns None if the range holds no indices, including
+    /// ones excluding 0 at the end or `usize::MAX` at the start
+    pub fn from_bounds<R: RangeBounds<usize>>(bounds: R) -> Option<Self> {
+        let min = match bounds.start_bound() {
+            Bound::Included(&min) => min,
+            Bound::Excluded(&min) => min.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let max = match bounds.end_bound() {
+            Bound::Included(&max) => max,
+            Bound::Excluded(&max) => max.checked_sub(1)?,
+            Bound::Unbounded => usize::MAX,
+        };
+        if min > max {
+            return None;
+        }
+        Some(Range { min, max })
+    }
+
+    #[inline]
+    pub fn empty(self) -> bool {
+        self.min > self.max
+    }
+
+    #[inline]
+    pub fn push_front(mut self) -> Self {
+        self.min -= 1;
+        self
+    }
+
+    #[inline]
+    pub fn push_back(mut self) -> Self {
+        self.max += 1;
+        self
+    }
+
+    #[inline]
+    pub fn pop_front(mut self) -> Self {
+        self.min += 1;
+        self
+    }
+
+    #[inline]
+    pub fn pop_back(mut self) -> Self {
+        self.max -= 1;
+        self
+    }
+
+    /// Like `push_front`, but returns None instead of underflowing at 0
+    #[inline]
+    pub fn checked_push_front(self) -> Option<Self> {
+        self.min
+            .checked_sub(1)
+            .map(|min| Range { min, max: self.max })
+    }
+
+    /// Like `push_back`, but returns None instead of overflowing at `usize::MAX`
+    #[inline]
+    pub fn checked_push_back(self) -> Option<Self> {
+        self.max
+            .checked_add(1)
+            .map(|max| Range { min: self.min, max })
+    }
+
+    /// Like `pop_front`, but returns None if the range would become empty
+    #[inline]
+    pub fn checked_pop_front(self) -> Option<Self> {
+        if self.min < self.max {
+            Some(self.pop_front())
+        } else {
+            None
+        }
+    }
+
+    /// Like `pop_back`, but returns None if the range would become empty
+    #[inline]
+    pub fn checked_pop_back(self) -> Option<Self> {
+        if self.min < self.max {
+            Some(self.pop_back())
+        } else {
+            None
+        }
+    }
+
+    /// Extends both ends by `n`, clamping at 0 and `usize::MAX`
+    #[inline]
+    pub fn saturating_grow(self, n: usize) -> Self {
+        Range {
+            min: self.min.saturating_sub(n),
+            max: self.max.saturating_add(n),
+        }
+    }
+
+    /// Pulls both ends in by `n`. Returns None if nothing is left
+    #[inline]
+    pub fn saturating_shrink(self, n: usize) -> Option<Self> {
+        let min = self.min.checked_add(n)?;
+        let max = self.max.checked_sub(n)?;
+        if min <= max {
+            Some(Range { min, max })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn merge(self, other: Self) -> Self {
+        Range {
+            min: cmp::min(self.min, other.min),
+            max: cmp::max(self.max, other.max),
+        }
+    }
+
+    /// Classifies how this range relates to `other`
+    pub fn relation(self, other: Range) -> RangeRelation {
+        if self.max < other.min {
+            return if other.min - self.max == 1 {
+                RangeRelation::Meets
+            } else {
+                RangeRelation::Before
+            };
+        }
+        if other.max < self.min {
+            return if self.min - other.max == 1 {
+                RangeRelation::MetBy
+            } else {
+                RangeRelation::After
+            };
+        }
+
+        match (self.min.cmp(&other.min), self.max.cmp(&other.max)) {
+            (Ordering::Equal, Ordering::Equal) => RangeRelation::Equal,
+            (Ordering::Less, Ordering::Less) => RangeRelation::OverlapsStart,
+            (Ordering::Greater, Ordering::Greater) => RangeRelation::OverlapsEnd,
+            (Ordering::Greater, _) | (_, Ordering::Less) => RangeRelation::ContainedBy,
+            (Ordering::Less, _) | (_, Ordering::Greater) => RangeRelation::Contains,
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, value: usize) -> bool {
+        value >= self.min && value <= self.max
+    }
+
+    /// Splits the range around `middle`, which is left out of both halves.
+    /// Returns None if `middle` is not inside the range, and a half is None
+    /// when nothing is left on that side.
+    #[inline]
+    pub fn split_at(self, middle: usize) -> Option<(Option<Range>, Option<Range>)> {
+        if !self.contains(middle) {
+            return None;
+        }
+
+        let left = if middle > self.min {
+            Some(Range {
+                min: self.min,
+                max: middle - 1,
+            })
+        } else {
+            None
+        };
+        let right = if middle < self.max {
+            Some(Range {
+                min: middle + 1,
+                max: self.max,
+            })
+        } else {
+            None
+        };
+        Some((left, right))
+    }
+
+    /// Splits the range into the pieces left over after removing every index
+    /// in `cuts`. The cuts must be sorted; ones outside the range are ignored.
+    pub fn split_at_many(self, cuts: &[usize]) -> Vec<Range> {
+        debug_assert!(cuts.windows(2).all(|w| w[0] <= w[1]), "cuts must be sorted");
+
+        let mut pieces = Vec::new();
+        let mut rest = Some(self);
+        for &cut in cuts {
+            let current = match rest {
+                Some(current) => current,
+                None => break,
+            };
+            if let Some((left, right)) = current.split_at(cut) {
+                pieces.extend(left);
+                rest = right;
+            }
+        }
+        pieces.extend(rest);
+        pieces
+    }
+}
+
+/// Parses a range written as `N`, `N..=M` or `N-M`, where `N` and `M` are
+/// decimal numbers and `N <= M`. Whitespace around the whole input is
+/// ignored; anything else, such as `N..M` or a sign, is rejected.
+impl FromStr for Range {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (min, max) = if let Some(at) = s.find("..=") {
+            (&s[..at], &s[at + 3..])
+        } else if let Some(at) = s.find('-') {
+            (&s[..at], &s[at + 1..])
+        } else {
+            (s, s)
+        };
+
+        let parse = |n: &str| {
+            // usize::from_str would also accept a leading plus sign
+            if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(ParseRangeError::Malformed);
+            }
+            n.parse::<usize>().map_err(|_| ParseRangeError::Malformed)
+        };
+        let min = parse(min)?;
+        let max = parse(max)?;
+        if min > max {
+            return Err(ParseRangeError::Inverted { min, max });
+        }
+        Ok(Range { min, max })
+    }
+}
+
+/// Why a string could not be parsed as a Range
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseRangeError {
+    /// The input did not match any of the accepted forms
+    Malformed,
+    /// The range ended before it started
+    Inverted { min: usize, max: usize },
+}
+
+impl fmt::Display for ParseRangeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseRangeError::Malformed => write!(fmt, "expected `N`, `N..=M` or `N-M`"),
+            ParseRangeError::Inverted { min, max } => {
+                write!(fmt, "range {}..={} ends before it starts", min, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseRangeError {}
+
+/// How one range sits relative to another, as returned by `Range::relation`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RangeRelation {
+    /// Ends before the other starts, with at least one index in between
+    Before,
+    /// Ends immediately before the other starts
+    Meets,
+    /// Starts before the other and ends inside it
+    OverlapsStart,
+    /// Lies inside the other without being equal to it
+    ContainedBy,
+    /// Covers the same indices as the other
+    Equal,
+    /// Covers the other without being equal to it
+    Contains,
+    /// Starts inside the other and ends after it
+    OverlapsEnd,
+    /// Starts immediately after the other ends
+    MetBy,
+    /// Starts after the other ends, with at least one index in between
+    After,
+}
+
+impl PartialEq for Range {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Range {}
+
+impl PartialOrd for Range {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Range {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.contains(other.min) || self.contains(other.max) || other.contains(self.min)
+            || other.contains(self.max)
+        {
+            return Ordering::Equal;
+        }
+
+        self.min.cmp(&other.min)
+    }
+}
+
+/// Rounds `value` up to the next multiple of `align`, treating an `align`
+/// of 0 as 1. Returns None if that would overflow
+#[inline]
+fn align_up(value: usize, align: usize) -> Option<usize> {
+    match value % align.max(1) {
+        0 => Some(value),
+        rem => value.checked_add(align - rem),
+    }
+}
+
+/// Splits off the ranges of `set` at or above `at`, cutting the one which
+/// straddles it in two
+fn split_set(set: &mut BTreeSet<Range>, at: usize) -> BTreeSet<Range> {
+    if let Some(&straddling) = set.get(&Range::id(at)) {
+        if straddling.min < at {
+            set.remove(&straddling);
+            set.insert(Range {
+                min: straddling.min,
+                max: at - 1,
+            });
+            set.insert(Range {
+                min: at,
+                max: straddling.max,
+            });
+        }
+    }
+    set.split_off(&Range::id(at))
+}
+
+/// The lowest index in `range` congruent to `remainder` modulo `modulus`
+#[inline]
+fn first_congruent(range: Range, modulus: usize, remainder: usize) -> Option<usize> {
+    let first = if modulus == 0 {
+        remainder
+    } else {
+        let wanted = remainder % modulus;
+        let have = range.min % modulus;
+        if wanted >= have {
+            range.min.checked_add(wanted - have)?
+        } else {
+            range.min.checked_add(modulus - have + wanted)?
+        }
+    };
+
+    if range.contains(first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+/// The longest of `ranges`, preferring the earliest on ties
+fn longest<I: IntoIterator<Item = Range>>(ranges: I) -> Option<Range> {
+    let mut longest: Option<Range> = None;
+    for range in ranges {
+        if longest.is_none_or(|l| l.max - l.min < range.max - range.min) {
+            longest = Some(range);
+        }
     }
+    longest
 }
@@ -0,0 +1,82 @@
+/// Builds a FreeRanges from a list of free indices and ranges, such as
+/// `free_ranges![0..=4, 9, 20..30]`. Items may be single indices, inclusive
+/// ranges or exclusive ranges; empty ranges add nothing. Items that touch
+/// are merged, but items that overlap panic, since that is almost always
+/// a mistake in the list.
+///
+/// ```
+/// #[macro_use]
+/// extern crate free_ranges;
+///
+/// fn main() {
+///     let ranges = free_ranges![0..=4, 9, 20..30, 5,];
+///     assert_eq!(ranges.range_count(), 3);
+/// }
+/// ```
+///
+/// At most one trailing comma is accepted:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate free_ranges;
+///
+/// fn main() {
+///     let _ = free_ranges![0..=4, 9,,];
+/// }
+/// ```
+#[macro_export]
+macro_rules! free_ranges {
+    ($($item:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut ranges = $crate::FreeRanges::new();
+        $($crate::__macro_support::add(&mut ranges, $item);)*
+        ranges
+    }};
+}
+
+#[doc(hidden)]
+pub mod __macro_support {
+    use std::ops;
+
+    use {FreeRanges, Range, StrictFreeError};
+
+    pub trait Item {
+        fn into_range(self) -> Option<Range>;
+    }
+
+    impl Item for usize {
+        fn into_range(self) -> Option<Range> {
+            Some(Range::id(self))
+        }
+    }
+
+    impl Item for ops::RangeInclusive<usize> {
+        fn into_range(self) -> Option<Range> {
+            let (min, max) = self.into_inner();
+            if min <= max {
+                Some(Range { min, max })
+            } else {
+                None
+            }
+        }
+    }
+
+    impl Item for ops::Range<usize> {
+        fn into_range(self) -> Option<Range> {
+            Range::from_len(self.start, self.end.checked_sub(self.start)?)
+        }
+    }
+
+    pub fn add<I: Item>(ranges: &mut FreeRanges, item: I) {
+        if let Some(range) = item.into_range() {
+            match ranges.set_range_free_strict(range) {
+                Ok(()) => {}
+                Err(StrictFreeError::AlreadyFree(at)) => panic!(
+                    "free_ranges!: {:?} overlaps an earlier item at {:?}",
+                    range, at
+                ),
+                Err(StrictFreeError::EmptyRange(_)) => unreachable!(),
+            }
+        }
+    }
+}
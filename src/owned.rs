@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{FreeRanges, Range};
+
+/// A FreeRanges which records which owner every allocated range belongs
+/// to, so everything an owner holds can be freed at once. Every index is
+/// either free or owned by exactly one allocation.
+#[derive(Debug, Clone)]
+pub struct OwnedRanges<K> {
+    ranges: FreeRanges,
+    owned: BTreeMap<usize, (Range, K)>,
+}
+
+impl<K: PartialEq> OwnedRanges<K> {
+    /// Starts with `ranges` as the free space and nothing owned
+    #[inline]
+    pub fn new(ranges: FreeRanges) -> Self {
+        OwnedRanges {
+            ranges,
+            owned: BTreeMap::new(),
+        }
+    }
+
+    /// The free space which is left
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    /// Allocates `len` contiguous indices like `FreeRanges::allocate` and
+    /// records them as belonging to `owner`
+    pub fn allocate_for(&mut self, owner: K, len: usize) -> Option<Range> {
+        let range = self.ranges.allocate(len)?;
+        self.owned.insert(range.min, (range, owner));
+        Some(range)
+    }
+
+    /// Frees `range`, which has to be a whole allocation or its start or
+    /// end. Freeing the start or end leaves the rest of the allocation with
+    /// the same owner
+    pub fn free_range(&mut self, range: Range) -> Result<(), NotOwned> {
+        let error = NotOwned { range };
+        let (&start, &(allocation, _)) = self.owned.range(..=range.min).next_back().ok_or(error)?;
+        if range.empty() || !allocation.contains(range.min) || !allocation.contains(range.max) {
+            return Err(error);
+        }
+
+        let rest = match (range.min == allocation.min, range.max == allocation.max) {
+            (true, true) => None,
+            (true, false) => Some(Range {
+                min: range.max + 1,
+                max: allocation.max,
+            }),
+            (false, true) => Some(Range {
+                min: allocation.min,
+                max: range.min - 1,
+            }),
+            (false, false) => return Err(error),
+        };
+
+        let (_, owner) = self
+            .owned
+            .remove(&start)
+            .expect("allocation was just found");
+        if let Some(rest) = rest {
+            self.owned.insert(rest.min, (rest, owner));
+        }
+        self.ranges.set_range_free(range);
+        Ok(())
+    }
+
+    /// Frees every range belonging to `owner` and returns how many there
+    /// were. This walks every allocation
+    pub fn release_owner(&mut self, owner: &K) -> usize {
+        let starts: Vec<usize> = self
+            .owned
+            .iter()
+            .filter(|&(_, (_, k))| k == owner)
+            .map(|(&start, _)| start)
+            .collect();
+        for start in &starts {
+            let (range, _) = self.owned.remove(start).expect("allocation was just found");
+            self.ranges.set_range_free(range);
+        }
+        starts.len()
+    }
+
+    /// The owner of the allocation containing `index`, if it is allocated
+    pub fn owner_of(&self, index: usize) -> Option<&K> {
+        self.owned
+            .range(..=index)
+            .next_back()
+            .filter(|&(_, &(range, _))| range.contains(index))
+            .map(|(_, (_, owner))| owner)
+    }
+
+    /// Iterator over every allocated range and its owner, in address order
+    #[inline]
+    pub fn allocations(&self) -> impl Iterator<Item = (Range, &K)> {
+        self.owned.values().map(|(range, owner)| (*range, owner))
+    }
+}
+
+/// Returned by `OwnedRanges::free_range` when the range isn't a whole
+/// allocation or the start or end of one
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NotOwned {
+    pub range: Range,
+}
+
+impl fmt::Display for NotOwned {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "indices {:?} are not the start or end of an allocation",
+            self.range
+        )
+    }
+}
+
+impl std::error::Error for NotOwned {}
@@ -0,0 +1,110 @@
+use std::cmp;
+use std::collections::BTreeSet;
+
+use super::exclusion::Pieces;
+use super::{align_up, FreeRanges, Range};
+
+/// One allocation for `FreeRanges::plan` and `FreeRanges::allocate_request`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AllocRequest {
+    /// How many contiguous indices to allocate
+    pub len: usize,
+    /// What the first index has to be a multiple of. 0 and 1 both mean
+    /// any index will do
+    pub align: usize,
+    /// Where the block has to lie, or anywhere if None
+    pub window: Option<Range>,
+}
+
+impl AllocRequest {
+    /// A request for `len` indices anywhere
+    #[inline]
+    pub fn new(len: usize) -> Self {
+        AllocRequest {
+            len,
+            align: 1,
+            window: None,
+        }
+    }
+
+    /// Requires the block to start at a multiple of `align`
+    #[inline]
+    pub fn aligned(self, align: usize) -> Self {
+        AllocRequest { align, ..self }
+    }
+
+    /// Requires the block to lie inside `window`
+    #[inline]
+    pub fn within(self, window: Range) -> Self {
+        AllocRequest {
+            window: Some(window),
+            ..self
+        }
+    }
+}
+
+/// What `FreeRanges::plan` found
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlanResult {
+    /// Every request fits, at these blocks
+    Fits(Vec<Range>),
+    /// The request at `index` doesn't fit after placing the ones before it
+    Fails { index: usize },
+}
+
+impl FreeRanges {
+    /// Works out where `allocate_request` would put each of `requests` if
+    /// they were made in order, without changing the set. The blocks placed
+    /// so far are kept in a small overlay which hides them from the later
+    /// requests, so the set is never copied
+    pub fn plan(&self, requests: &[AllocRequest]) -> PlanResult {
+        let mut planned = BTreeSet::new();
+        let mut placements = Vec::with_capacity(requests.len());
+        for (index, request) in requests.iter().enumerate() {
+            match self.find_placement(request, &planned) {
+                Some(placement) => {
+                    planned.insert(placement);
+                    placements.push(placement);
+                }
+                None => return PlanResult::Fails { index },
+            }
+        }
+        PlanResult::Fits(placements)
+    }
+
+    /// Marks the lowest block satisfying `request` as used: the first
+    /// aligned start with room for `len` indices inside the window
+    pub fn allocate_request(&mut self, request: &AllocRequest) -> Option<Range> {
+        let placement = self.find_placement(request, &BTreeSet::new())?;
+        self.take(placement);
+        Some(placement)
+    }
+
+    /// The first block satisfying `request` which lies in the free space
+    /// outside of the excluded ranges and `planned`
+    fn find_placement(&self, request: &AllocRequest, planned: &BTreeSet<Range>) -> Option<Range> {
+        let span = request.len.checked_sub(1)?;
+        let window = request.window.unwrap_or(Range {
+            min: 0,
+            max: usize::MAX,
+        });
+        self.ranges_between(window.min, window.max)
+            .flat_map(|&range| {
+                let clipped = Range {
+                    min: cmp::max(range.min, window.min),
+                    max: cmp::min(range.max, window.max),
+                };
+                Pieces::new(clipped, &self.excluded)
+            })
+            .flat_map(|piece| Pieces::new(piece, planned))
+            .find_map(|piece| {
+                let min = align_up(piece.min, request.align)?;
+                let max = min.checked_add(span)?;
+                if max <= piece.max {
+                    Some(Range { min, max })
+                } else {
+                    None
+                }
+            })
+    }
+}
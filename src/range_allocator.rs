@@ -0,0 +1,104 @@
+use super::{
+    BoundedFreeRanges, CowFreeRanges, FreeRanges, HoldingFreeRanges, IndexedFreeRanges,
+    LazyFreeRanges, Range, TrackedFreeRanges,
+};
+
+/// The core operations of a FreeRanges, for code which wants to accept
+/// other implementations such as `BoundedFreeRanges` and the wrappers
+/// around a FreeRanges or, in tests, the `MockRangeAllocator` from
+/// `test_util`.
+///
+/// The trait is object safe, so `&mut dyn RangeAllocator` works too. That
+/// is why `free_ranges` returns a boxed iterator.
+pub trait RangeAllocator {
+    fn is_free(&self, index: usize) -> bool;
+
+    /// Marks `index` as free. Returns false if it already was
+    fn set_free(&mut self, index: usize) -> bool;
+
+    /// Marks `index` as used. Returns false if it was not free
+    fn set_used(&mut self, index: usize) -> bool;
+
+    /// Marks every index in `range` as free. Returns false if they all were
+    fn set_range_free(&mut self, range: Range) -> bool;
+
+    /// The lowest free index
+    fn first(&self) -> Option<usize>;
+
+    /// Marks the lowest free index as used and returns it
+    fn set_first_used(&mut self) -> Option<usize>;
+
+    /// Iterator over the free ranges, lowest first
+    fn free_ranges(&self) -> Box<dyn Iterator<Item = Range> + '_>;
+}
+
+/// Implements RangeAllocator for `$ty` by calling the methods of the same
+/// names on `$inherent`, which is `$ty` itself or the type it derefs to.
+/// `$free_ranges` builds the iterator from `$this`
+macro_rules! range_allocator {
+    ([$($generics:tt)*] $ty:ty => $inherent:ty, |$this:ident| $free_ranges:expr) => {
+        impl<$($generics)*> RangeAllocator for $ty {
+            #[inline]
+            fn is_free(&self, index: usize) -> bool {
+                <$inherent>::is_free(self, index)
+            }
+
+            #[inline]
+            fn set_free(&mut self, index: usize) -> bool {
+                <$inherent>::set_free(self, index)
+            }
+
+            #[inline]
+            fn set_used(&mut self, index: usize) -> bool {
+                <$inherent>::set_used(self, index)
+            }
+
+            #[inline]
+            fn set_range_free(&mut self, range: Range) -> bool {
+                <$inherent>::set_range_free(self, range)
+            }
+
+            #[inline]
+            fn first(&self) -> Option<usize> {
+                <$inherent>::first(self)
+            }
+
+            #[inline]
+            fn set_first_used(&mut self) -> Option<usize> {
+                <$inherent>::set_first_used(self)
+            }
+
+            #[inline]
+            fn free_ranges(&self) -> Box<dyn Iterator<Item = Range> + '_> {
+                let $this = self;
+                Box::new($free_ranges)
+            }
+        }
+    };
+}
+
+range_allocator!(
+    [] FreeRanges => FreeRanges,
+    |ranges| FreeRanges::free_ranges(ranges).cloned()
+);
+range_allocator!(
+    [const BOUND: usize] BoundedFreeRanges<BOUND> => BoundedFreeRanges<BOUND>,
+    |ranges| BoundedFreeRanges::free_ranges(ranges).cloned()
+);
+range_allocator!(
+    [] CowFreeRanges => FreeRanges,
+    |cow| FreeRanges::free_ranges(cow).cloned()
+);
+range_allocator!(
+    [] HoldingFreeRanges => HoldingFreeRanges,
+    |holding| holding.ranges().free_list.iter().cloned()
+);
+range_allocator!(
+    [] IndexedFreeRanges => IndexedFreeRanges,
+    |indexed| indexed.ranges().free_list.iter().cloned()
+);
+range_allocator!([] LazyFreeRanges => LazyFreeRanges, |lazy| LazyFreeRanges::free_ranges(lazy));
+range_allocator!(
+    [] TrackedFreeRanges => TrackedFreeRanges,
+    |tracked| tracked.ranges().free_list.iter().cloned()
+);
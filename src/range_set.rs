@@ -0,0 +1,303 @@
+use std::collections::btree_set;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::{self, FromIterator};
+use std::mem;
+use std::ops::RangeBounds;
+use std::option;
+use std::sync::OnceLock;
+
+use super::Range;
+
+static EMPTY: BTreeSet<Range> = BTreeSet::new();
+
+/// The sorted ranges behind a FreeRanges. Zero or one range is stored
+/// inline without allocating; inserting a second range moves them into a
+/// BTreeSet. Removing ranges keeps the tree, so a set which keeps going
+/// between one and two ranges doesn't rebuild it each time. The ranges only
+/// move back inline on `clear` or `shrink_to_fit`.
+///
+/// The public API hands out BTreeSet iterators and references, so `tree`
+/// builds a one-range tree for an inline set on demand. It is kept until
+/// the set next changes.
+pub(crate) enum RangeSet {
+    Inline(Option<Range>, OnceLock<BTreeSet<Range>>),
+    Tree(BTreeSet<Range>),
+}
+
+impl RangeSet {
+    #[inline]
+    pub fn new() -> Self {
+        RangeSet::inline(None)
+    }
+
+    #[inline]
+    fn inline(range: Option<Range>) -> Self {
+        RangeSet::Inline(range, OnceLock::new())
+    }
+
+    /// The ranges as a BTreeSet
+    #[inline]
+    pub fn tree(&self) -> &BTreeSet<Range> {
+        match *self {
+            RangeSet::Inline(None, _) => &EMPTY,
+            RangeSet::Inline(Some(range), ref tree) => {
+                tree.get_or_init(|| iter::once(range).collect())
+            }
+            RangeSet::Tree(ref tree) => tree,
+        }
+    }
+
+    #[inline]
+    pub fn into_tree(self) -> BTreeSet<Range> {
+        match self {
+            RangeSet::Inline(range, _) => range.into_iter().collect(),
+            RangeSet::Tree(tree) => tree,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        match *self {
+            RangeSet::Inline(range, _) => range.is_some() as usize,
+            RangeSet::Tree(ref tree) => tree.len(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn iter(&self) -> Iter<'_> {
+        match *self {
+            RangeSet::Inline(ref range, _) => Iter::Inline(range.as_ref().into_iter()),
+            RangeSet::Tree(ref tree) => Iter::Tree(tree.iter()),
+        }
+    }
+
+    /// The ranges within `bounds`, like `BTreeSet::range`
+    #[inline]
+    pub fn range<B: RangeBounds<Range>>(&self, bounds: B) -> Iter<'_> {
+        match *self {
+            RangeSet::Inline(ref range, _) => {
+                let within = range.as_ref().filter(|range| bounds.contains(range));
+                Iter::Inline(within.into_iter())
+            }
+            RangeSet::Tree(ref tree) => Iter::Range(tree.range(bounds)),
+        }
+    }
+
+    /// The range equal to (overlapping) `value`
+    #[inline]
+    pub fn get(&self, value: &Range) -> Option<&Range> {
+        match *self {
+            RangeSet::Inline(ref range, _) => range.as_ref().filter(|range| *range == value),
+            RangeSet::Tree(ref tree) => tree.get(value),
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, value: &Range) -> bool {
+        self.get(value).is_some()
+    }
+
+    /// Inserts `value` unless a range equal to (overlapping) it is already
+    /// there, returning whether it was inserted
+    pub fn insert(&mut self, value: Range) -> bool {
+        match *self {
+            RangeSet::Inline(None, _) => *self = RangeSet::inline(Some(value)),
+            RangeSet::Inline(Some(range), _) => {
+                if range == value {
+                    return false;
+                }
+                *self = RangeSet::Tree([range, value].iter().cloned().collect());
+            }
+            RangeSet::Tree(ref mut tree) => return tree.insert(value),
+        }
+        true
+    }
+
+    #[inline]
+    pub fn remove(&mut self, value: &Range) -> bool {
+        self.take(value).is_some()
+    }
+
+    /// Removes and returns the range equal to (overlapping) `value`
+    pub fn take(&mut self, value: &Range) -> Option<Range> {
+        match *self {
+            RangeSet::Inline(range, _) => {
+                let taken = range.filter(|range| range == value);
+                if taken.is_some() {
+                    self.clear();
+                }
+                taken
+            }
+            RangeSet::Tree(ref mut tree) => tree.take(value),
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        *self = RangeSet::new();
+    }
+
+    /// Moves every range of `other` into this set
+    pub fn append(&mut self, other: &mut RangeSet) {
+        match (self, mem::take(other)) {
+            (&mut RangeSet::Tree(ref mut tree), RangeSet::Tree(mut other)) => {
+                tree.append(&mut other)
+            }
+            (set, other) => set.extend(other),
+        }
+    }
+
+    /// Moves the ranges back inline if the tree is down to at most one
+    pub fn shrink_to_fit(&mut self) {
+        if let RangeSet::Tree(ref mut tree) = *self {
+            if tree.len() <= 1 {
+                let range = tree.pop_first();
+                *self = RangeSet::inline(range);
+            }
+        }
+    }
+}
+
+impl Default for RangeSet {
+    #[inline]
+    fn default() -> Self {
+        RangeSet::new()
+    }
+}
+
+impl Clone for RangeSet {
+    #[inline]
+    fn clone(&self) -> Self {
+        match *self {
+            RangeSet::Inline(range, _) => RangeSet::inline(range),
+            RangeSet::Tree(ref tree) => RangeSet::Tree(tree.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for RangeSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl From<BTreeSet<Range>> for RangeSet {
+    #[inline]
+    fn from(tree: BTreeSet<Range>) -> Self {
+        let mut set = RangeSet::Tree(tree);
+        set.shrink_to_fit();
+        set
+    }
+}
+
+impl Extend<Range> for RangeSet {
+    fn extend<I: IntoIterator<Item = Range>>(&mut self, ranges: I) {
+        for range in ranges {
+            self.insert(range);
+        }
+    }
+}
+
+impl FromIterator<Range> for RangeSet {
+    fn from_iter<I: IntoIterator<Item = Range>>(ranges: I) -> Self {
+        let mut set = RangeSet::new();
+        set.extend(ranges);
+        set
+    }
+}
+
+impl IntoIterator for RangeSet {
+    type Item = Range;
+    type IntoIter = IntoIter;
+
+    #[inline]
+    fn into_iter(self) -> IntoIter {
+        match self {
+            RangeSet::Inline(range, _) => IntoIter::Inline(range.into_iter()),
+            RangeSet::Tree(tree) => IntoIter::Tree(tree.into_iter()),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a RangeSet {
+    type Item = &'a Range;
+    type IntoIter = Iter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the ranges of a RangeSet, or a part of them
+#[derive(Clone)]
+pub(crate) enum Iter<'a> {
+    Inline(option::IntoIter<&'a Range>),
+    Tree(btree_set::Iter<'a, Range>),
+    Range(btree_set::Range<'a, Range>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Range;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a Range> {
+        match *self {
+            Iter::Inline(ref mut iter) => iter.next(),
+            Iter::Tree(ref mut iter) => iter.next(),
+            Iter::Range(ref mut iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            Iter::Inline(ref iter) => iter.size_hint(),
+            Iter::Tree(ref iter) => iter.size_hint(),
+            Iter::Range(ref iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a Range> {
+        match *self {
+            Iter::Inline(ref mut iter) => iter.next_back(),
+            Iter::Tree(ref mut iter) => iter.next_back(),
+            Iter::Range(ref mut iter) => iter.next_back(),
+        }
+    }
+}
+
+/// Owning iterator over the ranges of a RangeSet
+pub(crate) enum IntoIter {
+    Inline(option::IntoIter<Range>),
+    Tree(btree_set::IntoIter<Range>),
+}
+
+impl Iterator for IntoIter {
+    type Item = Range;
+
+    #[inline]
+    fn next(&mut self) -> Option<Range> {
+        match *self {
+            IntoIter::Inline(ref mut iter) => iter.next(),
+            IntoIter::Tree(ref mut iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            IntoIter::Inline(ref iter) => iter.size_hint(),
+            IntoIter::Tree(ref iter) => iter.size_hint(),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::cmp;
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+use super::{FreeRanges, Range};
+
+impl FreeRanges {
+    /// The indices free in either set.
+    ///
+    /// Like the other set operations, this only combines the free indices:
+    /// the result keeps the barriers, exclusions and preference tiers of
+    /// `self`, with its ranges cut at those barriers, and ignores the ones
+    /// of `other`
+    #[inline]
+    pub fn union(&self, other: &FreeRanges) -> FreeRanges {
+        combine(self, other, |a, b| a || b)
+    }
+
+    /// The indices free in both sets
+    #[inline]
+    pub fn intersection(&self, other: &FreeRanges) -> FreeRanges {
+        combine(self, other, |a, b| a && b)
+    }
+
+    /// The indices free in this set but not in `other`
+    #[inline]
+    pub fn difference(&self, other: &FreeRanges) -> FreeRanges {
+        combine(self, other, |a, b| a && !b)
+    }
+
+    /// The indices free in exactly one of the sets, which are the ones
+    /// whose state differs between them
+    #[inline]
+    pub fn symmetric_difference(&self, other: &FreeRanges) -> FreeRanges {
+        combine(self, other, |a, b| a != b)
+    }
+}
+
+macro_rules! set_operator {
+    ($trait:ident, $fn:ident, $method:ident) => {
+        impl<'a, 'b> $trait<&'b FreeRanges> for &'a FreeRanges {
+            type Output = FreeRanges;
+
+            #[inline]
+            fn $fn(self, other: &'b FreeRanges) -> FreeRanges {
+                self.$method(other)
+            }
+        }
+
+        impl<'b> $trait<&'b FreeRanges> for FreeRanges {
+            type Output = FreeRanges;
+
+            #[inline]
+            fn $fn(self, other: &'b FreeRanges) -> FreeRanges {
+                self.$method(other)
+            }
+        }
+
+        impl<'a> $trait<FreeRanges> for &'a FreeRanges {
+            type Output = FreeRanges;
+
+            #[inline]
+            fn $fn(self, other: FreeRanges) -> FreeRanges {
+                self.$method(&other)
+            }
+        }
+
+        impl $trait<FreeRanges> for FreeRanges {
+            type Output = FreeRanges;
+
+            #[inline]
+            fn $fn(self, other: FreeRanges) -> FreeRanges {
+                self.$method(&other)
+            }
+        }
+    };
+}
+
+set_operator!(BitOr, bitor, union);
+set_operator!(BitAnd, bitand, intersection);
+set_operator!(Sub, sub, difference);
+set_operator!(BitXor, bitxor, symmetric_difference);
+
+/// Where each range of `ranges` starts and ends, as `(index, +1)` at its
+/// min and `(index, -1)` just past its max, in ascending order
+fn edges(ranges: &FreeRanges) -> impl Iterator<Item = (u128, i32)> + '_ {
+    ranges
+        .free_list
+        .iter()
+        .flat_map(|range| [(range.min as u128, 1), (range.max as u128 + 1, -1)])
+}
+
+/// Sweeps the edges of both sets in a single lockstep pass, keeping the
+/// indices for which `keep` holds given whether each set has them free.
+/// Ranges split by a barrier are treated as one, and the result is cut at
+/// the barriers of `a` again
+fn combine(a: &FreeRanges, b: &FreeRanges, keep: fn(bool, bool) -> bool) -> FreeRanges {
+    let mut a_edges = edges(a).peekable();
+    let mut b_edges = edges(b).peekable();
+    let (mut in_a, mut in_b) = (0, 0);
+    let mut start = None;
+    let mut result = FreeRanges {
+        barriers: a.barriers.clone(),
+        excluded: a.excluded.clone(),
+        tiers: a.tiers.clone(),
+        ..FreeRanges::new()
+    };
+    loop {
+        let at = match (a_edges.peek(), b_edges.peek()) {
+            (Some(&(x, _)), Some(&(y, _))) => cmp::min(x, y),
+            (Some(&(x, _)), None) | (None, Some(&(x, _))) => x,
+            (None, None) => break,
+        };
+        while let Some((_, delta)) = a_edges.next_if(|&(x, _)| x == at) {
+            in_a += delta;
+        }
+        while let Some((_, delta)) = b_edges.next_if(|&(x, _)| x == at) {
+            in_b += delta;
+        }
+
+        match (start, keep(in_a > 0, in_b > 0)) {
+            (None, true) => start = Some(at),
+            (Some(min), false) => {
+                result.insert_cut(Range {
+                    min: min as usize,
+                    max: (at - 1) as usize,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    result
+}
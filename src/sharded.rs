@@ -0,0 +1,190 @@
+use std::cmp;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use super::{FreeRanges, Range};
+
+/// A FreeRanges split into shards which each own a slice of the domain
+/// behind their own lock, so threads working in different shards don't
+/// contend.
+///
+/// Each call locks at most one shard at a time, always in ascending shard
+/// order, so calls can't deadlock each other. Allocations try the caller's
+/// preferred shard first and then steal from the others, but a block never
+/// spans two shards: a request only succeeds if one shard can hold it.
+#[derive(Debug)]
+pub struct ShardedFreeRanges {
+    domain: Range,
+    shards: Vec<Shard>,
+}
+
+#[derive(Debug)]
+struct Shard {
+    bounds: Range,
+    ranges: Mutex<FreeRanges>,
+    free: AtomicUsize,
+}
+
+impl ShardedFreeRanges {
+    /// Splits `domain` into `shard_count` shards of as equal a size as
+    /// possible, with every index free.
+    ///
+    /// Panics if `shard_count` is 0 or larger than the domain.
+    pub fn new(domain: Range, shard_count: usize) -> Self {
+        let len = (domain.max - domain.min) as u128 + 1;
+        assert!(shard_count > 0, "a sharded set needs at least one shard");
+        assert!(
+            shard_count as u128 <= len,
+            "{} shards don't fit in a domain of {} indices",
+            shard_count,
+            len
+        );
+
+        let base = len / shard_count as u128;
+        let extra = len % shard_count as u128;
+        let mut min = domain.min;
+        let shards = (0..shard_count as u128)
+            .map(|i| {
+                let shard_len = base + (i < extra) as u128;
+                let bounds = Range {
+                    min,
+                    max: min + (shard_len - 1) as usize,
+                };
+                min = bounds.max.wrapping_add(1);
+                Shard {
+                    bounds,
+                    ranges: Mutex::new(FreeRanges::with_initial_range(bounds)),
+                    free: AtomicUsize::new(cmp::min(shard_len, usize::MAX as u128) as usize),
+                }
+            })
+            .collect();
+
+        ShardedFreeRanges { domain, shards }
+    }
+
+    #[inline]
+    pub fn domain(&self) -> Range {
+        self.domain
+    }
+
+    #[inline]
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The indices owned by each shard, in order
+    #[inline]
+    pub fn shard_bounds(&self) -> impl Iterator<Item = Range> + '_ {
+        self.shards.iter().map(|shard| shard.bounds)
+    }
+
+    /// The shard owning `index`, if it is inside the domain
+    pub fn shard_of(&self, index: usize) -> Option<usize> {
+        if !self.domain.contains(index) {
+            return None;
+        }
+        self.shards
+            .binary_search_by(|shard| shard.bounds.cmp(&Range::id(index)))
+            .ok()
+    }
+
+    /// Allocates `size` contiguous indices like `FreeRanges::allocate`,
+    /// trying shard `preferred % shard_count` first and then every other
+    /// shard after it in turn
+    pub fn allocate(&self, preferred: usize, size: usize) -> Option<Range> {
+        let count = self.shards.len();
+        (0..count)
+            .map(|i| &self.shards[(preferred % count + i) % count])
+            .filter(|shard| shard.free.load(Ordering::Relaxed) >= size)
+            .filter_map(|shard| {
+                let range = lock(shard).allocate(size)?;
+                shard.free.fetch_sub(size, Ordering::Relaxed);
+                Some(range)
+            })
+            .next()
+    }
+
+    /// Marks a single free index as used and returns it, trying shards in
+    /// the same order as `allocate`
+    #[inline]
+    pub fn set_first_used(&self, preferred: usize) -> Option<usize> {
+        self.allocate(preferred, 1).map(|range| range.min)
+    }
+
+    /// Frees `index` in the shard owning it. Returns false if it was
+    /// already free or outside the domain
+    pub fn set_free(&self, index: usize) -> bool {
+        let shard = match self.shard_of(index) {
+            Some(shard) => &self.shards[shard],
+            None => return false,
+        };
+        let freed = lock(shard).set_free(index);
+        if freed {
+            shard.free.fetch_add(1, Ordering::Relaxed);
+        }
+        freed
+    }
+
+    /// Marks `index` as used in the shard owning it. Returns false if it
+    /// was not free or is outside the domain
+    pub fn set_used(&self, index: usize) -> bool {
+        let shard = match self.shard_of(index) {
+            Some(shard) => &self.shards[shard],
+            None => return false,
+        };
+        let used = lock(shard).set_used(index);
+        if used {
+            shard.free.fetch_sub(1, Ordering::Relaxed);
+        }
+        used
+    }
+
+    /// Frees every index of `range` inside the domain, one shard at a time.
+    /// Ranges are never merged across shard boundaries
+    pub fn set_range_free(&self, range: Range) {
+        for shard in &self.shards {
+            let part = Range {
+                min: cmp::max(range.min, shard.bounds.min),
+                max: cmp::min(range.max, shard.bounds.max),
+            };
+            if part.empty() {
+                continue;
+            }
+            let mut ranges = lock(shard);
+            let newly_freed = ranges.window_report(part).used;
+            ranges.set_range_free(part);
+            shard.free.fetch_add(newly_freed, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        self.shard_of(index)
+            .is_some_and(|shard| lock(&self.shards[shard]).is_free(index))
+    }
+
+    /// The number of free indices across every shard. This reads each
+    /// shard's counter without locking, so it may be off by the operations
+    /// in flight while it runs. Counts saturate at usize::MAX
+    pub fn total_free(&self) -> usize {
+        self.shards.iter().fold(0usize, |total, shard| {
+            total.saturating_add(shard.free.load(Ordering::Relaxed))
+        })
+    }
+
+    /// The number of free indices in each shard, read the same way as
+    /// `total_free`
+    pub fn shard_free(&self) -> Vec<usize> {
+        self.shards
+            .iter()
+            .map(|shard| shard.free.load(Ordering::Relaxed))
+            .collect()
+    }
+}
+
+fn lock(shard: &Shard) -> MutexGuard<'_, FreeRanges> {
+    shard
+        .ranges
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,153 @@
+use std::ops::RangeInclusive;
+
+use super::{FreeRanges, Range};
+
+/// Flipping the sign bit maps isize::MIN..=isize::MAX onto 0..=usize::MAX
+/// without changing the order or the distance between indices
+const SIGN_BIT: usize = 1 << (usize::BITS - 1);
+
+/// A FreeRanges over signed indices, for positions relative to an origin.
+///
+/// Each index is stored with its sign bit flipped, so the order is kept,
+/// -1 and 0 are adjacent and merge like any other neighbours, and
+/// isize::MIN and isize::MAX are the ends of the domain.
+#[derive(Debug, Clone, Default)]
+pub struct SignedFreeRanges {
+    ranges: FreeRanges,
+}
+
+impl SignedFreeRanges {
+    /// Starts empty with no indices free
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts with isize::MIN..=isize::MAX free
+    #[inline]
+    pub fn with_all_free() -> Self {
+        SignedFreeRanges {
+            ranges: FreeRanges::with_all_free(),
+        }
+    }
+
+    /// Starts with `range` free
+    pub fn with_initial_range(range: RangeInclusive<isize>) -> Self {
+        let mut signed = SignedFreeRanges::new();
+        signed.set_range_free(range);
+        signed
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: isize) -> bool {
+        self.ranges.is_free(to_unsigned(index))
+    }
+
+    /// Marks `index` as free. Returns false if it was already free
+    #[inline]
+    pub fn set_free(&mut self, index: isize) -> bool {
+        self.ranges.set_free(to_unsigned(index))
+    }
+
+    /// Marks every index in `range` as free. Returns false if they were
+    /// all free already or the range is empty
+    pub fn set_range_free(&mut self, range: RangeInclusive<isize>) -> bool {
+        match to_unsigned_range(range) {
+            Some(range) => self.ranges.set_range_free(range),
+            None => false,
+        }
+    }
+
+    /// Marks a free index as used. Returns false if it was not free
+    #[inline]
+    pub fn set_used(&mut self, index: isize) -> bool {
+        self.ranges.set_used(to_unsigned(index))
+    }
+
+    /// Marks every index in `range` as used. Returns false if none of them
+    /// were free
+    pub fn set_range_used(&mut self, range: RangeInclusive<isize>) -> bool {
+        match to_unsigned_range(range) {
+            Some(range) => self.ranges.set_range_used(range),
+            None => false,
+        }
+    }
+
+    /// The lowest free index
+    #[inline]
+    pub fn first(&self) -> Option<isize> {
+        self.ranges.first().map(to_signed)
+    }
+
+    /// Marks the lowest free index as used and returns it
+    #[inline]
+    pub fn set_first_used(&mut self) -> Option<isize> {
+        self.ranges.set_first_used().map(to_signed)
+    }
+
+    /// The highest free index
+    #[inline]
+    pub fn last(&self) -> Option<isize> {
+        self.ranges.last().map(to_signed)
+    }
+
+    /// Marks the highest free index as used and returns it
+    #[inline]
+    pub fn set_last_used(&mut self) -> Option<isize> {
+        self.ranges.set_last_used().map(to_signed)
+    }
+
+    /// Marks the first `size` contiguous free indices as used and returns
+    /// them, like `FreeRanges::allocate`
+    #[inline]
+    pub fn allocate(&mut self, size: usize) -> Option<RangeInclusive<isize>> {
+        self.ranges.allocate(size).map(to_signed_range)
+    }
+
+    /// Iterator over the free ranges, lowest first
+    pub fn free_ranges(&self) -> impl Iterator<Item = RangeInclusive<isize>> + '_ {
+        self.ranges
+            .free_list
+            .iter()
+            .map(|&range| to_signed_range(range))
+    }
+
+    /// The number of contiguous free ranges
+    #[inline]
+    pub fn range_count(&self) -> usize {
+        self.ranges.range_count()
+    }
+
+    /// Marks every index as used
+    #[inline]
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+}
+
+#[inline]
+fn to_unsigned(index: isize) -> usize {
+    index as usize ^ SIGN_BIT
+}
+
+#[inline]
+fn to_signed(index: usize) -> isize {
+    (index ^ SIGN_BIT) as isize
+}
+
+#[inline]
+fn to_unsigned_range(range: RangeInclusive<isize>) -> Option<Range> {
+    let (min, max) = range.into_inner();
+    if min > max {
+        return None;
+    }
+    Some(Range {
+        min: to_unsigned(min),
+        max: to_unsigned(max),
+    })
+}
+
+#[inline]
+fn to_signed_range(range: Range) -> RangeInclusive<isize> {
+    to_signed(range.min)..=to_signed(range.max)
+}
@@ -0,0 +1,135 @@
+use std::iter::repeat_with;
+
+use super::{FreeRanges, Range};
+
+/// A slab of values stored at stable indices, with the free slots tracked
+/// by a FreeRanges so new values always land in the lowest free slot
+#[derive(Debug, Clone)]
+pub struct SlotPool<T> {
+    slots: Vec<Option<T>>,
+    free: FreeRanges,
+    len: usize,
+}
+
+impl<T> Default for SlotPool<T> {
+    fn default() -> Self {
+        SlotPool::new()
+    }
+}
+
+impl<T> SlotPool<T> {
+    /// Starts with no slots
+    #[inline]
+    pub fn new() -> Self {
+        SlotPool {
+            slots: Vec::new(),
+            free: FreeRanges::new(),
+            len: 0,
+        }
+    }
+
+    /// Starts with `capacity` empty slots
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut pool = SlotPool::new();
+        pool.grow_to(capacity);
+        pool
+    }
+
+    /// The number of values stored
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of slots, occupied or not
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// The empty slots
+    #[inline]
+    pub fn free_slots(&self) -> &FreeRanges {
+        &self.free
+    }
+
+    /// Stores `value` in the lowest empty slot, adding slots if there are
+    /// none, and returns its index
+    pub fn insert(&mut self, value: T) -> usize {
+        let index = match self.free.set_first_used() {
+            Some(index) => index,
+            None => {
+                let index = self.slots.len();
+                self.grow_to(std::cmp::max(index * 2, 4));
+                self.free.set_used(index);
+                index
+            }
+        };
+
+        self.slots[index] = Some(value);
+        self.len += 1;
+        index
+    }
+
+    /// Takes the value out of slot `index`, leaving the slot empty
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let value = self.slots.get_mut(index)?.take()?;
+        self.free.set_free(index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.slots.get(index)?.as_ref()
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.slots.get_mut(index)?.as_mut()
+    }
+
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool {
+        self.get(index).is_some()
+    }
+
+    /// Iterator over the occupied slots and their values, in index order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.as_ref().map(|value| (index, value)))
+    }
+
+    /// Drops the run of empty slots at the end, if any, and releases the
+    /// spare memory behind them
+    pub fn shrink_to_fit(&mut self) {
+        let end = self.slots.len();
+        if let Some(last) = self.free.free_list.iter().next_back().cloned() {
+            if last.max + 1 == end {
+                self.free.remove_last_contiguous();
+                self.slots.truncate(last.min);
+            }
+        }
+        self.slots.shrink_to_fit();
+    }
+
+    fn grow_to(&mut self, capacity: usize) {
+        let old = self.slots.len();
+        if capacity <= old {
+            return;
+        }
+
+        self.slots.extend(repeat_with(|| None).take(capacity - old));
+        self.free.set_range_free(Range {
+            min: old,
+            max: capacity - 1,
+        });
+    }
+}
@@ -0,0 +1,77 @@
+use std::ops::{Deref, DerefMut};
+
+use super::{FreeRanges, Range};
+
+/// A block taken out of a parent FreeRanges to be sub-allocated on its own.
+///
+/// It derefs to a FreeRanges which starts with the whole block free, so it
+/// can be allocated from and carved further like any other set. `close`
+/// consumes it, and it can't be cloned, so it can't be given back twice.
+#[derive(Debug)]
+pub struct SubPool {
+    range: Range,
+    ranges: FreeRanges,
+}
+
+impl FreeRanges {
+    /// Takes `len` contiguous indices like `allocate` and hands them to a
+    /// SubPool. They stay used here until the sub-pool is closed
+    pub fn carve(&mut self, len: usize) -> Option<SubPool> {
+        let range = self.allocate(len)?;
+        Some(SubPool {
+            range,
+            ranges: FreeRanges::with_initial_range(range),
+        })
+    }
+}
+
+impl SubPool {
+    /// The block taken from the parent
+    #[inline]
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    /// Gives the indices still free in the sub-pool back to `parent`, where
+    /// they merge with their neighbours. Indices allocated from the
+    /// sub-pool stay used in the parent, and anything freed in it outside
+    /// the block is dropped rather than handed over.
+    ///
+    /// The block must still be entirely used in `parent`. If any of it is
+    /// free there, this is the wrong parent and the sub-pool is returned
+    /// untouched.
+    // The sub-pool is handed back by value so it can be closed again
+    #[allow(clippy::result_large_err)]
+    pub fn close(self, parent: &mut FreeRanges) -> Result<(), SubPool> {
+        if parent
+            .ranges_between(self.range.min, self.range.max)
+            .next()
+            .is_some()
+        {
+            return Err(self);
+        }
+        for &range in self.ranges.ranges_between(self.range.min, self.range.max) {
+            parent.set_range_free(Range {
+                min: range.min.max(self.range.min),
+                max: range.max.min(self.range.max),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Deref for SubPool {
+    type Target = FreeRanges;
+
+    #[inline]
+    fn deref(&self) -> &FreeRanges {
+        &self.ranges
+    }
+}
+
+impl DerefMut for SubPool {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut FreeRanges {
+        &mut self.ranges
+    }
+}
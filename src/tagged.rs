@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+
+use super::{FreeRanges, Range};
+
+/// A FreeRanges which remembers a tag for every block it hands out,
+/// giving the tag back when the block is freed
+#[derive(Debug, Clone)]
+pub struct TaggedFreeRanges<T> {
+    ranges: FreeRanges,
+    tags: BTreeMap<usize, (Range, T)>,
+}
+
+impl<T> TaggedFreeRanges<T> {
+    /// Starts with `ranges` as the free space and nothing allocated
+    #[inline]
+    pub fn new(ranges: FreeRanges) -> Self {
+        TaggedFreeRanges {
+            ranges,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    /// The free space which is left
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    /// Allocates `size` contiguous indices like `FreeRanges::allocate`,
+    /// storing `tag` under the start of the block
+    pub fn alloc_tagged(&mut self, size: usize, tag: T) -> Option<Range> {
+        let range = self.ranges.allocate(size)?;
+        self.tags.insert(range.min, (range, tag));
+        Some(range)
+    }
+
+    /// Frees the block starting at `start` and returns its tag, or None if
+    /// no tagged block starts there
+    pub fn free_tagged(&mut self, start: usize) -> Option<T> {
+        let (range, tag) = self.tags.remove(&start)?;
+        self.ranges.set_range_free(range);
+        Some(tag)
+    }
+
+    /// The block starting at `start` and its tag
+    #[inline]
+    pub fn get(&self, start: usize) -> Option<(Range, &T)> {
+        self.tags.get(&start).map(|(range, tag)| (*range, tag))
+    }
+
+    /// Iterator over every allocated block and its tag, in address order
+    #[inline]
+    pub fn allocations(&self) -> impl Iterator<Item = (Range, &T)> {
+        self.tags.values().map(|(range, tag)| (*range, tag))
+    }
+}
@@ -0,0 +1,175 @@
+//! Helpers for testing code built on top of FreeRanges. Only available
+//! with the `test-util` feature.
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, VecDeque};
+
+use super::{FreeRanges, Range, RangeAllocator};
+
+/// The simplest possible model of a FreeRanges: a set holding every free
+/// index. Every operation is linear in the number of indices involved, so
+/// keep the domain small.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NaiveFreeSet {
+    free: BTreeSet<usize>,
+}
+
+impl NaiveFreeSet {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Marks a specific index as free. Returns false if it already was
+    #[inline]
+    pub fn set_free(&mut self, index: usize) -> bool {
+        self.free.insert(index)
+    }
+
+    /// Marks an index as used. Returns false if the index was not free
+    #[inline]
+    pub fn set_used(&mut self, index: usize) -> bool {
+        self.free.remove(&index)
+    }
+
+    /// Marks every index in `range` as free. Returns false if they all were
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        let mut changed = false;
+        for index in range.min..=range.max {
+            changed |= self.free.insert(index);
+        }
+        changed
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        self.free.contains(&index)
+    }
+
+    /// The free indices merged into contiguous ranges, in order
+    pub fn free_ranges(&self) -> Vec<Range> {
+        let mut ranges: Vec<Range> = Vec::new();
+        for &index in &self.free {
+            match ranges.last_mut() {
+                Some(last) if last.max + 1 == index => last.max = index,
+                _ => ranges.push(Range::id(index)),
+            }
+        }
+        ranges
+    }
+}
+
+/// Panics with both sets of ranges if `ranges` and `model` do not hold
+/// exactly the same free indices
+pub fn assert_equivalent(ranges: &FreeRanges, model: &NaiveFreeSet) {
+    let actual: Vec<(usize, usize)> = ranges.free_ranges().map(|r| (r.min, r.max)).collect();
+    let expected: Vec<(usize, usize)> =
+        model.free_ranges().iter().map(|r| (r.min, r.max)).collect();
+    assert_eq!(
+        actual, expected,
+        "FreeRanges (left) differs from the naive model (right)"
+    );
+}
+
+/// A call made to a `MockRangeAllocator`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Call {
+    IsFree(usize),
+    SetFree(usize),
+    SetUsed(usize),
+    SetRangeFree(Range),
+    First,
+    SetFirstUsed,
+    FreeRanges,
+}
+
+/// A RangeAllocator which records every call made to it and answers from
+/// a script instead of tracking anything. Calls returning a bool take the
+/// next scripted bool, calls returning an index take the next scripted
+/// index, and `free_ranges` always returns the scripted ranges. Running
+/// out of scripted answers panics, naming the call.
+#[derive(Debug, Clone, Default)]
+pub struct MockRangeAllocator {
+    calls: RefCell<Vec<Call>>,
+    bools: RefCell<VecDeque<bool>>,
+    indices: RefCell<VecDeque<Option<usize>>>,
+    ranges: Vec<Range>,
+}
+
+impl MockRangeAllocator {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Queues the answer for the next call returning a bool
+    pub fn push_bool(&mut self, answer: bool) -> &mut Self {
+        self.bools.get_mut().push_back(answer);
+        self
+    }
+
+    /// Queues the answer for the next call returning an index
+    pub fn push_index(&mut self, answer: Option<usize>) -> &mut Self {
+        self.indices.get_mut().push_back(answer);
+        self
+    }
+
+    /// Sets what `free_ranges` yields
+    pub fn set_free_ranges(&mut self, ranges: Vec<Range>) -> &mut Self {
+        self.ranges = ranges;
+        self
+    }
+
+    /// Every call made so far, oldest first
+    #[inline]
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.borrow().clone()
+    }
+
+    fn answer_bool(&self, call: Call) -> bool {
+        self.calls.borrow_mut().push(call);
+        match self.bools.borrow_mut().pop_front() {
+            Some(answer) => answer,
+            None => panic!("no scripted answer left for {:?}", call),
+        }
+    }
+
+    fn answer_index(&self, call: Call) -> Option<usize> {
+        self.calls.borrow_mut().push(call);
+        match self.indices.borrow_mut().pop_front() {
+            Some(answer) => answer,
+            None => panic!("no scripted answer left for {:?}", call),
+        }
+    }
+}
+
+impl RangeAllocator for MockRangeAllocator {
+    fn is_free(&self, index: usize) -> bool {
+        self.answer_bool(Call::IsFree(index))
+    }
+
+    fn set_free(&mut self, index: usize) -> bool {
+        self.answer_bool(Call::SetFree(index))
+    }
+
+    fn set_used(&mut self, index: usize) -> bool {
+        self.answer_bool(Call::SetUsed(index))
+    }
+
+    fn set_range_free(&mut self, range: Range) -> bool {
+        self.answer_bool(Call::SetRangeFree(range))
+    }
+
+    fn first(&self) -> Option<usize> {
+        self.answer_index(Call::First)
+    }
+
+    fn set_first_used(&mut self) -> Option<usize> {
+        self.answer_index(Call::SetFirstUsed)
+    }
+
+    fn free_ranges(&self) -> Box<dyn Iterator<Item = Range> + '_> {
+        self.calls.borrow_mut().push(Call::FreeRanges);
+        Box::new(self.ranges.iter().cloned())
+    }
+}
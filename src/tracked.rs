@@ -0,0 +1,218 @@
+use std::cmp;
+#[cfg(feature = "tracked-ranges")]
+use std::collections::btree_set::Iter;
+#[cfg(feature = "tracked-ranges")]
+use std::collections::BTreeSet;
+use std::thread;
+
+use super::{FreeRanges, Range};
+
+/// A FreeRanges which counts the blocks handed out by its allocation
+/// methods until they are freed, to find leaks and to measure peak usage.
+///
+/// By default only the number of outstanding blocks and indices is kept,
+/// which assumes each free releases one whole block: a free which releases
+/// any used index ends one block, and the total drops by the number of
+/// indices it releases. Freeing part of a block or several blocks at once
+/// leaves the count off by the difference.
+///
+/// With the `tracked-ranges` feature the blocks themselves are recorded,
+/// at a cost of memory proportional to their number. Frees then don't have
+/// to match the allocations: freeing part of a block keeps the rest of it
+/// outstanding as one or two smaller blocks, and freeing indices which were
+/// never allocated here only frees them.
+#[derive(Debug, Clone)]
+pub struct TrackedFreeRanges {
+    ranges: FreeRanges,
+    #[cfg(feature = "tracked-ranges")]
+    outstanding: BTreeSet<Range>,
+    outstanding_count: usize,
+    outstanding_total: usize,
+    high_water_mark: usize,
+    panic_on_drop: bool,
+}
+
+impl TrackedFreeRanges {
+    /// Starts with `ranges` as the free space and nothing outstanding
+    #[inline]
+    pub fn new(ranges: FreeRanges) -> Self {
+        TrackedFreeRanges {
+            ranges,
+            #[cfg(feature = "tracked-ranges")]
+            outstanding: BTreeSet::new(),
+            outstanding_count: 0,
+            outstanding_total: 0,
+            high_water_mark: 0,
+            panic_on_drop: false,
+        }
+    }
+
+    /// Makes dropping the set panic while anything is outstanding, unless
+    /// the thread is already panicking
+    #[inline]
+    pub fn set_panic_on_drop(&mut self, panic_on_drop: bool) {
+        self.panic_on_drop = panic_on_drop;
+    }
+
+    /// The free space which is left
+    #[inline]
+    pub fn ranges(&self) -> &FreeRanges {
+        &self.ranges
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        self.ranges.is_free(index)
+    }
+
+    #[inline]
+    pub fn first(&self) -> Option<usize> {
+        self.ranges.first()
+    }
+
+    /// Allocates like `FreeRanges::allocate` and records the block
+    pub fn allocate(&mut self, size: usize) -> Option<Range> {
+        let range = self.ranges.allocate(size)?;
+        self.record(range);
+        Some(range)
+    }
+
+    /// Marks the first free index as used like `FreeRanges::set_first_used`
+    /// and records it as a block of one
+    pub fn set_first_used(&mut self) -> Option<usize> {
+        let index = self.ranges.set_first_used()?;
+        self.record(Range::id(index));
+        Some(index)
+    }
+
+    /// Marks `index` as used and records it as a block of one if it was
+    /// free
+    pub fn set_used(&mut self, index: usize) -> bool {
+        let used = self.ranges.set_used(index);
+        if used {
+            self.record(Range::id(index));
+        }
+        used
+    }
+
+    /// Frees `index`, taking it out of the block it belongs to
+    pub fn set_free(&mut self, index: usize) -> bool {
+        self.forget(Range::id(index));
+        self.ranges.set_free(index)
+    }
+
+    /// Frees `range`, taking it out of every block it overlaps
+    pub fn set_range_free(&mut self, range: Range) -> bool {
+        self.forget(range);
+        self.ranges.set_range_free(range)
+    }
+
+    /// The number of outstanding blocks
+    #[inline]
+    pub fn outstanding_count(&self) -> usize {
+        self.outstanding_count
+    }
+
+    /// The number of outstanding indices
+    #[inline]
+    pub fn outstanding_total(&self) -> usize {
+        self.outstanding_total
+    }
+
+    /// The largest number of indices that have been outstanding at once
+    #[inline]
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
+    /// Iterator over the outstanding blocks, lowest first
+    #[cfg(feature = "tracked-ranges")]
+    #[inline]
+    pub fn outstanding_ranges(&self) -> Iter<'_, Range> {
+        self.outstanding.iter()
+    }
+
+    /// Panics if there are any outstanding blocks, listing them with the
+    /// `tracked-ranges` feature
+    #[cfg(not(feature = "tracked-ranges"))]
+    pub fn assert_no_outstanding(&self) {
+        assert!(
+            self.outstanding_count == 0,
+            "{} blocks of {} indices are still outstanding",
+            self.outstanding_count,
+            self.outstanding_total
+        );
+    }
+
+    /// Panics if there are any outstanding blocks, listing them with the
+    /// `tracked-ranges` feature
+    #[cfg(feature = "tracked-ranges")]
+    pub fn assert_no_outstanding(&self) {
+        assert!(
+            self.outstanding.is_empty(),
+            "{} blocks are still outstanding: {:?}",
+            self.outstanding.len(),
+            self.outstanding
+        );
+    }
+
+    fn record(&mut self, range: Range) {
+        #[cfg(feature = "tracked-ranges")]
+        self.outstanding.insert(range);
+        self.outstanding_count += 1;
+        self.outstanding_total += range.max - range.min + 1;
+        self.high_water_mark = cmp::max(self.high_water_mark, self.outstanding_total);
+    }
+
+    /// Counts the indices in `range` which are used, before they are freed,
+    /// as the end of one block
+    #[cfg(not(feature = "tracked-ranges"))]
+    fn forget(&mut self, range: Range) {
+        if range.empty() {
+            return;
+        }
+        let free = self
+            .ranges
+            .ranges_between(range.min, range.max)
+            .map(|r| cmp::min(r.max, range.max) - cmp::max(r.min, range.min))
+            .fold(0u128, |sum, len| sum + len as u128 + 1);
+        let used = (range.max - range.min) as u128 + 1 - free;
+        if used > 0 {
+            self.outstanding_count = self.outstanding_count.saturating_sub(1);
+            let used = cmp::min(used, usize::MAX as u128) as usize;
+            self.outstanding_total = self.outstanding_total.saturating_sub(used);
+        }
+    }
+
+    /// Cuts `range` out of the outstanding blocks
+    #[cfg(feature = "tracked-ranges")]
+    fn forget(&mut self, range: Range) {
+        while let Some(&overlap) = self.outstanding.get(&range) {
+            self.outstanding.remove(&overlap);
+            let min = cmp::max(overlap.min, range.min);
+            let max = cmp::min(overlap.max, range.max);
+            self.outstanding_total -= max - min + 1;
+            if overlap.min < range.min {
+                self.outstanding.insert(Range {
+                    min: overlap.min,
+                    max: range.min - 1,
+                });
+            }
+            if overlap.max > range.max {
+                self.outstanding.insert(Range {
+                    min: range.max + 1,
+                    max: overlap.max,
+                });
+            }
+        }
+        self.outstanding_count = self.outstanding.len();
+    }
+}
+
+impl Drop for TrackedFreeRanges {
+    fn drop(&mut self) {
+        if self.panic_on_drop && !thread::panicking() {
+            self.assert_no_outstanding();
+        }
+    }
+}
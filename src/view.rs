@@ -0,0 +1,75 @@
+use std::cmp;
+use std::collections::btree_set::Iter;
+use std::sync::Arc;
+
+use super::{FreeRanges, Range};
+
+/// A read-only snapshot of a FreeRanges.
+///
+/// The snapshot owns a copy of the ranges behind an `Arc`, so it is `Send`
+/// and `Sync` and cloning it is cheap. Taking one from a `CowFreeRanges`
+/// shares its storage instead of copying it. Reader threads each hold a clone
+/// while the writer keeps mutating its FreeRanges; they see the ranges as
+/// they were when `snapshot` was called until they are handed a new one.
+#[derive(Debug, Clone)]
+pub struct FreeRangesView {
+    ranges: Arc<FreeRanges>,
+}
+
+impl FreeRanges {
+    /// Copies the current ranges into a view which can be shared between
+    /// threads, see `FreeRangesView`
+    #[inline]
+    pub fn snapshot(&self) -> FreeRangesView {
+        FreeRangesView {
+            ranges: Arc::new(self.clone()),
+        }
+    }
+}
+
+impl FreeRangesView {
+    #[inline]
+    pub(crate) fn from_shared(ranges: Arc<FreeRanges>) -> Self {
+        FreeRangesView { ranges }
+    }
+
+    /// Copies the ranges back out into a FreeRanges which can be mutated
+    #[inline]
+    pub fn to_free_ranges(&self) -> FreeRanges {
+        (*self.ranges).clone()
+    }
+
+    #[inline]
+    pub fn is_free(&self, index: usize) -> bool {
+        self.ranges.free_list.contains(&Range::id(index))
+    }
+
+    /// The number of free indices. A view with every index free would hold
+    /// one more than usize::MAX, so the count saturates.
+    pub fn total_free(&self) -> usize {
+        self.ranges.free_list.iter().fold(0usize, |total, r| {
+            total.saturating_add(r.max - r.min).saturating_add(1)
+        })
+    }
+
+    /// Iterator over the free ranges, lowest first
+    #[inline]
+    pub fn free_ranges(&self) -> Iter<'_, Range> {
+        self.ranges.free_list.tree().iter()
+    }
+
+    /// The lowest free index at or after `index`
+    #[inline]
+    pub fn next_free(&self, index: usize) -> Option<usize> {
+        self.ranges
+            .free_list
+            .range(Range::id(index)..)
+            .next()
+            .map(|r| cmp::max(r.min, index))
+    }
+
+    #[inline]
+    pub fn range_count(&self) -> usize {
+        self.ranges.free_list.len()
+    }
+}
@@ -0,0 +1,184 @@
+#![cfg(feature = "allocator-api2")]
+
+extern crate allocator_api2;
+extern crate free_ranges;
+
+mod common;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use allocator_api2::alloc::{AllocError, Allocator};
+use common::{r, span, spans, Span, XorShift};
+use free_ranges::{FreeRanges, FreeRangesIn};
+
+/// Counts every allocation made on this thread through the global
+/// allocator, so a test can check that none escape the arena
+struct CountingGlobal;
+
+thread_local! {
+    static GLOBAL_ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingGlobal {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        GLOBAL_ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingGlobal = CountingGlobal;
+
+fn global_allocations() -> usize {
+    GLOBAL_ALLOCATIONS.with(Cell::get)
+}
+
+/// Stands in for an arena: takes its memory straight from the system,
+/// bypassing the global allocator, and counts what is live
+#[derive(Clone)]
+struct Arena {
+    id: usize,
+    live: Rc<Cell<usize>>,
+    total: Rc<Cell<usize>>,
+}
+
+impl Arena {
+    fn new(id: usize) -> Self {
+        Arena {
+            id,
+            live: Rc::new(Cell::new(0)),
+            total: Rc::new(Cell::new(0)),
+        }
+    }
+}
+
+unsafe impl Allocator for Arena {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let block = System.allocate(layout)?;
+        self.live.set(self.live.get() + 1);
+        self.total.set(self.total.get() + 1);
+        Ok(block)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.live.set(self.live.get() - 1);
+        System.deallocate(ptr, layout)
+    }
+}
+
+fn in_spans<A: Allocator>(ranges: &FreeRangesIn<A>) -> Vec<Span> {
+    ranges.free_ranges().map(span).collect()
+}
+
+/// Runs `f` and checks that it made no allocations through the global
+/// allocator
+fn no_global_allocations<T, F: FnOnce() -> T>(f: F) -> T {
+    let before = global_allocations();
+    let result = f();
+    assert_eq!(global_allocations(), before, "allocated outside the arena");
+    result
+}
+
+#[test]
+fn random_operations_match_free_ranges() {
+    const DOMAIN: usize = 64;
+    let arena = Arena::new(0);
+    let mut rng = XorShift::new(0x1234_5678);
+    let mut ranges = FreeRanges::new_in(arena.clone());
+    let mut model = FreeRanges::new();
+
+    for _ in 0..5_000 {
+        let a = rng.below(DOMAIN);
+        let b = (a + rng.below(8)).min(DOMAIN - 1);
+        let op = rng.below(6);
+        let got = no_global_allocations(|| match op {
+            0 => ranges.set_free(a) as usize,
+            1 => ranges.set_used(a) as usize,
+            2 => ranges.set_range_free(r(a, b)) as usize,
+            3 => ranges.set_range_used(r(a, b)) as usize,
+            4 => ranges.set_first_used().map_or(usize::MAX, |i| i),
+            _ => ranges.allocate(b - a + 1).map_or(usize::MAX, |r| r.min),
+        });
+        let expected = match op {
+            0 => model.set_free(a) as usize,
+            1 => model.set_used(a) as usize,
+            2 => model.set_range_free(r(a, b)) as usize,
+            3 => model.set_range_used(r(a, b)) as usize,
+            4 => model.set_first_used().map_or(usize::MAX, |i| i),
+            _ => model.allocate(b - a + 1).map_or(usize::MAX, |r| r.min),
+        };
+        assert_eq!(got, expected, "op {} on ({}, {})", op, a, b);
+
+        no_global_allocations(|| {
+            for i in 0..DOMAIN {
+                assert_eq!(ranges.is_free(i), model.is_free(i));
+            }
+            assert_eq!(ranges.is_range_free(r(a, b)), model.is_range_free(r(a, b)));
+            assert_eq!(ranges.first(), model.first());
+            assert_eq!(ranges.last(), model.last());
+        });
+        assert_eq!(in_spans(&ranges), spans(&model));
+    }
+
+    assert!(arena.total.get() > 0);
+    drop(ranges);
+    assert_eq!(arena.live.get(), 0);
+}
+
+#[test]
+fn clones_and_splits_keep_the_allocator() {
+    let arena = Arena::new(7);
+    let mut lower = no_global_allocations(|| {
+        let mut ranges = FreeRangesIn::with_initial_range_in(r(0, 99), arena.clone());
+        ranges.set_used(50);
+        ranges
+    });
+    let copy = no_global_allocations(|| lower.clone());
+    let mut higher = no_global_allocations(|| lower.split_off(20));
+    assert_eq!(copy.allocator().id, 7);
+    assert_eq!(higher.allocator().id, 7);
+    assert_eq!(in_spans(&copy), vec![(0, 49), (51, 99)]);
+    assert_eq!(in_spans(&lower), vec![(0, 19)]);
+    assert_eq!(in_spans(&higher), vec![(20, 49), (51, 99)]);
+
+    no_global_allocations(|| {
+        higher.set_free(50);
+        lower.join(higher);
+    });
+    assert_eq!(in_spans(&lower), vec![(0, 99)]);
+    drop(lower);
+    assert!(arena.live.get() > 0);
+    drop(copy);
+    assert_eq!(arena.live.get(), 0);
+}
+
+#[test]
+fn the_full_domain_and_conversions() {
+    let max = usize::MAX;
+    let mut ranges = FreeRangesIn::with_initial_range_in(r(0, max), Arena::new(0));
+    assert!(ranges.is_range_free(r(0, max)));
+    assert!(!ranges.set_range_free(r(5, max)));
+    assert!(ranges.set_used(max));
+    assert!(ranges.set_used(0));
+    assert_eq!(in_spans(&ranges), vec![(1, max - 1)]);
+    assert!(ranges.set_range_free(r(max - 1, max)));
+    assert_eq!(in_spans(&ranges), vec![(1, max)]);
+
+    let higher = ranges.split_off(max);
+    assert_eq!(in_spans(&higher), vec![(max, max)]);
+    assert_eq!(spans(ranges.to_free_ranges()), vec![(1, max - 1)]);
+
+    let mut model = FreeRanges::new();
+    model.set_range_free(r(3, 9));
+    model.set_range_free(r(20, 29));
+    let copied = FreeRangesIn::from_ranges_in(&model, Arena::new(1));
+    assert_eq!(in_spans(&copied), spans(&model));
+    assert_eq!(copied.range_count(), 2);
+}
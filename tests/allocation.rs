@@ -0,0 +1,340 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, Span};
+use free_ranges::{AllocError, Allocation, FreeRanges, Placement};
+
+#[test]
+fn allocate_whole_takes_the_smallest_fitting_range() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 12));
+    ranges.set_range_free(r(16, 19));
+    ranges.set_range_free(r(32, 35));
+    ranges.set_range_free(r(40, 43));
+
+    let mut count = ranges.range_count();
+    for &(size, expected) in &[(3, (16, 19)), (4, (32, 35)), (5, (1, 12)), (0, (40, 43))] {
+        assert_eq!(ranges.allocate_whole(size).map(span), Some(expected));
+        assert_eq!(ranges.range_count(), count - 1);
+        count -= 1;
+    }
+    assert_eq!(ranges.allocate_whole(0), None);
+}
+
+#[test]
+fn allocate_whole_without_a_fit_leaves_the_set_alone() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 12));
+    ranges.set_range_free(r(16, 19));
+    assert_eq!(ranges.allocate_whole(13), None);
+    assert_eq!(spans(&ranges), vec![(1, 12), (16, 19)]);
+
+    let mut all = FreeRanges::with_all_free();
+    assert_eq!(
+        all.allocate_whole(usize::MAX).map(span),
+        Some((0, usize::MAX))
+    );
+    assert_eq!(all.range_count(), 0);
+}
+
+#[test]
+fn allocate_best_alignment_prefers_the_least_padding() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 100));
+    ranges.set_range_free(r(204, 212));
+
+    // 64 would skip 63 indices in 1..=100, 4 fits 204..=212 without any
+    let (carved, align) = ranges.allocate_best_alignment(8, &[64, 4]).unwrap();
+    assert_eq!((span(carved), align), ((204, 211), 4));
+
+    let (carved, align) = ranges.allocate_best_alignment(8, &[64]).unwrap();
+    assert_eq!((span(carved), align), ((64, 71), 64));
+
+    // Equal padding goes to the lowest address, then the earliest alignment
+    let (carved, align) = ranges.allocate_best_alignment(4, &[2, 1]).unwrap();
+    assert_eq!((span(carved), align), ((1, 4), 1));
+    let (carved, align) = ranges.allocate_best_alignment(4, &[1, 5]).unwrap();
+    assert_eq!((span(carved), align), ((5, 8), 1));
+}
+
+#[test]
+fn allocate_best_alignment_without_a_fit() {
+    let mut ranges = FreeRanges::with_initial_range(r(1, 100));
+    assert_eq!(ranges.allocate_best_alignment(8, &[]), None);
+    assert_eq!(ranges.allocate_best_alignment(200, &[1]), None);
+    assert_eq!(ranges.allocate_best_alignment(8, &[128]), None);
+    assert_eq!(ranges.allocate_best_alignment(0, &[1]), None);
+    assert_eq!(spans(&ranges), vec![(1, 100)]);
+}
+
+#[test]
+fn can_allocate_at_the_exact_size() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 4));
+    ranges.set_range_free(r(10, 19));
+    assert!(ranges.can_allocate(10));
+    assert!(!ranges.can_allocate(11));
+    assert!(ranges.can_allocate(0));
+    assert_eq!(spans(&ranges), vec![(0, 4), (10, 19)]);
+
+    assert!(!FreeRanges::new().can_allocate(1));
+}
+
+#[test]
+fn can_allocate_the_full_domain() {
+    let ranges = FreeRanges::with_all_free();
+    assert!(ranges.can_allocate(usize::MAX));
+
+    let mut ranges = FreeRanges::with_all_free();
+    ranges.set_used(0);
+    assert!(ranges.can_allocate(usize::MAX));
+    ranges.set_used(usize::MAX);
+    assert!(!ranges.can_allocate(usize::MAX));
+}
+
+#[test]
+fn allocate_multi_packs_placements_into_long_ranges() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 3));
+    ranges.set_range_free(r(10, 29));
+    ranges.set_range_free(r(40, 47));
+    let placed = ranges.allocate_multi(3, 8).unwrap();
+    assert_eq!(
+        placed.into_iter().map(span).collect::<Vec<_>>(),
+        vec![(10, 17), (18, 25), (40, 47)]
+    );
+    assert_eq!(spans(&ranges), vec![(0, 3), (26, 29)]);
+}
+
+#[test]
+fn allocate_multi_is_all_or_nothing() {
+    let mut ranges = FreeRanges::new();
+    for &min in &[0, 10, 20, 30] {
+        ranges.set_range_free(r(min, min + 6));
+    }
+    let before = spans(&ranges);
+    assert_eq!(ranges.allocate_multi(2, 8), None);
+    assert_eq!(spans(&ranges), before);
+    assert_eq!(ranges.allocate_multi(5, 7), None);
+    assert_eq!(spans(&ranges), before);
+    assert_eq!(ranges.allocate_multi(0, 8), Some(vec![]));
+    assert_eq!(spans(&ranges), before);
+}
+
+#[test]
+fn allocate_multi_over_the_full_domain() {
+    let mut ranges = FreeRanges::with_all_free();
+    let placed = ranges.allocate_multi(2, usize::MAX / 2).unwrap();
+    assert_eq!(
+        placed.into_iter().map(span).collect::<Vec<_>>(),
+        vec![(0, usize::MAX / 2 - 1), (usize::MAX / 2, usize::MAX - 2)]
+    );
+    assert_eq!(spans(&ranges), vec![(usize::MAX - 1, usize::MAX)]);
+}
+
+#[test]
+fn allocate_multi_of_single_indices_over_the_full_domain() {
+    let max = usize::MAX;
+    let mut ranges = FreeRanges::with_all_free();
+    let placed = ranges.allocate_multi(2, 1).unwrap();
+    assert_eq!(spans(placed), vec![(0, 0), (1, 1)]);
+    assert_eq!(spans(&ranges), vec![(2, max)]);
+
+    // Two halves of 2^63 use up the whole domain
+    let half = 1 << (usize::BITS - 1);
+    let mut ranges = FreeRanges::with_all_free();
+    let placed = ranges.allocate_multi(2, half).unwrap();
+    assert_eq!(spans(placed), vec![(0, half - 1), (half, max)]);
+    assert_eq!(ranges.first(), None);
+}
+
+/// The placement as `(relocated, span)`, since Range compares fuzzily
+fn placed(result: Result<Placement, AllocError>) -> Option<(bool, Span)> {
+    result.ok().map(|placement| match placement {
+        Placement::AtPreferred(range) => (false, span(range)),
+        Placement::Relocated(range) => (true, span(range)),
+    })
+}
+
+#[test]
+fn allocate_preferring_falls_back_to_the_same_length() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 9));
+    ranges.set_range_free(r(20, 39));
+    // Fully free, partly free and fully used preferred ranges
+    assert_eq!(
+        placed(ranges.allocate_preferring(r(22, 25))),
+        Some((false, (22, 25)))
+    );
+    assert_eq!(
+        placed(ranges.allocate_preferring(r(8, 11))),
+        Some((true, (0, 3)))
+    );
+    assert_eq!(
+        placed(ranges.allocate_preferring(r(22, 24))),
+        Some((true, (4, 6)))
+    );
+    assert_eq!(spans(&ranges), vec![(7, 9), (20, 21), (26, 39)]);
+}
+
+#[test]
+fn allocate_preferring_changes_nothing_on_failure() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 9));
+    ranges.set_range_free(r(20, 39));
+    let before = spans(&ranges);
+    let error = ranges.allocate_preferring(r(0, 20)).unwrap_err();
+    assert_eq!(span(error.preferred), (0, 20));
+    assert_eq!(spans(&ranges), before);
+
+    let mut all = FreeRanges::with_all_free();
+    all.set_used(5);
+    assert!(all.allocate_preferring(r(0, usize::MAX)).is_err());
+    assert_eq!(spans(&all), vec![(0, 4), (6, usize::MAX)]);
+    assert_eq!(span(Placement::Relocated(r(1, 2)).range()), (1, 2));
+}
+
+#[test]
+fn allocate_fill_small_exhausts_small_ranges_first() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 99));
+    ranges.set_range_free(r(200, 202));
+    ranges.set_range_free(r(300, 304));
+    let mut first_fit = ranges.clone();
+
+    assert_eq!(ranges.allocate_fill_small(2).map(span), Some((200, 201)));
+    assert_eq!(ranges.allocate_fill_small(2).map(span), Some((300, 301)));
+    assert_eq!(spans(&ranges), vec![(0, 99), (202, 202), (302, 304)]);
+    assert_eq!(ranges.allocate_fill_small(3).map(span), Some((302, 304)));
+    assert_eq!(ranges.allocate_fill_small(200), None);
+
+    // First fit cuts into the big range instead
+    assert_eq!(first_fit.allocate(2).map(span), Some((0, 1)));
+    assert_eq!(first_fit.allocate(2).map(span), Some((2, 3)));
+    assert_eq!(spans(&first_fit), vec![(4, 99), (200, 202), (300, 304)]);
+}
+
+#[test]
+fn threading_the_cursor_spreads_allocations_forward() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    let (first, cursor) = ranges.allocate_with_cursor(10).unwrap();
+    assert_eq!((span(first), cursor), ((0, 9), 10));
+    let (second, cursor) = ranges.allocate_from(cursor, 10).unwrap();
+    assert_eq!(span(second), (10, 19));
+
+    // The freed block behind the cursor is skipped until the end is reached
+    ranges.set_range_free(r(0, 9));
+    let (third, cursor) = ranges.allocate_from(cursor, 5).unwrap();
+    assert_eq!(span(third), (20, 24));
+    assert_eq!(ranges.allocate(5).map(span), Some((0, 4)));
+    assert!(ranges.allocate_from(cursor, 80).is_none());
+
+    let (wrapped, cursor) = ranges.allocate_from(200, 3).unwrap();
+    assert_eq!((span(wrapped), cursor), ((5, 7), 8));
+    assert_eq!(spans(&ranges), vec![(8, 9), (25, 99)]);
+}
+
+#[test]
+fn the_cursor_wraps_past_the_end_of_the_domain() {
+    let mut ranges = FreeRanges::with_initial_range(r(usize::MAX - 1, usize::MAX));
+    ranges.set_free(0);
+    let (block, cursor) = ranges.allocate_with_cursor(2).unwrap();
+    assert_eq!((span(block), cursor), ((usize::MAX - 1, usize::MAX), 0));
+    assert_eq!(
+        ranges.allocate_from(cursor, 1).map(|(b, c)| (span(b), c)),
+        Some(((0, 0), 1))
+    );
+    assert!(ranges.allocate_with_cursor(1).is_none());
+    assert!(ranges.allocate_with_cursor(0).is_none());
+}
+
+#[test]
+fn allocate_tail_of_largest_keeps_the_front_free() {
+    let mut ranges = FreeRanges::new();
+    for &(min, max) in &[(0, 9), (20, 49), (60, 89)] {
+        ranges.set_range_free(r(min, max));
+    }
+    assert_eq!(ranges.allocate_tail_of_largest(5).map(span), Some((45, 49)));
+    assert_eq!(spans(&ranges), vec![(0, 9), (20, 44), (60, 89)]);
+    assert_eq!(ranges.allocate_tail_of_largest(5).map(span), Some((85, 89)));
+    assert_eq!(ranges.allocate_tail_of_largest(30), None);
+    assert_eq!(ranges.allocate_tail_of_largest(0), None);
+
+    // Ties go to the lowest range, and a whole range can be taken
+    assert_eq!(
+        ranges.allocate_tail_of_largest(25).map(span),
+        Some((20, 44))
+    );
+    assert_eq!(spans(&ranges), vec![(0, 9), (60, 84)]);
+}
+
+#[test]
+fn allocate_tail_of_largest_at_the_end_of_the_domain() {
+    let mut ranges = FreeRanges::with_all_free();
+    let max = usize::MAX;
+    assert_eq!(
+        ranges.allocate_tail_of_largest(1).map(span),
+        Some((max, max))
+    );
+    assert_eq!(
+        ranges.allocate_tail_of_largest(max).map(span),
+        Some((0, max - 1))
+    );
+    assert_eq!(ranges.first(), None);
+}
+
+#[test]
+fn allocate_alloc_ends_just_past_the_block() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 2));
+    ranges.set_range_free(r(10, 30));
+    let mut copy = ranges.clone();
+
+    let alloc = ranges.allocate_alloc(5).unwrap();
+    let range = copy.allocate(5).unwrap();
+    assert_eq!(alloc, Allocation { offset: 10, len: 5 });
+    assert_eq!((alloc.offset, alloc.end()), (range.min, range.max + 1));
+    assert!(!ranges.is_free(alloc.end() - 1));
+    assert!(ranges.is_free(alloc.end()));
+    assert_eq!(ranges.allocate_alloc(0), None);
+    assert_eq!(ranges.allocate_alloc(100), None);
+    assert_eq!(spans(&ranges), vec![(0, 2), (15, 30)]);
+}
+
+#[test]
+fn allocate_no_cross_shifts_past_a_boundary() {
+    let mut ranges = FreeRanges::with_initial_range(r(4090, 4200));
+    // 4090 would cross the page boundary at 4096
+    assert_eq!(
+        ranges.allocate_no_cross(16, 4096).map(span),
+        Some((4096, 4111))
+    );
+    assert!(ranges.is_free(4090));
+    assert!(ranges.is_free(4095));
+    assert!(ranges.is_free(4112));
+    assert_eq!(
+        ranges.allocate_no_cross(6, 4096).map(span),
+        Some((4090, 4095))
+    );
+    assert_eq!(spans(&ranges), vec![(4112, 4200)]);
+
+    let mut aligned = FreeRanges::with_initial_range(r(0, 15));
+    assert_eq!(aligned.allocate_no_cross(8, 8).map(span), Some((0, 7)));
+    assert_eq!(aligned.allocate_no_cross(8, 8).map(span), Some((8, 15)));
+}
+
+#[test]
+fn allocate_no_cross_without_a_placement() {
+    let mut ranges = FreeRanges::with_initial_range(r(4090, 4100));
+    assert_eq!(ranges.allocate_no_cross(8, 4096), None);
+    assert_eq!(ranges.allocate_no_cross(4097, 4096), None);
+    assert_eq!(ranges.allocate_no_cross(0, 4096), None);
+    assert_eq!(spans(&ranges), vec![(4090, 4100)]);
+
+    // A boundary of 0 never splits anything
+    assert_eq!(ranges.allocate_no_cross(8, 0).map(span), Some((4090, 4097)));
+
+    let max = usize::MAX;
+    let mut top = FreeRanges::with_initial_range(r(max - 3, max));
+    assert_eq!(top.allocate_no_cross(4, 4).map(span), Some((max - 3, max)));
+}
@@ -0,0 +1,73 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::alloc::Layout;
+
+use common::spans;
+use free_ranges::FreeRangesAllocator;
+
+fn layout(size: usize, align: usize) -> Layout {
+    Layout::from_size_align(size, align).unwrap()
+}
+
+#[test]
+fn layouts_get_aligned_non_overlapping_offsets() {
+    let mut alloc = FreeRangesAllocator::new(64);
+    let layouts = [
+        layout(3, 1),
+        layout(8, 8),
+        layout(16, 16),
+        layout(16, 16),
+        layout(16, 16),
+        layout(4, 4),
+    ];
+    let offsets: Vec<usize> = layouts
+        .iter()
+        .map(|&l| alloc.allocate(l).unwrap())
+        .collect();
+    assert_eq!(offsets, vec![0, 8, 16, 32, 48, 4]);
+
+    for (i, (&a, la)) in offsets.iter().zip(&layouts).enumerate() {
+        assert_eq!(a % la.align(), 0);
+        assert!(a + la.size() <= alloc.buffer_len());
+        for (&b, lb) in offsets.iter().zip(&layouts).skip(i + 1) {
+            assert!(
+                a + la.size() <= b || b + lb.size() <= a,
+                "{} and {} overlap",
+                a,
+                b
+            );
+        }
+    }
+    assert_eq!(alloc.allocate(layout(8, 8)), None);
+    assert_eq!(spans(alloc.ranges()), vec![(3, 3)]);
+}
+
+#[test]
+fn deallocated_blocks_are_reused() {
+    let mut alloc = FreeRangesAllocator::new(64);
+    let small = layout(3, 1);
+    let big = layout(16, 16);
+    let a = alloc.allocate(small).unwrap();
+    let b = alloc.allocate(big).unwrap();
+    let c = alloc.allocate(big).unwrap();
+
+    alloc.deallocate(b, big);
+    assert_eq!(alloc.allocate(big), Some(b));
+    alloc.deallocate(b, big);
+    alloc.deallocate(a, small);
+    alloc.deallocate(c, big);
+    assert_eq!(spans(alloc.ranges()), vec![(0, 63)]);
+    assert_eq!(alloc.allocate(layout(64, 1)), Some(0));
+}
+
+#[test]
+fn zero_sized_and_empty_buffers() {
+    let mut alloc = FreeRangesAllocator::new(0);
+    assert_eq!(alloc.ranges().range_count(), 0);
+    assert_eq!(alloc.allocate(layout(0, 8)), Some(0));
+    assert_eq!(alloc.allocate(layout(1, 1)), None);
+    alloc.deallocate(0, layout(0, 8));
+    assert_eq!(alloc.ranges().range_count(), 0);
+}
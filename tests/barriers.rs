@@ -0,0 +1,93 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans};
+use free_ranges::FreeRanges;
+
+const MAX: usize = usize::MAX;
+
+fn with_barriers(barriers: &[usize]) -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for &barrier in barriers {
+        assert!(ranges.add_barrier(barrier));
+    }
+    ranges
+}
+
+#[test]
+fn frees_are_cut_at_barriers() {
+    let mut ranges = with_barriers(&[9, 19, 29]);
+    assert!(!ranges.add_barrier(9));
+    assert!(!ranges.add_barrier(MAX));
+
+    // Across no barrier, one and several
+    ranges.set_range_free(r(0, 5));
+    assert_eq!(spans(&ranges), vec![(0, 5)]);
+    ranges.set_range_free(r(5, 15));
+    assert_eq!(spans(&ranges), vec![(0, 9), (10, 15)]);
+    ranges.set_range_free(r(16, 40));
+    assert_eq!(spans(&ranges), vec![(0, 9), (10, 19), (20, 29), (30, 40)]);
+    assert_eq!(ranges.range_count(), 4);
+
+    assert!(ranges.set_used(20));
+    assert!(ranges.set_free(20));
+    assert_eq!(spans(&ranges), vec![(0, 9), (10, 19), (20, 29), (30, 40)]);
+}
+
+#[test]
+fn barriers_split_and_rejoin_ranges() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 100));
+    ranges.add_barrier(50);
+    assert_eq!(spans(&ranges), vec![(0, 50), (51, 100)]);
+    ranges.set_used(50);
+    ranges.set_free(50);
+    assert_eq!(spans(&ranges), vec![(0, 50), (51, 100)]);
+
+    let higher = ranges.split_off(51);
+    assert_eq!(higher.barriers().count(), 0);
+    assert_eq!(ranges.barriers().collect::<Vec<_>>(), vec![50]);
+    ranges.join(higher);
+    assert_eq!(spans(&ranges), vec![(0, 50), (51, 100)]);
+
+    assert!(ranges.remove_barrier(50));
+    assert!(!ranges.remove_barrier(50));
+    assert_eq!(spans(&ranges), vec![(0, 100)]);
+
+    let mut all = FreeRanges::with_all_free();
+    all.add_barrier(MAX - 1);
+    assert_eq!(spans(&all), vec![(0, MAX - 1), (MAX, MAX)]);
+}
+
+#[test]
+fn longest_used_run_skips_barrier_splits() {
+    let mut ranges = with_barriers(&[5]);
+    ranges.set_range_free(r(0, 9));
+    assert_eq!(ranges.longest_used_run(), None);
+
+    ranges.set_range_free(r(20, 21));
+    assert_eq!(ranges.longest_used_run().map(span), Some((10, 19)));
+}
+
+#[test]
+fn first_overlap_accepts_barrier_splits() {
+    let mut ranges = with_barriers(&[5]);
+    ranges.set_range_free(r(0, 9));
+    assert!(ranges.is_canonical());
+    assert_eq!(ranges.first_overlap(), None);
+}
+
+#[test]
+fn range_queries_see_across_barriers() {
+    let mut ranges = with_barriers(&[4, 9]);
+    ranges.set_range_free(r(0, 14));
+    assert!(ranges.is_range_free(r(2, 12)));
+    assert!(ranges.is_range_free(r(0, 14)));
+    assert!(!ranges.is_range_free(r(0, 15)));
+    assert!(!ranges.set_range_free(r(3, 11)));
+    assert_eq!(ranges.leading_free_len(), 15);
+    assert_eq!(ranges.trailing_free_len(12), 13);
+
+    assert!(ranges.set_range_free(r(3, 15)));
+    assert_eq!(spans(&ranges), vec![(0, 4), (5, 9), (10, 15)]);
+}
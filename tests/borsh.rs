@@ -0,0 +1,86 @@
+#![cfg(feature = "borsh")]
+
+extern crate borsh;
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, XorShift};
+use free_ranges::{FreeRanges, Range};
+
+/// The little-endian u64 encoding of `value`
+fn le(value: u64) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+fn encoding(count: u32, bounds: &[u64]) -> Vec<u8> {
+    let mut bytes = count.to_le_bytes().to_vec();
+    for &bound in bounds {
+        bytes.extend(le(bound));
+    }
+    bytes
+}
+
+#[test]
+fn golden_bytes() {
+    let range = r(0x0102, 0x0304);
+    let bytes = borsh::to_vec(&range).unwrap();
+    assert_eq!(bytes, vec![2, 1, 0, 0, 0, 0, 0, 0, 4, 3, 0, 0, 0, 0, 0, 0]);
+
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 2));
+    ranges.set_free(5);
+    let bytes = borsh::to_vec(&ranges).unwrap();
+    #[rustfmt::skip]
+    let expected = vec![
+        2, 0, 0, 0,
+        1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0,
+        5, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    assert_eq!(bytes, expected);
+
+    assert_eq!(borsh::to_vec(&FreeRanges::new()).unwrap(), vec![0, 0, 0, 0]);
+    let all = borsh::to_vec(&FreeRanges::with_all_free()).unwrap();
+    assert_eq!(all, encoding(1, &[0, u64::MAX]));
+}
+
+#[test]
+fn golden_bytes_decode() {
+    let ranges: FreeRanges = borsh::from_slice(&encoding(2, &[1, 2, 5, 5])).unwrap();
+    assert_eq!(spans(&ranges), vec![(1, 2), (5, 5)]);
+    let range: Range =
+        borsh::from_slice(&le(7).into_iter().chain(le(9)).collect::<Vec<_>>()).unwrap();
+    assert_eq!(span(range), (7, 9));
+}
+
+#[test]
+fn non_canonical_encodings_are_rejected() {
+    let rejected = |bytes: Vec<u8>| borsh::from_slice::<FreeRanges>(&bytes).is_err();
+    // Adjacent
+    assert!(rejected(encoding(2, &[1, 2, 3, 5])));
+    // Out of order
+    assert!(rejected(encoding(2, &[5, 5, 1, 2])));
+    // Overlapping
+    assert!(rejected(encoding(2, &[1, 4, 3, 5])));
+    // Empty
+    assert!(rejected(encoding(1, &[5, 4])));
+    // Truncated, and trailing bytes
+    assert!(rejected(encoding(2, &[1, 2])));
+    assert!(rejected(encoding(1, &[1, 2, 9])));
+}
+
+#[test]
+fn random_sets_round_trip() {
+    let mut rng = XorShift::new(9);
+    for _ in 0..50 {
+        let mut ranges = FreeRanges::new();
+        for _ in 0..rng.below(20) {
+            let min = rng.below(1000);
+            ranges.set_range_free(r(min, min + rng.below(10)));
+        }
+        let bytes = borsh::to_vec(&ranges).unwrap();
+        assert_eq!(bytes.len(), 4 + 16 * ranges.range_count());
+        let decoded: FreeRanges = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(spans(&decoded), spans(&ranges));
+    }
+}
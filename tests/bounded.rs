@@ -0,0 +1,39 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans};
+use free_ranges::BoundedFreeRanges;
+
+#[test]
+fn indices_above_the_bound_are_rejected() {
+    let mut ranges = BoundedFreeRanges::<15>::new();
+    assert!(!ranges.set_free(16));
+    assert!(!ranges.is_free(16));
+    assert!(!ranges.set_range_free(r(10, 16)));
+    assert_eq!(ranges.free_count(), 0);
+
+    assert!(ranges.set_range_free(r(10, 15)));
+    assert_eq!(ranges.last(), Some(15));
+    assert!(ranges.set_free(0));
+    assert!(ranges.set_used(0));
+    assert!(!ranges.set_used(16));
+    assert_eq!(ranges.free_count(), 6);
+}
+
+#[test]
+fn with_all_free_stops_at_the_bound() {
+    let mut ranges = BoundedFreeRanges::<15>::with_all_free();
+    assert_eq!(spans(ranges.ranges()), vec![(0, 15)]);
+    assert_eq!(ranges.free_ranges().count(), 1);
+    assert_eq!(ranges.free_count(), 16);
+    assert_eq!(ranges.allocate(4).map(span), Some((0, 3)));
+    assert_eq!(ranges.allocate(13), None);
+    assert_eq!(ranges.set_first_used(), Some(4));
+    assert_eq!(ranges.set_last_used(), Some(15));
+    assert_eq!(ranges.first(), Some(5));
+
+    let all = BoundedFreeRanges::<{ usize::MAX }>::with_all_free();
+    assert_eq!(all.free_count(), usize::MAX);
+    assert!(all.is_free(usize::MAX));
+}
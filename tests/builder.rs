@@ -0,0 +1,104 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, spans};
+use free_ranges::{BuildError, FreeRangesBuilder};
+
+#[test]
+fn build_punches_out_reservations_then_frees_extras() {
+    let ranges = FreeRangesBuilder::new()
+        .domain(0..=0xFFFF)
+        .reserve(0..=255)
+        .reserve(0xFF00..=0xFFFF)
+        .reserve(200..=300)
+        .free_extra(250..=260)
+        .build()
+        .unwrap();
+    assert_eq!(spans(&ranges), vec![(250, 260), (301, 0xFEFF)]);
+
+    let ranges = FreeRangesBuilder::new().with_bound(9).build().unwrap();
+    assert_eq!(spans(&ranges), vec![(0, 9)]);
+}
+
+#[test]
+fn build_reports_the_offending_call() {
+    let error = FreeRangesBuilder::new()
+        .domain(0..=10)
+        .reserve(0..=3)
+        .reserve(5..=11)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        BuildError::ReserveOutsideDomain {
+            call: 1,
+            range: r(5, 11),
+            domain: r(0, 10),
+        }
+    );
+    assert!(error.to_string().starts_with("reserve #1 "));
+
+    let error = FreeRangesBuilder::new()
+        .domain(0..=10)
+        .free_extra(1..=2)
+        .free_extra(r(4, 3))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        BuildError::EmptyFreeExtra {
+            call: 1,
+            range: r(4, 3)
+        }
+    );
+}
+
+#[test]
+fn build_requires_a_domain() {
+    assert_eq!(
+        FreeRangesBuilder::new().build().unwrap_err(),
+        BuildError::MissingDomain
+    );
+    assert_eq!(
+        FreeRangesBuilder::new()
+            .domain(r(5, 4))
+            .build()
+            .unwrap_err(),
+        BuildError::EmptyDomain(r(5, 4))
+    );
+}
+
+#[test]
+fn with_bound_and_reservations() {
+    let ranges = FreeRangesBuilder::new()
+        .with_bound(99)
+        .reserve(0..=9)
+        .reserve(50..=59)
+        .build()
+        .unwrap();
+    assert_eq!(spans(&ranges), vec![(10, 49), (60, 99)]);
+    assert!(!ranges.is_free(9));
+    assert!(!ranges.is_free(55));
+
+    let error = FreeRangesBuilder::new()
+        .with_bound(99)
+        .reserve(90..=100)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        BuildError::ReserveOutsideDomain {
+            call: 0,
+            range: r(90, 100),
+            domain: r(0, 99),
+        }
+    );
+
+    let all = FreeRangesBuilder::new()
+        .with_bound(usize::MAX)
+        .reserve(usize::MAX..=usize::MAX)
+        .build()
+        .unwrap();
+    assert_eq!(spans(&all), vec![(0, usize::MAX - 1)]);
+}
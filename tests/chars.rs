@@ -0,0 +1,54 @@
+extern crate free_ranges;
+
+use std::ops::RangeInclusive;
+
+use free_ranges::CharRanges;
+
+fn free(chars: &CharRanges) -> Vec<RangeInclusive<char>> {
+    chars.free_ranges().collect()
+}
+
+#[test]
+fn the_surrogate_gap_is_never_free() {
+    let mut chars = CharRanges::new();
+    chars.set_range_free('\u{D700}'..='\u{E0FF}');
+    assert_eq!(
+        free(&chars),
+        vec!['\u{D700}'..='\u{D7FF}', '\u{E000}'..='\u{E0FF}']
+    );
+    assert_eq!(chars.ranges().range_count(), 2);
+
+    // The last char before the gap and the first after it stay apart
+    assert!(chars.set_used('\u{D7FF}'));
+    assert!(chars.set_free('\u{D7FF}'));
+    assert!(!chars.set_free('\u{D7FF}'));
+    assert_eq!(free(&chars).len(), 2);
+
+    let mut all = CharRanges::with_all_free();
+    assert_eq!(free(&all), vec!['\0'..='\u{D7FF}', '\u{E000}'..=char::MAX]);
+    all.set_range_free('\u{D7F0}'..='\u{E010}');
+    assert_eq!(free(&all).len(), 2);
+    assert_eq!(all.first_free(), Some('\0'));
+}
+
+#[test]
+fn ranges_reach_char_max() {
+    let mut chars = CharRanges::new();
+    chars.set_range_free(char::MAX..=char::MAX);
+    chars.set_range_free('\u{10FFF0}'..=char::MAX);
+    assert_eq!(free(&chars), vec!['\u{10FFF0}'..=char::MAX]);
+    assert!(chars.is_free(char::MAX));
+}
+
+#[test]
+fn private_use_constructors() {
+    assert_eq!(
+        free(&CharRanges::with_bmp_private_use()),
+        vec!['\u{E000}'..='\u{F8FF}']
+    );
+
+    let mut chars = CharRanges::with_private_use();
+    assert_eq!(chars.set_first_used(), Some('\u{E000}'));
+    assert_eq!(chars.first_free(), Some('\u{E001}'));
+    assert_eq!(free(&chars).len(), 3);
+}
@@ -0,0 +1,146 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, Span};
+use free_ranges::{CircularFreeRanges, Range};
+
+fn spans2(pair: (Range, Option<Range>)) -> (Span, Option<Span>) {
+    (span(pair.0), pair.1.map(span))
+}
+
+#[test]
+fn allocate_without_wrapping() {
+    let mut ring = CircularFreeRanges::with_all_free(16);
+    assert_eq!(ring.allocate(4).map(spans2), Some(((0, 3), None)));
+    assert_eq!(ring.allocate(4).map(spans2), Some(((4, 7), None)));
+    assert!(!ring.is_free(7) && ring.is_free(8));
+}
+
+#[test]
+fn allocate_across_the_seam() {
+    let mut ring = CircularFreeRanges::new(16);
+    ring.set_range_free(r(13, 15));
+    ring.set_range_free(r(0, 1));
+
+    assert_eq!(ring.allocate(5).map(spans2), Some(((13, 15), Some((0, 1)))));
+    assert_eq!(ring.ranges().free_ranges().count(), 0);
+}
+
+#[test]
+fn allocate_prefers_a_region_which_fits_without_wrapping_first() {
+    let mut ring = CircularFreeRanges::new(16);
+    ring.set_range_free(r(14, 15));
+    ring.set_free(0);
+    ring.set_range_free(r(4, 9));
+
+    // The wrapped region starts at 14, so the one at 4 comes first
+    assert_eq!(ring.allocate(3).map(spans2), Some(((4, 6), None)));
+    assert_eq!(ring.allocate(3).map(spans2), Some(((7, 9), None)));
+    assert_eq!(ring.allocate(3).map(spans2), Some(((14, 15), Some((0, 0)))));
+}
+
+#[test]
+fn allocate_fails_when_no_region_is_long_enough() {
+    let mut ring = CircularFreeRanges::new(16);
+    ring.set_range_free(r(14, 15));
+    ring.set_free(0);
+    assert!(ring.allocate(4).is_none());
+    assert!(ring.allocate(0).is_none());
+    assert_eq!(ring.allocate(3).map(spans2), Some(((14, 15), Some((0, 0)))));
+}
+
+#[test]
+fn free_run_at_joins_across_the_seam() {
+    let mut ring = CircularFreeRanges::new(16);
+    ring.set_range_free(r(13, 15));
+    ring.set_range_free(r(0, 1));
+    ring.set_free(8);
+
+    assert_eq!(
+        ring.free_run_at(0).map(spans2),
+        Some(((13, 15), Some((0, 1))))
+    );
+    assert_eq!(
+        ring.free_run_at(14).map(spans2),
+        Some(((13, 15), Some((0, 1))))
+    );
+    assert_eq!(ring.free_run_at(8).map(spans2), Some(((8, 8), None)));
+    assert_eq!(ring.free_run_at(5), None);
+
+    let regions: Vec<_> = ring.free_ranges().map(spans2).collect();
+    assert_eq!(regions, vec![((8, 8), None), ((13, 15), Some((0, 1)))]);
+}
+
+#[test]
+fn indices_outside_the_ring_are_rejected() {
+    let mut ring = CircularFreeRanges::new(16);
+    assert!(!ring.set_free(16));
+    assert!(!ring.set_range_free(r(10, 16)));
+    assert!(!ring.is_free(16));
+}
+
+fn regions(ring: &CircularFreeRanges) -> Vec<(Span, Option<Span>)> {
+    ring.free_ranges().map(spans2).collect()
+}
+
+#[test]
+fn wrapping_free_coalesces_across_the_seam() {
+    let mut ring = CircularFreeRanges::new(20);
+    assert!(ring.set_wrapping_range_free(17, 8));
+    assert_eq!(regions(&ring), vec![((17, 19), Some((0, 4)))]);
+    assert!(!ring.set_wrapping_range_free(18, 3));
+
+    // Freeing the two halves separately joins them the same way
+    let mut ring = CircularFreeRanges::new(20);
+    ring.set_range_free(r(0, 4));
+    ring.set_range_free(r(17, 19));
+    assert_eq!(regions(&ring), vec![((17, 19), Some((0, 4)))]);
+    assert_eq!(
+        ring.free_run_at(2).map(spans2),
+        Some(((17, 19), Some((0, 4))))
+    );
+}
+
+#[test]
+fn wrapped_region_splits_when_used_across_the_seam() {
+    let mut ring = CircularFreeRanges::new(20);
+    ring.set_wrapping_range_free(17, 8);
+    ring.set_range_free(r(8, 9));
+    assert_eq!(
+        regions(&ring),
+        vec![((8, 9), None), ((17, 19), Some((0, 4)))]
+    );
+
+    assert_eq!(ring.allocate(5).map(spans2), Some(((17, 19), Some((0, 1)))));
+    assert_eq!(regions(&ring), vec![((2, 4), None), ((8, 9), None)]);
+
+    assert!(ring.set_wrapping_range_free(19, 3));
+    assert!(!ring.set_wrapping_range_used(18, 3));
+    assert!(ring.set_wrapping_range_used(19, 4));
+    assert_eq!(regions(&ring), vec![((3, 4), None), ((8, 9), None)]);
+}
+
+#[test]
+fn wrapping_use_within_one_stored_range() {
+    // Both parts of the wrapped run lie in the single range 0..=9
+    let mut ring = CircularFreeRanges::with_all_free(10);
+    assert!(ring.set_wrapping_range_used(8, 4));
+    assert_eq!(regions(&ring), vec![((2, 7), None)]);
+    assert!(!ring.is_free(8));
+    assert!(!ring.is_free(9));
+    assert!(!ring.is_free(0));
+
+    let mut ring = CircularFreeRanges::with_all_free(10);
+    assert!(ring.set_wrapping_range_used(9, 10));
+    assert_eq!(regions(&ring), vec![]);
+}
+
+#[test]
+fn wrapping_ranges_must_fit_in_the_ring() {
+    let mut ring = CircularFreeRanges::new(20);
+    assert!(!ring.set_wrapping_range_free(0, 21));
+    assert!(!ring.set_wrapping_range_free(20, 1));
+    assert!(ring.set_wrapping_range_free(5, 20));
+    assert_eq!(regions(&ring), vec![((0, 19), None)]);
+}
@@ -0,0 +1,47 @@
+#![allow(dead_code)]
+
+use std::borrow::Borrow;
+
+use free_ranges::Range;
+
+pub type Span = (usize, usize);
+
+/// A range as a `(min, max)` pair. Ranges compare equal whenever they
+/// overlap, so tests compare these instead to check the exact bounds
+pub fn span<R: Borrow<Range>>(range: R) -> Span {
+    let range = range.borrow();
+    (range.min, range.max)
+}
+
+/// Every range as a `(min, max)` pair, in order
+pub fn spans<I>(ranges: I) -> Vec<Span>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Range>,
+{
+    ranges.into_iter().map(span).collect()
+}
+
+#[inline]
+pub fn r(min: usize, max: usize) -> Range {
+    Range { min, max }
+}
+
+/// A small xorshift generator, so randomized tests are repeatable and
+/// don't need the `rand` feature
+pub struct XorShift(u32);
+
+impl XorShift {
+    #[inline]
+    pub fn new(seed: u32) -> Self {
+        XorShift(seed.max(1))
+    }
+
+    /// A number below `bound`
+    pub fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as usize % bound
+    }
+}
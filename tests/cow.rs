@@ -0,0 +1,87 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::thread;
+
+use common::{r, spans};
+use free_ranges::{CowFreeRanges, FreeRanges, FreeRangesView};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+/// 1000 ranges of 5, one every 10 indices
+fn striped() -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for i in 0..1000 {
+        ranges.set_range_free(r(i * 10, i * 10 + 4));
+    }
+    ranges
+}
+
+#[test]
+fn clones_share_storage_until_written() {
+    let mut ranges = CowFreeRanges::new(striped());
+    assert!(!ranges.is_shared());
+    let copy = ranges.clone();
+    assert!(ranges.is_shared());
+    assert!(copy.is_shared());
+    assert_eq!(&*ranges as *const FreeRanges, &*copy as *const FreeRanges);
+
+    assert!(ranges.set_used(0));
+    assert!(!ranges.is_shared());
+    assert!(!copy.is_shared());
+    assert!(!ranges.is_free(0));
+    assert!(copy.is_free(0));
+    assert_eq!(spans(copy.into_inner()), spans(striped()));
+}
+
+#[test]
+fn snapshots_are_unchanged_by_later_writes() {
+    let mut ranges = CowFreeRanges::new(striped());
+    let snapshot = ranges.snapshot();
+    assert!(ranges.is_shared());
+    ranges.set_used(0);
+    ranges.set_range_free(r(5, 9));
+    assert!(snapshot.is_free(0));
+    assert!(!snapshot.is_free(5));
+    assert_eq!(snapshot.range_count(), 1000);
+
+    // Converting back copies, so the snapshot stays as it was
+    let mut materialized = snapshot.to_free_ranges();
+    materialized.set_used(10);
+    assert!(snapshot.is_free(10));
+    assert_eq!(spans(&materialized)[..2], [(0, 4), (11, 14)]);
+}
+
+#[test]
+fn snapshots_are_read_across_threads_during_writes() {
+    assert_send_sync::<FreeRangesView>();
+    assert_send_sync::<CowFreeRanges>();
+
+    let mut ranges = CowFreeRanges::new(striped());
+    ranges.set_used(0);
+    let snapshot = ranges.snapshot();
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let snapshot = snapshot.clone();
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    assert!(!snapshot.is_free(0));
+                    assert!(snapshot.is_free(10));
+                    assert_eq!(snapshot.range_count(), 1000);
+                }
+            })
+        })
+        .collect();
+    for i in 1..500 {
+        ranges.set_used(i * 10);
+    }
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    assert_eq!(snapshot.range_count(), 1000);
+    assert!(snapshot.is_free(4990));
+    assert!(!ranges.is_free(4990));
+    assert_eq!(ranges.range_count(), 1000);
+}
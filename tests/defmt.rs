@@ -0,0 +1,124 @@
+#![cfg(feature = "defmt")]
+
+extern crate defmt;
+extern crate free_ranges;
+
+mod common;
+
+use std::cell::RefCell;
+use std::net::Ipv4Addr;
+
+use common::r;
+use defmt::Format;
+use free_ranges::{FreeRanges, Ipv4PoolError, Range, StrictFreeError};
+
+thread_local! {
+    static LOGGED: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Collects the raw, unframed bytes defmt writes on this thread
+#[defmt::global_logger]
+struct Logger;
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {}
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {}
+
+    unsafe fn write(bytes: &[u8]) {
+        LOGGED.with(|logged| logged.borrow_mut().extend_from_slice(bytes));
+    }
+}
+
+// Logging on the host needs a timestamp to link. This one is empty, so it
+// writes nothing
+defmt::timestamp!("");
+
+/// The bytes `value` is encoded as. This drops the index of the message's
+/// own format string, leaving the index of the value's format tag, then
+/// each `write!` as the index of its format string followed by its
+/// arguments, then a 0 u16 terminator. A usize goes out as a u32
+fn encode<T: Format>(value: &T) -> Vec<u8> {
+    LOGGED.with(|logged| logged.borrow_mut().clear());
+    defmt::println!("{}", value);
+    LOGGED.with(|logged| logged.borrow()[2..].to_vec())
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn contains(bytes: &[u8], part: &[u8]) -> bool {
+    bytes.windows(part.len()).any(|window| window == part)
+}
+
+fn with_ranges(count: usize) -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for i in 0..count {
+        ranges.set_range_free(r(i * 10, i * 10 + 4));
+    }
+    ranges
+}
+
+#[test]
+fn ranges_are_their_bounds() {
+    let bytes = encode(&r(3, 9));
+    assert_eq!(bytes.len(), 2 + 2 + 4 + 4 + 2);
+    assert_eq!((u32_at(&bytes, 4), u32_at(&bytes, 8)), (3, 9));
+    assert_eq!(&bytes[12..], &[0, 0]);
+
+    // The same format string, with different arguments
+    let other = encode(&r(70_000, 70_001));
+    assert_eq!(other[..4], bytes[..4]);
+    assert_eq!((u32_at(&other, 4), u32_at(&other, 8)), (70_000, 70_001));
+}
+
+#[test]
+fn free_ranges_log_at_most_four_ranges() {
+    let empty = encode(&FreeRanges::new());
+    assert_eq!(u32_at(&empty, 4), 0);
+    let four = encode(&with_ranges(4));
+    assert_eq!(u32_at(&four, 4), 4);
+    assert!(four.len() > empty.len());
+
+    // Past four only the count changes, plus the elision marker once
+    let five = encode(&with_ranges(5));
+    let hundred = encode(&with_ranges(100));
+    assert_eq!(u32_at(&five, 4), 5);
+    assert_eq!(u32_at(&hundred, 4), 100);
+    assert_eq!(five.len(), four.len() + 2);
+    assert_eq!(five[8..], hundred[8..]);
+    assert_eq!(five[..4], hundred[..4]);
+
+    let mut last = [0; 8];
+    last[..4].copy_from_slice(&30u32.to_le_bytes());
+    last[4..].copy_from_slice(&34u32.to_le_bytes());
+    assert!(contains(&four, &last));
+    assert!(contains(&hundred, &last));
+    last[..4].copy_from_slice(&40u32.to_le_bytes());
+    last[4..].copy_from_slice(&44u32.to_le_bytes());
+    assert!(!contains(&hundred, &last));
+}
+
+#[test]
+fn errors_carry_their_arguments() {
+    let empty = encode(&StrictFreeError::EmptyRange(Range { min: 5, max: 4 }));
+    let already = encode(&StrictFreeError::AlreadyFree(r(5, 4)));
+    assert_ne!(
+        empty[2..4],
+        already[2..4],
+        "each variant has its own message"
+    );
+    let mut bounds = 5u32.to_le_bytes().to_vec();
+    bounds.extend_from_slice(&4u32.to_le_bytes());
+    assert!(contains(&empty, &bounds));
+    assert!(contains(&already, &bounds));
+
+    let outside = encode(&Ipv4PoolError::OutsidePool(Ipv4Addr::new(10, 0, 0, 1)));
+    assert!(contains(&outside, &[10, 0, 0, 1]));
+    let prefix = encode(&Ipv4PoolError::InvalidPrefix(33));
+    assert_eq!(prefix.len(), 2 + 2 + 1 + 2);
+    assert_eq!(prefix[4], 33);
+}
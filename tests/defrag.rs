@@ -0,0 +1,108 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, spans};
+use free_ranges::{FreeRanges, Move};
+
+fn free(ranges: &FreeRanges) -> usize {
+    ranges
+        .free_ranges()
+        .map(|range| range.max - range.min + 1)
+        .sum()
+}
+
+fn longest(ranges: &FreeRanges) -> usize {
+    ranges
+        .free_ranges()
+        .map(|range| range.max - range.min + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Free 0..=9, 15..=29, 32..=49 and 60..=69, with 10..=14 and 30..=31
+/// used by movable extents
+fn fragmented() -> FreeRanges {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.set_range_used(r(10, 14));
+    ranges.set_range_used(r(30, 31));
+    ranges.set_range_used(r(50, 59));
+    ranges.set_range_used(r(70, 99));
+    ranges
+}
+
+#[test]
+fn applying_the_plan_frees_a_long_enough_run() {
+    let ranges = fragmented();
+    assert_eq!(longest(&ranges), 18);
+
+    for &target in &[19, 30, 40] {
+        let plan = ranges
+            .plan_defragmentation(&[r(10, 14), r(30, 31)], target)
+            .unwrap();
+        assert!(!plan.is_empty());
+        for m in &plan {
+            assert_eq!(m.from.max - m.from.min, m.to.max - m.to.min);
+        }
+
+        let mut applied = ranges.clone();
+        assert!(applied.apply_moves(&plan));
+        assert!(longest(&applied) >= target, "{:?} left {:?}", plan, applied);
+        assert_eq!(free(&applied), free(&ranges));
+    }
+}
+
+#[test]
+fn planning_an_already_free_run_needs_no_moves() {
+    let plan = fragmented().plan_defragmentation(&[r(10, 14)], 10);
+    assert_eq!(plan, Some(vec![]));
+}
+
+#[test]
+fn planning_fails_when_no_run_can_be_cleared() {
+    let ranges = fragmented();
+    assert_eq!(
+        ranges.plan_defragmentation(&[r(10, 14), r(30, 31)], 60),
+        None
+    );
+    // 50..=59 isn't movable, so nothing longer than 50 indices can be freed
+    assert_eq!(
+        ranges.plan_defragmentation(&[r(10, 14), r(30, 31)], 51),
+        None
+    );
+}
+
+#[test]
+fn invalid_moves_leave_the_set_untouched() {
+    let ranges = fragmented();
+    let before = spans(&ranges);
+
+    let mut applied = ranges.clone();
+    let bad = [
+        Move {
+            from: r(30, 31),
+            to: r(0, 1),
+        },
+        // 50..=54 is used
+        Move {
+            from: r(10, 14),
+            to: r(50, 54),
+        },
+    ];
+    assert!(!applied.apply_moves(&bad));
+    assert_eq!(spans(&applied), before);
+
+    // Mismatched lengths
+    let bad = [Move {
+        from: r(10, 14),
+        to: r(0, 3),
+    }];
+    assert!(!applied.apply_moves(&bad));
+    // A source which is still free
+    let bad = [Move {
+        from: r(0, 1),
+        to: r(20, 21),
+    }];
+    assert!(!applied.apply_moves(&bad));
+    assert_eq!(spans(&applied), before);
+}
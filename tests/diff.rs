@@ -0,0 +1,80 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, Span};
+use free_ranges::{DiffConflict, FreeRanges, FreeRangesDiff};
+
+fn free_spans(spans: &[Span]) -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for &(min, max) in spans {
+        ranges.set_range_free(r(min, max));
+    }
+    ranges
+}
+
+fn diff_spans(diff: &FreeRangesDiff) -> (Vec<Span>, Vec<Span>) {
+    (
+        diff.freed.iter().map(span).collect(),
+        diff.used.iter().map(span).collect(),
+    )
+}
+
+fn conflict(result: Result<(), DiffConflict>) -> Option<(bool, Span)> {
+    match result {
+        Ok(()) => None,
+        Err(DiffConflict::AlreadyFree(range)) => Some((true, span(range))),
+        Err(DiffConflict::AlreadyUsed(range)) => Some((false, span(range))),
+    }
+}
+
+#[test]
+fn diff_turns_one_set_into_another() {
+    let before = free_spans(&[(0, 9), (20, 29)]);
+    let after = free_spans(&[(0, 4), (15, 24), (30, 30)]);
+    let diff = before.diff(&after);
+    assert_eq!(
+        diff_spans(&diff),
+        (vec![(15, 19), (30, 30)], vec![(5, 9), (25, 29)])
+    );
+
+    let mut applied = before.clone();
+    applied.apply_diff(&diff).unwrap();
+    assert_eq!(spans(&applied), spans(&after));
+    assert!(before.diff(&before).is_empty());
+}
+
+#[test]
+fn apply_diff_reports_conflicts_without_changing_anything() {
+    let diff = free_spans(&[(0, 9), (20, 29)]).diff(&free_spans(&[(0, 4), (15, 24), (30, 30)]));
+
+    let mut already_free = free_spans(&[(0, 9), (17, 29)]);
+    assert_eq!(
+        conflict(already_free.apply_diff(&diff)),
+        Some((true, (17, 19)))
+    );
+    assert_eq!(spans(&already_free), vec![(0, 9), (17, 29)]);
+
+    let mut already_used = free_spans(&[(0, 7), (20, 29)]);
+    assert_eq!(
+        conflict(already_used.apply_diff(&diff)),
+        Some((false, (8, 9)))
+    );
+    assert_eq!(spans(&already_used), vec![(0, 7), (20, 29)]);
+}
+
+#[test]
+fn apply_diff_lossy_skips_the_conflicting_parts() {
+    let target = free_spans(&[(0, 4), (15, 24), (30, 30)]);
+    let diff = free_spans(&[(0, 9), (20, 29)]).diff(&target);
+
+    let mut already_used = free_spans(&[(0, 7), (20, 29)]);
+    let skipped = already_used.apply_diff_lossy(&diff);
+    assert_eq!(diff_spans(&skipped), (vec![], vec![(8, 9)]));
+    assert_eq!(spans(&already_used), spans(&target));
+
+    let mut already_free = free_spans(&[(0, 9), (17, 29)]);
+    let skipped = already_free.apply_diff_lossy(&diff);
+    assert_eq!(diff_spans(&skipped), (vec![(17, 19)], vec![]));
+    assert_eq!(spans(&already_free), spans(&target));
+}
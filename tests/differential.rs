@@ -0,0 +1,125 @@
+#![cfg(feature = "test-util")]
+
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, XorShift};
+use free_ranges::test_util::{assert_equivalent, Call, MockRangeAllocator, NaiveFreeSet};
+use free_ranges::{FreeRanges, Range, RangeAllocator};
+
+const DOMAIN: usize = 64;
+
+/// Applies the same random operation to both sets, checking that they
+/// give the same answers
+fn random_step(rng: &mut XorShift, ranges: &mut FreeRanges, model: &mut NaiveFreeSet) {
+    let index = rng.below(DOMAIN);
+    match rng.below(3) {
+        0 => assert_eq!(ranges.set_free(index), model.set_free(index)),
+        1 => assert_eq!(ranges.set_used(index), model.set_used(index)),
+        _ => {
+            let range = r(index, index + rng.below(8));
+            let all_free = (range.min..=range.max).all(|i| model.is_free(i));
+            assert_eq!(ranges.set_range_free(range), !all_free);
+            assert!(model.set_range_free(range) != all_free);
+        }
+    }
+}
+
+#[test]
+fn random_operations_match_the_naive_model() {
+    let mut rng = XorShift::new(3);
+    for _ in 0..200 {
+        let mut ranges = FreeRanges::new();
+        let mut model = NaiveFreeSet::new();
+        for _ in 0..50 {
+            random_step(&mut rng, &mut ranges, &mut model);
+            assert_equivalent(&ranges, &model);
+            for index in 0..DOMAIN + 8 {
+                assert_eq!(ranges.is_free(index), model.is_free(index));
+            }
+        }
+    }
+}
+
+#[test]
+fn the_model_coalesces_its_ranges() {
+    let mut model = NaiveFreeSet::new();
+    assert!(model.set_range_free(r(0, 3)));
+    assert!(model.set_free(5));
+    assert!(model.set_range_free(r(4, 4)));
+    assert!(!model.set_range_free(r(1, 5)));
+    assert!(model.set_used(2));
+    let spans: Vec<_> = model.free_ranges().iter().map(|r| (r.min, r.max)).collect();
+    assert_eq!(spans, vec![(0, 1), (3, 5)]);
+}
+
+#[test]
+#[should_panic(expected = "differs from the naive model")]
+fn assert_equivalent_catches_a_difference() {
+    let mut model = NaiveFreeSet::new();
+    model.set_range_free(r(0, 3));
+    assert_equivalent(&FreeRanges::with_initial_range(r(0, 4)), &model);
+}
+
+/// Takes up to `count` indices from the front of the pool, stopping early
+/// if it runs dry. Stands in for user code written against the trait
+fn take_front(pool: &mut dyn RangeAllocator, count: usize) -> Vec<usize> {
+    (0..count).map_while(|_| pool.set_first_used()).collect()
+}
+
+#[test]
+fn generic_code_agrees_with_the_model_through_the_trait() {
+    let mut rng = XorShift::new(11);
+    let mut ranges = FreeRanges::new();
+    let mut model = NaiveFreeSet::new();
+    for _ in 0..500 {
+        random_step(&mut rng, &mut ranges, &mut model);
+        let count = rng.below(4);
+        let taken = take_front(&mut ranges, count);
+        let first: Vec<usize> = model
+            .free_ranges()
+            .iter()
+            .flat_map(|r| r.min..=r.max)
+            .take(count)
+            .collect();
+        assert_eq!(taken, first);
+        for &index in &taken {
+            model.set_used(index);
+        }
+        assert_equivalent(&ranges, &model);
+    }
+}
+
+#[test]
+fn the_mock_records_calls_and_replays_answers() {
+    let mut mock = MockRangeAllocator::new();
+    mock.push_index(Some(4))
+        .push_index(Some(9))
+        .push_index(None);
+    assert_eq!(take_front(&mut mock, 5), vec![4, 9]);
+    assert_eq!(mock.calls(), vec![Call::SetFirstUsed; 3]);
+
+    let mut mock = MockRangeAllocator::new();
+    mock.push_bool(true)
+        .push_bool(false)
+        .set_free_ranges(vec![r(1, 2), r(7, 7)]);
+    assert!(mock.set_range_free(r(1, 2)));
+    assert!(!mock.is_free(3));
+    let ranges: Vec<Range> = mock.free_ranges().collect();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(
+        mock.calls(),
+        vec![
+            Call::SetRangeFree(r(1, 2)),
+            Call::IsFree(3),
+            Call::FreeRanges
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "no scripted answer left for SetUsed(3)")]
+fn the_mock_panics_without_an_answer() {
+    MockRangeAllocator::new().set_used(3);
+}
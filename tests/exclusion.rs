@@ -0,0 +1,126 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, Span};
+use free_ranges::{FreeRanges, Placement};
+
+fn excluded(ranges: &FreeRanges) -> Vec<Span> {
+    ranges.excluded_ranges().map(span).collect()
+}
+
+fn effective(ranges: &FreeRanges) -> Vec<Span> {
+    ranges.effective_free_ranges().map(span).collect()
+}
+
+/// 0..=9 and 20..=29 free, with exclusions overlapping each other and
+/// partly covering both free ranges
+fn quarantined() -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 9));
+    ranges.set_range_free(r(20, 29));
+    ranges.exclude(r(0, 4));
+    ranges.exclude(r(3, 6));
+    ranges.exclude(r(25, 40));
+    ranges
+}
+
+#[test]
+fn allocation_skips_excluded_space() {
+    let mut ranges = quarantined();
+    assert_eq!(excluded(&ranges), vec![(0, 6), (25, 40)]);
+    assert_eq!(effective(&ranges), vec![(7, 9), (20, 24)]);
+    assert!(ranges.is_free(0));
+
+    assert_eq!(ranges.set_first_used(), Some(7));
+    assert_eq!(ranges.set_last_used(), Some(24));
+    assert_eq!(ranges.allocate(3).map(span), Some((20, 22)));
+    assert_eq!(ranges.allocate(3), None);
+    assert_eq!(spans(&ranges), vec![(0, 6), (8, 9), (23, 23), (25, 29)]);
+
+    // Frees inside an exclusion are still tracked
+    assert!(ranges.set_free(30));
+    assert!(ranges.is_free(30));
+    assert_eq!(effective(&ranges), vec![(8, 9), (23, 23)]);
+}
+
+#[test]
+fn every_allocation_method_consults_the_mask() {
+    let mut ranges = quarantined();
+    ranges.set_first_used();
+    ranges.set_last_used();
+    ranges.allocate(3);
+
+    let multi = ranges.allocate_multi(2, 1).unwrap();
+    assert_eq!(
+        multi.into_iter().map(span).collect::<Vec<_>>(),
+        vec![(8, 8), (9, 9)]
+    );
+    match ranges.allocate_preferring(r(0, 0)) {
+        Ok(Placement::Relocated(range)) => assert_eq!(span(range), (23, 23)),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    ranges.unexclude(r(4, 26));
+    assert_eq!(excluded(&ranges), vec![(0, 3), (27, 40)]);
+    assert_eq!(ranges.allocate_whole(1).map(span), Some((25, 26)));
+    assert_eq!(ranges.allocate_aligned(2, 2).map(span), Some((4, 5)));
+}
+
+#[test]
+fn exclusions_follow_split_and_join() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.exclude(r(40, 59));
+    let higher = ranges.split_off(50);
+    assert_eq!(excluded(&higher), vec![(50, 59)]);
+    assert_eq!(excluded(&ranges), vec![(40, 49)]);
+    ranges.join(higher);
+    assert_eq!(effective(&ranges), vec![(0, 39), (60, 99)]);
+
+    assert_eq!(ranges.set_first_used_stride(10, 5), Some(5));
+    let best = ranges
+        .allocate_best_alignment(3, &[16])
+        .map(|(range, _)| span(range));
+    assert_eq!(best, Some((0, 2)));
+}
+
+#[test]
+fn drain_all_leaves_excluded_space_free() {
+    let mut ranges = quarantined();
+    let drained: Vec<_> = ranges.drain_all().into_iter().map(span).collect();
+    assert_eq!(drained, vec![(7, 9), (20, 24)]);
+    assert_eq!(spans(&ranges), vec![(0, 6), (25, 29)]);
+    assert!(ranges.effective_free_ranges().next().is_none());
+
+    ranges.unexclude(r(0, 40));
+    let drained: Vec<_> = ranges.drain_all().into_iter().map(span).collect();
+    assert_eq!(drained, vec![(0, 6), (25, 29)]);
+    assert_eq!(ranges.range_count(), 0);
+}
+
+#[test]
+fn holes_inside_one_free_range_are_stepped_around() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.exclude(r(10, 19));
+    ranges.exclude(r(50, 59));
+    assert_eq!(effective(&ranges), vec![(0, 9), (20, 49), (60, 99)]);
+
+    assert_eq!(ranges.allocate_fill_small(5).map(span), Some((0, 4)));
+    assert_eq!(ranges.allocate_tail_of_largest(5).map(span), Some((95, 99)));
+    assert_eq!(ranges.allocate_no_cross(15, 16).map(span), Some((32, 46)));
+    assert_eq!(ranges.set_last_used(), Some(94));
+
+    ranges.set_preference_tiers(vec![r(5, 25)]);
+    let tiered = ranges
+        .allocate_tiered(6)
+        .map(|(range, tier)| (span(range), tier));
+    assert_eq!(tiered, Some(((20, 25), Some(0))));
+
+    let (block, cursor) = ranges.allocate_from(12, 6).unwrap();
+    assert_eq!((span(block), cursor), ((26, 31), 32));
+    let (block, _) = ranges.allocate_from(90, 8).unwrap();
+    assert_eq!(span(block), (60, 67));
+
+    assert_eq!(spans(&ranges), vec![(5, 19), (47, 59), (68, 93)]);
+    assert_eq!(excluded(&ranges), vec![(10, 19), (50, 59)]);
+}
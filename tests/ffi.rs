@@ -0,0 +1,178 @@
+#![cfg(feature = "ffi")]
+
+extern crate free_ranges;
+
+use std::ptr;
+
+use free_ranges::ffi::free_ranges_status_t::*;
+use free_ranges::ffi::*;
+
+fn empty_buffer() -> [free_ranges_range_t; 2] {
+    [free_ranges_range_t { min: 0, max: 0 }; 2]
+}
+
+#[test]
+fn every_entry_point_on_a_live_handle() {
+    unsafe {
+        let handle = free_ranges_new();
+        assert!(!handle.is_null());
+        let (mut changed, mut free, mut index) = (false, false, 0);
+
+        assert_eq!(
+            free_ranges_set_free(handle, 5, &mut changed),
+            FREE_RANGES_OK
+        );
+        assert!(changed);
+        assert_eq!(
+            free_ranges_set_free(handle, 5, &mut changed),
+            FREE_RANGES_OK
+        );
+        assert!(!changed);
+        assert_eq!(
+            free_ranges_set_range_free(handle, 10, 20, &mut changed),
+            FREE_RANGES_OK
+        );
+        assert!(changed);
+        assert_eq!(
+            free_ranges_set_range_free(handle, 30, 40, ptr::null_mut()),
+            FREE_RANGES_OK
+        );
+
+        assert_eq!(free_ranges_is_free(handle, 15, &mut free), FREE_RANGES_OK);
+        assert!(free);
+        assert_eq!(free_ranges_is_free(handle, 25, &mut free), FREE_RANGES_OK);
+        assert!(!free);
+        assert_eq!(free_ranges_first(handle, &mut index), FREE_RANGES_OK);
+        assert_eq!(index, 5);
+        assert_eq!(free_ranges_last(handle, &mut index), FREE_RANGES_OK);
+        assert_eq!(index, 40);
+
+        assert_eq!(
+            free_ranges_set_first_used(handle, &mut index),
+            FREE_RANGES_OK
+        );
+        assert_eq!(index, 5);
+        assert_eq!(
+            free_ranges_set_used(handle, 15, &mut changed),
+            FREE_RANGES_OK
+        );
+        assert!(changed);
+        assert_eq!(
+            free_ranges_set_used(handle, 15, &mut changed),
+            FREE_RANGES_OK
+        );
+        assert!(!changed);
+
+        // 10..=14, 16..=20 and 30..=40 are left, two at a time
+        let (mut cursor, mut written) = (0, 0);
+        let mut buffer = empty_buffer();
+        let status = free_ranges_iterate(handle, &mut cursor, buffer.as_mut_ptr(), 2, &mut written);
+        assert_eq!(status, FREE_RANGES_MORE);
+        assert_eq!(written, 2);
+        assert_eq!((buffer[0].min, buffer[0].max), (10, 14));
+        assert_eq!((buffer[1].min, buffer[1].max), (16, 20));
+        let status = free_ranges_iterate(handle, &mut cursor, buffer.as_mut_ptr(), 2, &mut written);
+        assert_eq!(status, FREE_RANGES_OK);
+        assert_eq!(written, 1);
+        assert_eq!((buffer[0].min, buffer[0].max), (30, 40));
+
+        free_ranges_destroy(handle);
+    }
+}
+
+#[test]
+fn empty_and_full_handles() {
+    unsafe {
+        let mut index = 0;
+        let empty = free_ranges_new();
+        assert_eq!(free_ranges_first(empty, &mut index), FREE_RANGES_NOT_FOUND);
+        assert_eq!(free_ranges_last(empty, &mut index), FREE_RANGES_NOT_FOUND);
+        assert_eq!(
+            free_ranges_set_first_used(empty, &mut index),
+            FREE_RANGES_NOT_FOUND
+        );
+        let (mut cursor, mut written) = (0, 1);
+        let status = free_ranges_iterate(empty, &mut cursor, ptr::null_mut(), 0, &mut written);
+        assert_eq!((status, written), (FREE_RANGES_OK, 0));
+        free_ranges_destroy(empty);
+
+        let full = free_ranges_new_all_free();
+        assert!(!full.is_null());
+        assert_eq!(free_ranges_first(full, &mut index), FREE_RANGES_OK);
+        assert_eq!(index, 0);
+        assert_eq!(free_ranges_last(full, &mut index), FREE_RANGES_OK);
+        assert_eq!(index, usize::MAX);
+        free_ranges_destroy(full);
+    }
+}
+
+#[test]
+fn null_handles_and_bad_arguments_are_reported() {
+    unsafe {
+        let (mut changed, mut free, mut index, mut cursor, mut written) = (false, false, 0, 0, 0);
+        let mut buffer = empty_buffer();
+        let null = ptr::null_mut();
+        assert_eq!(
+            free_ranges_set_free(null, 1, &mut changed),
+            FREE_RANGES_NULL_POINTER
+        );
+        assert_eq!(
+            free_ranges_set_used(null, 1, &mut changed),
+            FREE_RANGES_NULL_POINTER
+        );
+        assert_eq!(
+            free_ranges_set_range_free(null, 1, 2, &mut changed),
+            FREE_RANGES_NULL_POINTER
+        );
+        assert_eq!(
+            free_ranges_is_free(null, 1, &mut free),
+            FREE_RANGES_NULL_POINTER
+        );
+        assert_eq!(
+            free_ranges_first(null, &mut index),
+            FREE_RANGES_NULL_POINTER
+        );
+        assert_eq!(free_ranges_last(null, &mut index), FREE_RANGES_NULL_POINTER);
+        assert_eq!(
+            free_ranges_set_first_used(null, &mut index),
+            FREE_RANGES_NULL_POINTER
+        );
+        assert_eq!(
+            free_ranges_iterate(null, &mut cursor, buffer.as_mut_ptr(), 2, &mut written),
+            FREE_RANGES_NULL_POINTER
+        );
+        free_ranges_destroy(null);
+
+        let handle = free_ranges_new();
+        assert_eq!(
+            free_ranges_set_range_free(handle, 30, 20, &mut changed),
+            FREE_RANGES_INVALID_ARGUMENT
+        );
+        // Required output pointers
+        assert_eq!(
+            free_ranges_is_free(handle, 1, ptr::null_mut()),
+            FREE_RANGES_NULL_POINTER
+        );
+        assert_eq!(
+            free_ranges_iterate(
+                handle,
+                ptr::null_mut(),
+                buffer.as_mut_ptr(),
+                2,
+                &mut written
+            ),
+            FREE_RANGES_NULL_POINTER
+        );
+        assert_eq!(
+            free_ranges_iterate(handle, &mut cursor, ptr::null_mut(), 2, &mut written),
+            FREE_RANGES_NULL_POINTER
+        );
+        // Optional ones
+        assert_eq!(
+            free_ranges_set_free(handle, 1, ptr::null_mut()),
+            FREE_RANGES_OK
+        );
+        assert_eq!(free_ranges_first(handle, ptr::null_mut()), FREE_RANGES_OK);
+        free_ranges_destroy(handle);
+    }
+}
@@ -0,0 +1,45 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+use common::{r, span, spans};
+use free_ranges::FreeRanges;
+
+#[test]
+fn guards_free_their_block_unless_leaked() {
+    let cell = RefCell::new(FreeRanges::with_initial_range(r(0, 9)));
+    {
+        let guard = FreeRanges::allocate_guarded(&cell, 3).unwrap();
+        assert_eq!(span(*guard), (0, 2));
+        assert_eq!(guard.max, 2);
+        assert!(!cell.borrow().is_free(1));
+    }
+    assert!(cell.borrow().is_free(1));
+
+    let leaked = FreeRanges::allocate_guarded(&cell, 3).unwrap().leak();
+    assert_eq!(span(leaked), (0, 2));
+    assert!(!cell.borrow().is_free(leaked.min));
+
+    let guard = FreeRanges::allocate_guarded(&cell, 2).unwrap();
+    assert_eq!(span(*guard), (3, 4));
+    guard.free();
+    assert!(cell.borrow().is_free(3));
+    assert!(FreeRanges::allocate_guarded(&cell, 8).is_none());
+    assert_eq!(spans(&*cell.borrow()), vec![(3, 9)]);
+}
+
+#[test]
+fn guards_free_their_block_while_unwinding() {
+    let cell = RefCell::new(FreeRanges::with_initial_range(r(0, 9)));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _first = FreeRanges::allocate_guarded(&cell, 3).unwrap();
+        let _second = FreeRanges::allocate_guarded(&cell, 7).unwrap();
+        assert_eq!(cell.borrow().first(), None);
+        panic!("early exit");
+    }));
+    assert!(result.is_err());
+    assert_eq!(spans(&*cell.borrow()), vec![(0, 9)]);
+}
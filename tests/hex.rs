@@ -0,0 +1,43 @@
+#[macro_use]
+extern crate free_ranges;
+
+mod common;
+
+use common::r;
+use free_ranges::FreeRanges;
+
+#[test]
+fn ranges_format_as_grouped_hex() {
+    let page = r(0x1_0000_0000, 0x1_0000_0fff);
+    assert_eq!(format!("{:x}", page), "1_0000_0000..=1_0000_0fff");
+    assert_eq!(format!("{:#x}", page), "0x1_0000_0000..=0x1_0000_0fff");
+    assert_eq!(format!("{:X}", page), "1_0000_0000..=1_0000_0FFF");
+    assert_eq!(format!("{:#X}", page), "0x1_0000_0000..=0x1_0000_0FFF");
+
+    // Groups of four digits, counted from the right
+    assert_eq!(format!("{:x}", r(0, 0xffff)), "0..=ffff");
+    assert_eq!(format!("{:x}", r(0xf, 0x1_0000)), "f..=1_0000");
+    assert_eq!(
+        format!("{:x}", r(0x10000, usize::MAX)),
+        "1_0000..=ffff_ffff_ffff_ffff"
+    );
+}
+
+#[test]
+fn memory_map_dumps() {
+    let map = free_ranges![0x10..=0x1f, 0xa000..=0x1_2fff];
+    assert_eq!(
+        map.display_hex().to_string(),
+        "0x10..=0x1f\n0xa000..=0x1_2fff"
+    );
+    assert_eq!(
+        format!("{:X}", map.display_hex()),
+        "0x10..=0x1F\n0xA000..=0x1_2FFF"
+    );
+    assert_eq!(
+        map.display_hex_within(r(0x18, 0xb000)).to_string(),
+        "0x18..=0x1f\n0xa000..=0xb000"
+    );
+    assert_eq!(map.display_hex_within(r(0x20, 0x9fff)).to_string(), "");
+    assert_eq!(FreeRanges::new().display_hex().to_string(), "");
+}
@@ -0,0 +1,190 @@
+#[macro_use]
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, spans, Span, XorShift};
+use free_ranges::{FreeRanges, HistoryError, HistoryFreeRanges, Range};
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Free(Range),
+    Used(Range),
+    FreeOne(usize),
+    UseOne(usize),
+    Allocate(usize),
+}
+
+fn apply(ranges: &mut FreeRanges, op: Op) {
+    match op {
+        Op::Free(range) => {
+            ranges.set_range_free(range);
+        }
+        Op::Used(range) => {
+            ranges.set_range_used(range);
+        }
+        Op::FreeOne(index) => {
+            ranges.set_free(index);
+        }
+        Op::UseOne(index) => {
+            ranges.set_used(index);
+        }
+        Op::Allocate(size) => {
+            ranges.allocate(size);
+        }
+    }
+}
+
+fn apply_recorded(history: &mut HistoryFreeRanges, op: Op) {
+    match op {
+        Op::Free(range) => {
+            history.set_range_free(range);
+        }
+        Op::Used(range) => {
+            history.set_range_used(range);
+        }
+        Op::FreeOne(index) => {
+            history.set_free(index);
+        }
+        Op::UseOne(index) => {
+            history.set_used(index);
+        }
+        Op::Allocate(size) => {
+            history.allocate(size);
+        }
+    }
+}
+
+fn base() -> FreeRanges {
+    let mut ranges = free_ranges![10..=60, 80..=90];
+    ranges.add_barrier(30);
+    ranges
+}
+
+/// The ranges after replaying every group of `applied` onto `base`
+fn replay(applied: &[Vec<Op>]) -> Vec<Span> {
+    let mut ranges = base();
+    for &op in applied.iter().flatten() {
+        apply(&mut ranges, op);
+    }
+    spans(&ranges)
+}
+
+fn random_op(rng: &mut XorShift) -> Op {
+    let index = rng.below(100);
+    let range = r(index, index + rng.below(15));
+    match rng.below(5) {
+        0 => Op::Free(range),
+        1 => Op::Used(range),
+        2 => Op::FreeOne(index),
+        3 => Op::UseOne(index),
+        _ => Op::Allocate(rng.below(7) + 1),
+    }
+}
+
+#[test]
+fn random_walk_matches_a_reference_replay() {
+    let mut rng = XorShift::new(11);
+    for _ in 0..30 {
+        let mut history = HistoryFreeRanges::new(base());
+        history.enable_history(1000);
+        // Each group is one undoable record: a single call, or the calls
+        // grouped by commit_action
+        let mut applied: Vec<Vec<Op>> = Vec::new();
+        let mut undone: Vec<Vec<Op>> = Vec::new();
+        let mut uncommitted = 0;
+
+        for _ in 0..300 {
+            match rng.below(10) {
+                0..=6 => {
+                    let op = random_op(&mut rng);
+                    let before = replay(&applied);
+                    apply_recorded(&mut history, op);
+                    // Calls that change nothing leave no record
+                    if spans(history.ranges()) != before {
+                        applied.push(vec![op]);
+                        undone.clear();
+                        uncommitted += 1;
+                    }
+                    if rng.below(4) == 0 {
+                        assert_eq!(history.commit_action("group"), uncommitted > 0);
+                        if uncommitted > 0 {
+                            let at = applied.len() - uncommitted;
+                            let group = applied.drain(at..).flatten().collect();
+                            applied.push(group);
+                            uncommitted = 0;
+                        }
+                    }
+                }
+                7 | 8 => match history.undo() {
+                    Ok(_) => {
+                        uncommitted = 0;
+                        undone.push(applied.pop().unwrap());
+                    }
+                    Err(error) => {
+                        assert_eq!(error, HistoryError::NothingToUndo);
+                        assert!(applied.is_empty());
+                    }
+                },
+                _ => match history.redo() {
+                    Ok(_) => {
+                        uncommitted = 0;
+                        applied.push(undone.pop().unwrap());
+                    }
+                    Err(error) => {
+                        assert_eq!(error, HistoryError::NothingToRedo);
+                        assert!(undone.is_empty());
+                    }
+                },
+            }
+            assert_eq!(spans(history.ranges()), replay(&applied));
+            assert_eq!(history.can_undo(), !applied.is_empty());
+            assert_eq!(history.can_redo(), !undone.is_empty());
+        }
+    }
+}
+
+#[test]
+fn undo_returns_the_labels() {
+    let mut history = HistoryFreeRanges::new(FreeRanges::new());
+    history.set_free(1);
+    // Nothing is recorded until history is enabled
+    assert_eq!(history.undo(), Err(HistoryError::NothingToUndo));
+
+    history.enable_history(2);
+    history.set_free(5);
+    history.set_free(6);
+    assert!(history.commit_action("pair"));
+    assert!(!history.commit_action("nothing"));
+    history.set_free(8);
+    assert_eq!(history.undo(), Ok(None));
+    assert_eq!(history.undo(), Ok(Some("pair".to_string())));
+    assert_eq!(spans(history.ranges()), vec![(1, 1)]);
+    assert_eq!(history.redo(), Ok(Some("pair".to_string())));
+    assert_eq!(spans(history.ranges()), vec![(1, 1), (5, 6)]);
+}
+
+#[test]
+fn a_new_action_drops_the_redo_stack() {
+    let mut history = HistoryFreeRanges::new(FreeRanges::new());
+    history.enable_history(4);
+    history.set_free(5);
+    history.undo().unwrap();
+    assert!(history.can_redo());
+    history.set_free(20);
+    assert_eq!(history.redo(), Err(HistoryError::NothingToRedo));
+    assert_eq!(spans(history.ranges()), vec![(20, 20)]);
+}
+
+#[test]
+fn history_keeps_only_the_last_depth_records() {
+    let mut history = HistoryFreeRanges::new(FreeRanges::new());
+    history.enable_history(2);
+    history.set_free(20);
+    history.set_free(30);
+    history.set_free(40);
+    history.undo().unwrap();
+    history.undo().unwrap();
+    assert_eq!(history.undo(), Err(HistoryError::NothingToUndo));
+    assert_eq!(spans(history.ranges()), vec![(20, 20)]);
+}
@@ -0,0 +1,65 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, spans};
+use free_ranges::{FreeRanges, HoldingFreeRanges};
+
+const MAX: usize = usize::MAX;
+
+#[test]
+fn holds_are_hidden_from_allocation() {
+    let mut holding = HoldingFreeRanges::new(FreeRanges::with_initial_range(r(0, 99)));
+    let _a = holding.hold(r(0, 9), 5).unwrap();
+    assert!(holding.hold(r(5, 15), 5).is_none());
+    let _b = holding.hold(r(20, 29), 10).unwrap();
+
+    assert_eq!(holding.allocate(5), Some(r(10, 14)));
+    assert_eq!(holding.set_first_used(), Some(15));
+    assert!(holding.is_held(3) && !holding.is_free(3));
+    assert!(!holding.set_free(3));
+    assert!(!holding.set_used(3));
+    assert_eq!(holding.held_ranges().count(), 2);
+}
+
+#[test]
+fn freeing_over_a_hold_skips_it() {
+    let mut holding = HoldingFreeRanges::new(FreeRanges::new());
+    holding.set_range_free(r(0, 40));
+    holding.hold(r(0, 9), 5).unwrap();
+    holding.hold(r(20, 29), 5).unwrap();
+    holding.set_range_free(r(0, 40));
+    assert!(!holding.is_free(5) && holding.is_free(10));
+    assert!(!holding.is_free(25) && holding.is_free(30));
+    assert_eq!(spans(holding.ranges()), vec![(10, 19), (30, 40)]);
+
+    let mut full = HoldingFreeRanges::new(FreeRanges::with_all_free());
+    full.hold(r(MAX - 1, MAX), 0).unwrap();
+    full.set_range_free(r(0, MAX));
+    assert!(full.is_held(MAX));
+    assert_eq!(spans(full.ranges()), vec![(0, MAX - 2)]);
+}
+
+#[test]
+fn holds_end_by_confirm_cancel_or_sweep() {
+    let mut holding = HoldingFreeRanges::new(FreeRanges::with_initial_range(r(0, 99)));
+    let a = holding.hold(r(0, 9), 5).unwrap();
+    let b = holding.hold(r(20, 29), 10).unwrap();
+    let c = holding.hold(r(40, 49), 10).unwrap();
+
+    assert_eq!(holding.sweep(4), 0);
+    assert_eq!(holding.sweep(5), 1);
+    assert!(holding.is_free(5) && !holding.is_held(5));
+    assert_eq!(holding.cancel(a), None);
+
+    assert_eq!(holding.confirm(b), Some(r(20, 29)));
+    assert!(!holding.is_free(25) && !holding.is_held(25));
+    assert_eq!(holding.confirm(b), None);
+
+    assert_eq!(holding.cancel(c), Some(r(40, 49)));
+    assert!(holding.is_free(45));
+    assert_eq!(holding.sweep(100), 0);
+
+    holding.set_range_free(r(20, 29));
+    assert_eq!(spans(holding.ranges()), vec![(0, 99)]);
+}
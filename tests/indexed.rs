@@ -0,0 +1,96 @@
+#[macro_use]
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, XorShift};
+use free_ranges::{FreeRanges, IndexedFreeRanges};
+
+const MAX: usize = usize::MAX;
+
+#[test]
+fn bitmap_matches_the_ranges_under_random_operations() {
+    const DOMAIN: usize = 400;
+    let mut rng = XorShift::new(0x5eed);
+    for round in 0..20 {
+        let mut indexed = IndexedFreeRanges::new(free_ranges![120..=180]);
+        let mut model = free_ranges![120..=180];
+        // The index covers part of the domain, so operations straddle it
+        indexed.enable_bitmap_index(r(100, 299));
+        assert_eq!(indexed.memory_usage(), 4 * 8);
+
+        for step in 0..500 {
+            let a = rng.below(DOMAIN);
+            let b = a + rng.below(90);
+            let op = rng.below(6);
+            let (got, expected) = match op {
+                0 => (indexed.set_free(a) as usize, model.set_free(a) as usize),
+                1 => (indexed.set_used(a) as usize, model.set_used(a) as usize),
+                2 => (
+                    indexed.set_range_free(r(a, b)) as usize,
+                    model.set_range_free(r(a, b)) as usize,
+                ),
+                3 => (
+                    indexed.set_range_used(r(a, b)) as usize,
+                    model.set_range_used(r(a, b)) as usize,
+                ),
+                4 => (
+                    indexed.allocate(b - a + 1).map_or(MAX, |r| r.min),
+                    model.allocate(b - a + 1).map_or(MAX, |r| r.min),
+                ),
+                _ => (
+                    indexed.set_first_used().unwrap_or(MAX),
+                    model.set_first_used().unwrap_or(MAX),
+                ),
+            };
+            assert_eq!(got, expected, "op {} on ({}, {})", op, a, b);
+            if round == 5 && step == 250 {
+                // Rebuilding over a word boundary mid-stream
+                indexed.enable_bitmap_index(r(63, 64));
+            }
+
+            for i in 0..DOMAIN {
+                assert_eq!(indexed.is_free(i), model.is_free(i), "index {}", i);
+            }
+            for _ in 0..20 {
+                let min = rng.below(DOMAIN);
+                let range = r(min, min + rng.below(130));
+                assert_eq!(indexed.is_range_free(range), model.is_range_free(range));
+            }
+        }
+        assert_eq!(spans(indexed.ranges()), spans(&model));
+    }
+}
+
+#[test]
+fn bitmap_at_the_end_of_the_domain() {
+    let mut indexed = IndexedFreeRanges::new(FreeRanges::with_all_free());
+    indexed.enable_bitmap_index(r(MAX - 10, MAX));
+    assert_eq!(indexed.bitmap_domain().map(span), Some((MAX - 10, MAX)));
+    assert!(indexed.is_range_free(r(MAX - 10, MAX)));
+    assert!(indexed.set_used(MAX));
+    assert!(!indexed.is_free(MAX));
+    assert!(indexed.is_free(MAX - 1));
+    assert!(!indexed.is_range_free(r(MAX - 10, MAX)));
+    assert!(indexed.is_range_free(r(0, MAX - 1)));
+}
+
+#[test]
+fn enabling_and_disabling_the_bitmap() {
+    let mut indexed = IndexedFreeRanges::new(free_ranges![0..=9]);
+    assert_eq!(indexed.bitmap_domain(), None);
+    assert_eq!(indexed.memory_usage(), 0);
+
+    indexed.enable_bitmap_index(r(0, 127));
+    assert_eq!(indexed.memory_usage(), 2 * 8);
+    indexed.disable_bitmap_index();
+    assert_eq!(indexed.bitmap_domain(), None);
+    assert_eq!(indexed.memory_usage(), 0);
+    assert!(indexed.is_range_free(r(0, 9)));
+
+    // An empty domain turns the index off
+    indexed.enable_bitmap_index(r(0, 127));
+    let (min, max) = (5, 4);
+    indexed.enable_bitmap_index(r(min, max));
+    assert_eq!(indexed.bitmap_domain(), None);
+}
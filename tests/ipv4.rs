@@ -0,0 +1,104 @@
+extern crate free_ranges;
+
+use std::net::Ipv4Addr;
+
+use free_ranges::{Ipv4Pool, Ipv4PoolError};
+
+fn ip(s: &str) -> Ipv4Addr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn cidr_pool_leases_in_address_order() {
+    let mut pool = Ipv4Pool::from_cidr(ip("192.168.1.77"), 24).unwrap();
+    assert_eq!(pool.bounds(), (ip("192.168.1.0"), ip("192.168.1.255")));
+    assert!(!pool.is_available(ip("192.168.1.0")));
+    assert!(!pool.is_available(ip("192.168.1.255")));
+
+    assert_eq!(pool.lease(), Some(ip("192.168.1.1")));
+    assert!(pool.reserve(ip("192.168.1.2")));
+    assert!(!pool.reserve(ip("192.168.1.2")));
+    assert_eq!(pool.lease(), Some(ip("192.168.1.3")));
+
+    assert_eq!(
+        pool.release(ip("10.0.0.1")),
+        Err(Ipv4PoolError::OutsidePool(ip("10.0.0.1")))
+    );
+    assert_eq!(
+        pool.release(ip("192.168.1.4")),
+        Err(Ipv4PoolError::AlreadyAvailable(ip("192.168.1.4")))
+    );
+    pool.release(ip("192.168.1.1")).unwrap();
+    assert_eq!(
+        pool.available_ranges().collect::<Vec<_>>(),
+        vec![
+            (ip("192.168.1.1"), ip("192.168.1.1")),
+            (ip("192.168.1.4"), ip("192.168.1.254")),
+        ]
+    );
+
+    assert!(pool.reserve_range(ip("192.168.1.10")..=ip("192.168.1.20")));
+    assert!(!pool.reserve_range(ip("192.168.1.10")..=ip("192.168.1.20")));
+    assert!(!pool.is_available(ip("192.168.1.15")));
+}
+
+#[test]
+fn release_rejects_the_network_and_broadcast_addresses() {
+    let mut pool = Ipv4Pool::from_cidr(ip("10.0.0.0"), 30).unwrap();
+    for &end in &["10.0.0.0", "10.0.0.3"] {
+        assert_eq!(
+            pool.release(ip(end)),
+            Err(Ipv4PoolError::NetworkOrBroadcast(ip(end)))
+        );
+        assert!(!pool.is_available(ip(end)));
+    }
+    assert_eq!(pool.lease(), Some(ip("10.0.0.1")));
+    assert_eq!(pool.lease(), Some(ip("10.0.0.2")));
+    assert_eq!(pool.lease(), None);
+
+    let mut all = Ipv4Pool::from_cidr_all(ip("10.0.0.0"), 30).unwrap();
+    assert_eq!(all.lease(), Some(ip("10.0.0.0")));
+    all.release(ip("10.0.0.0")).unwrap();
+    assert!(all.reserve(ip("10.0.0.3")));
+    all.release(ip("10.0.0.3")).unwrap();
+}
+
+#[test]
+fn small_subnets_have_no_network_or_broadcast_address() {
+    let mut pool = Ipv4Pool::from_cidr(ip("10.0.0.1"), 31).unwrap();
+    assert_eq!(pool.lease(), Some(ip("10.0.0.0")));
+    assert_eq!(pool.lease(), Some(ip("10.0.0.1")));
+    assert_eq!(pool.lease(), None);
+    pool.release(ip("10.0.0.0")).unwrap();
+
+    let mut pool = Ipv4Pool::from_cidr(ip("10.0.0.7"), 32).unwrap();
+    assert_eq!(pool.bounds(), (ip("10.0.0.7"), ip("10.0.0.7")));
+    assert_eq!(pool.lease(), Some(ip("10.0.0.7")));
+    assert_eq!(pool.lease(), None);
+    pool.release(ip("10.0.0.7")).unwrap();
+}
+
+#[test]
+fn prefixes_and_ranges_are_validated() {
+    let pool = Ipv4Pool::from_cidr(ip("1.2.3.4"), 0).unwrap();
+    assert_eq!(pool.bounds(), (ip("0.0.0.0"), ip("255.255.255.255")));
+    assert_eq!(
+        Ipv4Pool::from_cidr(ip("1.2.3.4"), 33).unwrap_err(),
+        Ipv4PoolError::InvalidPrefix(33)
+    );
+    assert_eq!(
+        Ipv4Pool::from_range(ip("10.0.0.2")..=ip("10.0.0.1")).unwrap_err(),
+        Ipv4PoolError::EmptyRange(ip("10.0.0.2"), ip("10.0.0.1"))
+    );
+}
+
+#[test]
+fn addresses_keep_their_byte_order() {
+    // 10.0.0.255 and 10.0.1.0 are adjacent once the octets are read big
+    // endian, so the pool crosses the octet boundary in order
+    let mut pool = Ipv4Pool::from_range(ip("10.0.0.255")..=ip("10.0.1.1")).unwrap();
+    assert_eq!(pool.lease(), Some(ip("10.0.0.255")));
+    assert_eq!(pool.lease(), Some(ip("10.0.1.0")));
+    assert_eq!(pool.lease(), Some(ip("10.0.1.1")));
+    assert_eq!(pool.lease(), None);
+}
@@ -0,0 +1,153 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, spans};
+use free_ranges::{FreeRanges, ScanToken};
+
+#[test]
+fn owned_iterator_reports_its_exact_length() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_free(1);
+    ranges.set_free(5);
+    ranges.set_range_free(r(9, 12));
+    let count = ranges.range_count();
+
+    let mut iter = ranges.into_iter();
+    assert_eq!(iter.len(), count);
+    assert_eq!(iter.size_hint(), (3, Some(3)));
+    iter.next();
+    assert_eq!(iter.len(), 2);
+    assert_eq!(spans(iter), vec![(5, 5), (9, 12)]);
+}
+
+#[test]
+fn owned_iterator_of_an_empty_set() {
+    let iter = FreeRanges::new().into_iter();
+    assert_eq!(iter.len(), 0);
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+}
+
+#[test]
+fn borrowed_and_owned_iteration_agree() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 3));
+    ranges.set_range_free(r(7, 7));
+    let borrowed = spans(&ranges);
+    assert_eq!(spans(ranges), borrowed);
+}
+
+fn striped() -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for i in 0..10 {
+        ranges.set_range_free(r(i * 10, i * 10 + 3));
+    }
+    ranges
+}
+
+#[test]
+fn scan_pages_through_every_range_once() {
+    let ranges = striped();
+    let mut seen = Vec::new();
+    let mut token = None;
+    loop {
+        let (page, next) = ranges.scan_from(token, 3);
+        assert!(page.len() <= 3);
+        seen.extend(spans(page));
+        match next {
+            Some(next) => token = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(seen, spans(&ranges));
+
+    let (page, token) = ranges.scan_from(None, 10);
+    assert_eq!(page.len(), 10);
+    assert!(token.is_none());
+}
+
+#[test]
+fn scan_resumes_after_the_token_despite_mutations() {
+    let mut ranges = striped();
+    let (first, token) = ranges.scan_from(None, 3);
+    assert_eq!(spans(first), vec![(0, 3), (10, 13), (20, 23)]);
+    let token = token.unwrap();
+    assert_eq!(token.into_raw(), 24);
+
+    // Freed behind the cursor: not seen this pass
+    ranges.set_range_free(r(5, 7));
+    // Grows across the cursor: only the part after it is returned
+    ranges.set_range_free(r(23, 26));
+    // Used ahead of the cursor: skipped
+    ranges.set_range_used(r(30, 33));
+
+    let (second, token) = ranges.scan_from(Some(token), 3);
+    assert_eq!(spans(second), vec![(24, 26), (40, 43), (50, 53)]);
+
+    // The raw value round-trips
+    let token = ScanToken::from_raw(token.unwrap().into_raw());
+    ranges.set_range_free(r(44, 49));
+    let (rest, token) = ranges.scan_from(Some(token), 100);
+    assert_eq!(spans(rest), vec![(60, 63), (70, 73), (80, 83), (90, 93)]);
+    assert!(token.is_none());
+}
+
+#[test]
+fn scan_at_the_top_of_the_domain() {
+    let all = FreeRanges::with_all_free();
+    let (page, token) = all.scan_from(None, 1);
+    assert_eq!(spans(page), vec![(0, usize::MAX)]);
+    assert!(token.is_none());
+
+    let (page, token) = all.scan_from(Some(ScanToken::from_raw(usize::MAX)), 1);
+    assert_eq!(spans(page), vec![(usize::MAX, usize::MAX)]);
+    assert!(token.is_none());
+
+    assert_eq!(FreeRanges::new().scan_from(None, 5), (vec![], None));
+}
+
+#[test]
+fn free_offsets_are_start_and_len() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 4));
+    ranges.set_free(10);
+    ranges.set_range_free(r(20, 29));
+    let offsets: Vec<_> = ranges.free_offsets().collect();
+    assert_eq!(offsets, vec![(0, 5), (10, 1), (20, 10)]);
+}
+
+#[test]
+fn free_offsets_saturate_for_the_full_domain() {
+    let all: Vec<_> = FreeRanges::with_all_free().free_offsets().collect();
+    assert_eq!(all, vec![(0, usize::MAX)]);
+
+    let mut ranges = FreeRanges::with_all_free();
+    ranges.set_used(0);
+    let rest: Vec<_> = ranges.free_offsets().collect();
+    assert_eq!(rest, vec![(1, usize::MAX)]);
+}
+
+#[test]
+fn aligned_free_ranges_trim_to_multiples() {
+    let mut ranges = FreeRanges::new();
+    for &(min, max) in &[(1, 7), (9, 30), (33, 47), (100, 100), (3000, 3000)] {
+        ranges.set_range_free(r(min, max));
+    }
+    // 1..=7 and 100 hold no multiple of 8; 3000 is one
+    assert_eq!(
+        spans(ranges.aligned_free_ranges(8)),
+        vec![(16, 24), (40, 40), (3000, 3000)]
+    );
+    assert_eq!(spans(ranges.aligned_free_ranges(1)), spans(&ranges));
+    assert_eq!(spans(ranges.aligned_free_ranges(0)), spans(&ranges));
+}
+
+#[test]
+fn aligned_free_ranges_at_the_end_of_the_domain() {
+    let max = usize::MAX;
+    let top = FreeRanges::with_initial_range(r(max - 20, max));
+    assert_eq!(spans(top.aligned_free_ranges(8)), vec![(max - 15, max - 7)]);
+    let all = FreeRanges::with_all_free();
+    let half = 1 << (usize::BITS - 1);
+    assert_eq!(spans(all.aligned_free_ranges(half)), vec![(0, half)]);
+}
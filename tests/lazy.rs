@@ -0,0 +1,74 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{spans, XorShift};
+use free_ranges::{FreeRanges, LazyFreeRanges};
+
+#[test]
+fn queries_see_buffered_frees() {
+    let mut lazy = FreeRanges::with_lazy_merge();
+    assert!(lazy.set_free(5));
+    assert!(lazy.set_free(3));
+    assert!(lazy.set_free(4));
+    assert!(!lazy.set_free(4));
+    assert_eq!(lazy.pending_len(), 3);
+    assert!(lazy.is_free(4) && !lazy.is_free(6));
+    assert_eq!(lazy.first(), Some(3));
+
+    assert_eq!(lazy.set_first_used(), Some(3));
+    assert!(!lazy.is_free(3));
+    assert!(lazy.set_used(5));
+    assert!(!lazy.set_used(5));
+
+    assert_eq!(spans(lazy.ranges()), vec![(4, 4)]);
+    assert_eq!(lazy.pending_len(), 0);
+}
+
+#[test]
+fn the_buffer_flushes_at_the_threshold() {
+    let mut lazy = LazyFreeRanges::new(FreeRanges::new(), 2);
+    lazy.set_free(1);
+    assert_eq!(lazy.pending_len(), 1);
+    lazy.set_free(2);
+    assert_eq!(lazy.pending_len(), 0);
+    lazy.set_free(3);
+    assert_eq!(lazy.pending_len(), 1);
+    assert_eq!(spans(lazy.into_inner()), vec![(1, 3)]);
+}
+
+#[test]
+fn random_operations_match_a_bitmap() {
+    const SIZE: usize = 40;
+    let mut rng = XorShift::new(0x9e37_79b9);
+    let mut lazy = LazyFreeRanges::new(FreeRanges::new(), 5);
+    let mut model = [false; SIZE];
+    for _ in 0..5_000 {
+        let index = rng.below(SIZE);
+        match rng.below(4) {
+            0 | 1 => {
+                assert_eq!(lazy.set_free(index), !model[index]);
+                model[index] = true;
+            }
+            2 => {
+                assert_eq!(lazy.set_used(index), model[index]);
+                model[index] = false;
+            }
+            _ => {
+                let first = model.iter().position(|&free| free);
+                assert_eq!(lazy.set_first_used(), first);
+                if let Some(first) = first {
+                    model[first] = false;
+                }
+            }
+        }
+        for (i, &free) in model.iter().enumerate() {
+            assert_eq!(lazy.is_free(i), free, "index {}", i);
+        }
+    }
+
+    let ranges = lazy.into_inner();
+    for (i, &free) in model.iter().enumerate() {
+        assert_eq!(ranges.is_free(i), free, "index {}", i);
+    }
+}
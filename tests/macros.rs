@@ -0,0 +1,25 @@
+#[macro_use]
+extern crate free_ranges;
+
+mod common;
+
+use common::spans;
+use free_ranges::FreeRanges;
+
+#[test]
+fn free_ranges_macro_mixes_indices_and_ranges() {
+    let ranges = free_ranges![0..=4, 9, 20..30, 5, 40..40];
+    assert_eq!(spans(&ranges), vec![(0, 5), (9, 9), (20, 29)]);
+
+    let trailing = free_ranges![0..=4, 9,];
+    assert_eq!(spans(&trailing), vec![(0, 4), (9, 9)]);
+
+    let empty: FreeRanges = free_ranges![];
+    assert!(empty.is_exhausted());
+}
+
+#[test]
+#[should_panic(expected = "overlaps an earlier item")]
+fn free_ranges_macro_panics_on_overlap() {
+    let _ = free_ranges![0..=4, 3];
+}
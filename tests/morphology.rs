@@ -0,0 +1,53 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, spans, XorShift};
+use free_ranges::FreeRanges;
+
+const MAX: usize = usize::MAX;
+
+fn free_spans(list: &[(usize, usize)]) -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for &(min, max) in list {
+        ranges.set_range_free(r(min, max));
+    }
+    ranges
+}
+
+fn is_subset(a: &FreeRanges, b: &FreeRanges) -> bool {
+    a.free_ranges().all(|&range| b.is_range_free(range))
+}
+
+#[test]
+fn erode_and_dilate_keep_the_domain_edges() {
+    let ranges = free_spans(&[(0, 4), (10, 20), (25, 26), (30, MAX)]);
+    assert_eq!(spans(ranges.erode(2)), vec![(0, 2), (12, 18), (32, MAX)]);
+    assert_eq!(spans(ranges.dilate(2)), vec![(0, 6), (8, MAX)]);
+    assert_eq!(spans(ranges.erode(0)), spans(&ranges));
+    assert_eq!(spans(ranges.dilate(0)), spans(&ranges));
+
+    assert_eq!(spans(free_spans(&[(0, 3)]).erode(5)), vec![]);
+    assert_eq!(spans(free_spans(&[(0, 3)]).erode(3)), vec![(0, 0)]);
+    assert_eq!(spans(free_spans(&[(MAX - 3, MAX)]).erode(5)), vec![]);
+    assert_eq!(spans(free_spans(&[(5, 5)]).dilate(MAX)), vec![(0, MAX)]);
+    assert_eq!(
+        spans(FreeRanges::with_all_free().erode(MAX)),
+        vec![(0, MAX)]
+    );
+}
+
+#[test]
+fn opening_is_contained_and_closing_contains() {
+    let mut rng = XorShift::new(0xd1_1a7e);
+    for _ in 0..300 {
+        let mut ranges = FreeRanges::new();
+        for _ in 0..8 {
+            let min = rng.below(300);
+            ranges.set_range_free(r(min, min + rng.below(20)));
+        }
+        let g = rng.below(6);
+        assert!(is_subset(&ranges.erode(g).dilate(g), &ranges));
+        assert!(is_subset(&ranges, &ranges.dilate(g).erode(g)));
+    }
+}
@@ -0,0 +1,381 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, Span, XorShift};
+use free_ranges::{CoalesceInfo, FreeRanges, Range, StrictFreeError};
+
+const MAX: usize = usize::MAX;
+
+#[test]
+fn set_used_splits_at_the_domain_edges() {
+    let mut ranges = FreeRanges::with_all_free();
+    assert_eq!(ranges.set_last_used(), Some(MAX));
+    assert_eq!(ranges.set_first_used(), Some(0));
+    assert!(ranges.set_used(5));
+    assert!(!ranges.set_used(5));
+    assert_eq!(spans(ranges.free_ranges()), vec![(1, 4), (6, MAX - 1)]);
+}
+
+#[test]
+fn set_last_used_empties_a_single_index() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_free(0);
+    assert_eq!(ranges.set_last_used(), Some(0));
+    assert_eq!(ranges.first(), None);
+}
+
+#[test]
+fn set_free_merges_at_the_domain_edges() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_free(MAX);
+    ranges.set_free(0);
+    ranges.set_free(MAX - 1);
+    ranges.set_free(1);
+    assert_eq!(spans(ranges.free_ranges()), vec![(0, 1), (MAX - 1, MAX)]);
+
+    ranges.set_range_free(r(10, 12));
+    ranges.set_range_free(r(20, 22));
+    ranges.set_range_free(r(2, 25));
+    assert_eq!(spans(ranges.free_ranges()), vec![(0, 25), (MAX - 1, MAX)]);
+
+    ranges.set_range_free(r(26, MAX - 2));
+    assert_eq!(spans(ranges.free_ranges()), vec![(0, MAX)]);
+}
+
+#[test]
+fn drain_all_exhausts_the_pool() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 9));
+    ranges.set_range_free(r(20, 29));
+    assert!(!ranges.is_exhausted());
+
+    let drained = ranges.drain_all();
+    assert!(ranges.is_exhausted());
+    assert_eq!(spans(&drained), vec![(0, 9), (20, 29)]);
+    assert_eq!(drained.iter().map(|r| r.max - r.min + 1).sum::<usize>(), 20);
+    assert_eq!(ranges.first(), None);
+
+    assert_eq!(ranges.drain_all().len(), 0);
+    assert!(ranges.is_exhausted());
+}
+
+#[test]
+fn grow_frees_the_new_tail_and_merges() {
+    let mut ranges = FreeRanges::new();
+    assert_eq!(span(ranges.grow(0, 256)), (0, 255));
+    ranges.set_used(0);
+    assert_eq!(span(ranges.grow(256, 256)), (256, 511));
+    assert_eq!(spans(&ranges), vec![(1, 511)]);
+
+    // Not merged when the old last index is used
+    ranges.set_used(511);
+    ranges.grow(512, 1);
+    assert_eq!(spans(&ranges), vec![(1, 510), (512, 512)]);
+
+    let mut top = FreeRanges::new();
+    assert_eq!(span(top.grow(MAX, 1)), (MAX, MAX));
+}
+
+#[test]
+#[should_panic(expected = "at least one")]
+fn grow_by_nothing_panics() {
+    FreeRanges::new().grow(5, 0);
+}
+
+#[test]
+#[should_panic(expected = "overflows")]
+fn grow_past_the_domain_panics() {
+    FreeRanges::new().grow(MAX, 2);
+}
+
+#[test]
+fn shrink_fails_while_the_tail_is_used() {
+    let mut ranges = FreeRanges::with_initial_range(r(1, 511));
+    ranges.set_used(300);
+    assert!(!ranges.shrink(512, 256));
+    assert_eq!(spans(&ranges), vec![(1, 299), (301, 511)]);
+
+    assert!(ranges.shrink(512, 400));
+    assert_eq!(spans(&ranges), vec![(1, 299), (301, 399)]);
+    // Same or larger lengths have nothing to drop
+    assert!(ranges.shrink(400, 400));
+    assert!(ranges.shrink(400, 500));
+    assert!(ranges.shrink(400, 301));
+    assert_eq!(spans(&ranges), vec![(1, 299)]);
+}
+
+#[test]
+fn without_matches_set_range_used_on_a_copy() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 9));
+    ranges.set_range_free(r(20, 29));
+
+    for &cut in &[r(5, 24), r(0, 9), r(10, 19), r(3, 3), r(0, MAX), r(29, MAX)] {
+        let without = ranges.without(cut);
+        let mut used = ranges.clone();
+        used.set_range_used(cut);
+        assert_eq!(spans(&without), spans(&used), "without {:?}", cut);
+        assert_eq!(spans(&ranges), vec![(0, 9), (20, 29)]);
+    }
+    assert_eq!(spans(ranges.without(r(5, 24))), vec![(0, 4), (25, 29)]);
+}
+
+fn strict_error(result: Result<(), StrictFreeError>) -> Option<Span> {
+    match result {
+        Ok(()) => None,
+        Err(StrictFreeError::AlreadyFree(range)) => Some(span(range)),
+        Err(error) => panic!("unexpected {:?}", error),
+    }
+}
+
+#[test]
+fn set_range_free_strict_reports_the_first_overlap() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(10, 19));
+    ranges.set_range_free(r(30, 39));
+
+    // At the start, end and middle, and an exact duplicate
+    assert_eq!(
+        strict_error(ranges.set_range_free_strict(r(5, 12))),
+        Some((10, 12))
+    );
+    assert_eq!(
+        strict_error(ranges.set_range_free_strict(r(15, 25))),
+        Some((15, 19))
+    );
+    assert_eq!(
+        strict_error(ranges.set_range_free_strict(r(0, 50))),
+        Some((10, 19))
+    );
+    assert_eq!(
+        strict_error(ranges.set_range_free_strict(r(32, 33))),
+        Some((32, 33))
+    );
+    assert_eq!(
+        strict_error(ranges.set_range_free_strict(r(30, 39))),
+        Some((30, 39))
+    );
+    assert_eq!(spans(&ranges), vec![(10, 19), (30, 39)]);
+
+    assert_eq!(strict_error(ranges.set_range_free_strict(r(20, 29))), None);
+    assert_eq!(spans(&ranges), vec![(10, 39)]);
+}
+
+#[test]
+fn set_range_free_strict_rejects_empty_ranges() {
+    let mut ranges = FreeRanges::with_initial_range(r(10, 19));
+    let error = ranges.set_range_free_strict(r(5, 4)).unwrap_err();
+    assert_eq!(error, StrictFreeError::EmptyRange(r(5, 4)));
+    assert_eq!(spans(&ranges), vec![(10, 19)]);
+    assert_eq!(error.to_string(), "range (5...4) is empty");
+}
+
+#[test]
+fn evict_smallest_goes_by_size_then_start() {
+    let mut ranges = FreeRanges::new();
+    for &(min, max) in &[(0, 4), (10, 10), (20, 22), (30, 30), (40, 41)] {
+        ranges.set_range_free(r(min, max));
+    }
+    let evicted: Vec<_> = std::iter::from_fn(|| ranges.evict_smallest())
+        .map(span)
+        .collect();
+    assert_eq!(
+        evicted,
+        vec![(10, 10), (30, 30), (40, 41), (20, 22), (0, 4)]
+    );
+    assert_eq!(ranges.range_count(), 0);
+    assert_eq!(ranges.evict_smallest(), None);
+}
+
+#[test]
+fn evict_smallest_handles_the_full_domain() {
+    let mut ranges = FreeRanges::with_all_free();
+    ranges.set_used(5);
+    assert_eq!(ranges.evict_smallest().map(span), Some((0, 4)));
+    assert_eq!(ranges.evict_smallest().map(span), Some((6, usize::MAX)));
+}
+
+/// What `set_used_strided` should do, one `set_used` at a time
+fn set_used_stepping(ranges: &mut FreeRanges, window: Range, step: usize, offset: usize) -> usize {
+    let mut marked = 0;
+    let mut index = window.min.checked_add(offset);
+    while let Some(i) = index.filter(|&i| i <= window.max) {
+        marked += ranges.set_used(i) as usize;
+        if step == 0 {
+            break;
+        }
+        index = i.checked_add(step);
+    }
+    marked
+}
+
+#[test]
+fn set_used_strided_matches_stepping_through_set_used() {
+    let mut rng = XorShift::new(0x0bad_5eed);
+    for _ in 0..500 {
+        let mut ranges = FreeRanges::new();
+        for _ in 0..10 {
+            let min = rng.below(200);
+            ranges.set_range_free(r(min, min + rng.below(40)));
+        }
+        let mut expected = ranges.clone();
+        let min = rng.below(200);
+        let window = r(min, min + rng.below(100));
+        let (step, offset) = (rng.below(10), rng.below(15));
+
+        let marked = set_used_stepping(&mut expected, window, step, offset);
+        assert_eq!(ranges.set_used_strided(window, step, offset), marked);
+        assert_eq!(spans(&ranges), spans(&expected));
+    }
+}
+
+#[test]
+fn set_used_strided_edges() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 9));
+    // Step 0 marks only the first index, and an offset past the window
+    // marks nothing
+    assert_eq!(ranges.set_used_strided(r(0, 9), 0, 2), 1);
+    assert_eq!(ranges.set_used_strided(r(0, 9), 3, 10), 0);
+    assert_eq!(spans(&ranges), vec![(0, 1), (3, 9)]);
+    // Step 1 marks the rest of the window
+    assert_eq!(ranges.set_used_strided(r(0, 9), 1, 5), 5);
+    assert_eq!(spans(&ranges), vec![(0, 1), (3, 4)]);
+
+    let mut all = FreeRanges::with_all_free();
+    assert_eq!(all.set_used_strided(r(MAX - 10, MAX), 5, 0), 3);
+    assert!(!all.is_free(MAX) && all.is_free(MAX - 1));
+    assert_eq!(all.set_used_strided(r(MAX, MAX), 5, 1), 0);
+}
+
+#[test]
+fn clear_keeps_barriers_and_exclusions_for_the_refill() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.add_barrier(49);
+    ranges.exclude(r(0, 9));
+    ranges.set_used(70);
+    ranges.clear();
+    assert_eq!(ranges.free_ranges().count(), 0);
+    assert_eq!(ranges.first(), None);
+    assert_eq!(ranges.barriers().collect::<Vec<_>>(), vec![49]);
+    assert_eq!(spans(ranges.excluded_ranges()), vec![(0, 9)]);
+
+    for _ in 0..3 {
+        ranges.set_range_free(r(0, 99));
+        assert_eq!(spans(ranges.free_ranges()), vec![(0, 49), (50, 99)]);
+        assert_eq!(ranges.allocate(5).map(span), Some((10, 14)));
+        ranges.clear();
+        assert_eq!(ranges.free_ranges().count(), 0);
+    }
+}
+
+#[test]
+fn set_free_grows_past_the_highest_index() {
+    let mut ranges = FreeRanges::new();
+    for &index in &[3, 10, 11, 100, 1000, MAX - 1, MAX] {
+        assert!(ranges.set_free(index));
+        assert_eq!(ranges.last(), Some(index));
+    }
+    assert!(!ranges.set_free(MAX));
+    assert_eq!(
+        spans(&ranges),
+        vec![(3, 3), (10, 11), (100, 100), (1000, 1000), (MAX - 1, MAX)]
+    );
+
+    // Filling the gaps in from above merges across them
+    for index in (4..10).rev() {
+        ranges.set_free(index);
+    }
+    assert_eq!(
+        spans(ranges.free_ranges().take(2)),
+        vec![(3, 11), (100, 100)]
+    );
+}
+
+fn neighbors(info: CoalesceInfo) -> (Option<Span>, Option<Span>) {
+    (info.front.map(span), info.back.map(span))
+}
+
+#[test]
+fn coalescing_neighbors_takes_out_what_touches() {
+    let mut ranges = FreeRanges::new();
+    for &(min, max) in &[(0, 4), (7, 8), (10, 14), (20, MAX)] {
+        ranges.set_range_free(r(min, max));
+    }
+    let info = ranges.coalescing_neighbors(r(5, 9));
+    assert_eq!(neighbors(info), (Some((0, 4)), Some((10, 14))));
+    assert_eq!(spans(&ranges), vec![(7, 8), (20, MAX)]);
+
+    let info = ranges.coalescing_neighbors(r(25, 30));
+    assert_eq!(neighbors(info), (Some((20, MAX)), None));
+    assert_eq!(
+        neighbors(ranges.coalescing_neighbors(r(0, 3))),
+        (None, None)
+    );
+    assert_eq!(spans(&ranges), vec![(7, 8)]);
+}
+
+#[test]
+fn coalescing_neighbors_at_the_domain_edges() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 9));
+    let info = ranges.coalescing_neighbors(r(0, 3));
+    assert_eq!(neighbors(info), (None, Some((0, 9))));
+    assert_eq!(ranges.first(), None);
+
+    let mut ranges = FreeRanges::with_all_free();
+    let info = ranges.coalescing_neighbors(r(1, MAX));
+    assert_eq!(neighbors(info), (Some((0, MAX)), None));
+    assert_eq!(ranges.first(), None);
+}
+
+#[test]
+fn bounds_entry_points_convert_like_from_bounds() {
+    let mut ranges = FreeRanges::new();
+    assert!(!ranges.set_free_bounds(..0));
+    assert!(ranges.set_free_bounds(10..20));
+    assert!(ranges.set_free_bounds(30..));
+    assert_eq!(
+        ranges.free_indices_bounds(18..=31).collect::<Vec<_>>(),
+        vec![18, 19, 30, 31]
+    );
+    assert_eq!(ranges.free_indices_bounds(..0).count(), 0);
+
+    assert!(ranges.set_used_bounds(..=10));
+    assert!(!ranges.set_used_bounds(5..5));
+    assert!(ranges.is_free(11));
+    assert!(!ranges.is_free(10));
+
+    ranges.retain_bounds(15..=40);
+    assert_eq!(spans(&ranges), vec![(15, 19), (30, 40)]);
+    ranges.retain_bounds(..0);
+    assert_eq!(ranges.free_ranges().count(), 0);
+
+    let mut all = FreeRanges::with_all_free();
+    all.retain_bounds(..);
+    assert_eq!(spans(&all), vec![(0, MAX)]);
+    assert!(all.set_used_bounds(MAX..));
+    assert_eq!(spans(&all), vec![(0, MAX - 1)]);
+}
+
+#[test]
+fn set_range_free_clamped_drops_the_part_past_the_bound() {
+    let mut ranges = FreeRanges::new();
+    assert!(ranges.set_range_free_clamped(r(90, 150), 99));
+    assert_eq!(spans(&ranges), vec![(90, 99)]);
+    assert!(!ranges.is_free(100));
+
+    // Nothing within the bound, or all of it already free
+    assert!(!ranges.set_range_free_clamped(r(100, 150), 99));
+    assert!(!ranges.set_range_free_clamped(r(95, MAX), 99));
+    assert_eq!(spans(&ranges), vec![(90, 99)]);
+
+    assert!(ranges.set_range_free_clamped(r(0, MAX), 99));
+    assert_eq!(spans(&ranges), vec![(0, 99)]);
+    let (min, max) = (5, 3);
+    assert!(!ranges.set_range_free_clamped(r(min, max), 99));
+    assert_eq!(spans(&ranges), vec![(0, 99)]);
+
+    let mut all = FreeRanges::new();
+    assert!(all.set_range_free_clamped(r(0, MAX), MAX));
+    assert_eq!(spans(&all), vec![(0, MAX)]);
+}
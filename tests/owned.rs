@@ -0,0 +1,70 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, XorShift};
+use free_ranges::{FreeRanges, OwnedRanges};
+
+#[test]
+fn owners_free_parts_of_their_ranges() {
+    let mut owned = OwnedRanges::new(FreeRanges::with_initial_range(r(0, 99)));
+    assert_eq!(owned.allocate_for("a", 10).map(span), Some((0, 9)));
+    assert_eq!(owned.allocate_for("b", 10).map(span), Some((10, 19)));
+    assert_eq!(owned.allocate_for("a", 5).map(span), Some((20, 24)));
+    assert_eq!(owned.owner_of(12), Some(&"b"));
+    assert_eq!(owned.owner_of(30), None);
+
+    // The middle of an allocation, spanning two, or nothing allocated at all
+    assert!(owned.free_range(r(3, 5)).is_err());
+    assert!(owned.free_range(r(8, 12)).is_err());
+    assert!(owned.free_range(r(30, 31)).is_err());
+
+    owned.free_range(r(0, 2)).unwrap();
+    assert_eq!(owned.owner_of(2), None);
+    assert_eq!(owned.owner_of(3), Some(&"a"));
+
+    owned.free_range(r(18, 19)).unwrap();
+    owned.free_range(r(10, 17)).unwrap();
+    assert!(owned.free_range(r(10, 10)).is_err());
+
+    assert_eq!(owned.release_owner(&"a"), 2);
+    assert_eq!(owned.release_owner(&"a"), 0);
+    assert_eq!(spans(owned.ranges()), vec![(0, 99)]);
+}
+
+#[test]
+fn ownership_and_free_space_never_overlap() {
+    let mut rng = XorShift::new(0xfeed_beef);
+    let mut owned = OwnedRanges::new(FreeRanges::with_initial_range(r(0, 199)));
+    for _ in 0..2_000 {
+        match rng.below(4) {
+            0 => {
+                owned.allocate_for(rng.below(4), rng.below(7) + 1);
+            }
+            1 => {
+                owned.release_owner(&rng.below(4));
+            }
+            _ => {
+                let allocations: Vec<_> = owned.allocations().map(|(range, _)| range).collect();
+                if !allocations.is_empty() {
+                    let range = allocations[rng.below(allocations.len())];
+                    let k = rng.below(range.max - range.min + 1);
+                    let part = if rng.below(2) == 0 {
+                        r(range.min, range.min + k)
+                    } else {
+                        r(range.max - k, range.max)
+                    };
+                    owned.free_range(part).unwrap();
+                }
+            }
+        }
+        for i in 0..200 {
+            assert_ne!(
+                owned.ranges().is_free(i),
+                owned.owner_of(i).is_some(),
+                "index {}",
+                i
+            );
+        }
+    }
+}
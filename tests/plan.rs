@@ -0,0 +1,97 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, Span, XorShift};
+use free_ranges::{AllocRequest, FreeRanges, PlanResult};
+
+/// The planned blocks, or the index of the request which failed
+fn planned(result: PlanResult) -> Result<Vec<Span>, usize> {
+    match result {
+        PlanResult::Fits(blocks) => Ok(spans(blocks)),
+        PlanResult::Fails { index } => Err(index),
+    }
+}
+
+#[test]
+fn plans_alignment_and_windows_in_order() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 9));
+    ranges.set_range_free(r(13, 40));
+    let requests = [
+        AllocRequest::new(5),
+        AllocRequest::new(8).aligned(8),
+        AllocRequest::new(3).within(r(30, 35)),
+        AllocRequest::new(5),
+    ];
+    let expected = vec![(0, 4), (16, 23), (30, 32), (5, 9)];
+    assert_eq!(planned(ranges.plan(&requests)), Ok(expected.clone()));
+    assert_eq!(spans(&ranges), vec![(0, 9), (13, 40)]);
+
+    let requests = [AllocRequest::new(20), AllocRequest::new(20)];
+    assert_eq!(planned(ranges.plan(&requests)), Err(1));
+    assert_eq!(planned(ranges.plan(&[])), Ok(vec![]));
+}
+
+#[test]
+fn plans_match_a_real_run() {
+    let mut rng = XorShift::new(0x91a2);
+    for _ in 0..300 {
+        let mut ranges = FreeRanges::new();
+        for _ in 0..10 {
+            let min = rng.below(400);
+            ranges.set_range_free(r(min, min + rng.below(30)));
+        }
+        if rng.below(2) == 0 {
+            let min = rng.below(400);
+            ranges.exclude(r(min, min + rng.below(20)));
+        }
+        let requests: Vec<AllocRequest> = (0..rng.below(7) + 1)
+            .map(|_| {
+                let mut request = AllocRequest::new(rng.below(12));
+                if rng.below(2) == 0 {
+                    request = request.aligned(rng.below(9));
+                }
+                if rng.below(2) == 0 {
+                    let min = rng.below(400);
+                    request = request.within(r(min, min + rng.below(80)));
+                }
+                request
+            })
+            .collect();
+
+        let before = spans(&ranges);
+        let plan = planned(ranges.plan(&requests));
+        assert_eq!(spans(&ranges), before);
+
+        let mut real = Vec::new();
+        let mut failed = None;
+        for (i, request) in requests.iter().enumerate() {
+            match ranges.allocate_request(request) {
+                Some(block) => real.push(span(block)),
+                None => {
+                    failed = Some(i);
+                    break;
+                }
+            }
+        }
+        match plan {
+            Ok(blocks) => assert_eq!((blocks, failed), (real.clone(), None)),
+            Err(index) => assert_eq!(failed, Some(index)),
+        }
+        for &(min, max) in &real {
+            assert!(ranges.excluded_ranges().all(|e| max < e.min || min > e.max));
+        }
+    }
+}
+
+#[test]
+fn a_plain_request_allocates_like_allocate() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 20));
+    let mut copy = ranges.clone();
+    let request = AllocRequest::new(4);
+    assert_eq!(
+        ranges.allocate_request(&request).map(span),
+        copy.allocate(4).map(span)
+    );
+    assert_eq!(spans(&ranges), spans(&copy));
+}
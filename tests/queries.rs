@@ -0,0 +1,624 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, Span, XorShift};
+use free_ranges::FreeRanges;
+
+const MAX: usize = usize::MAX;
+
+fn sample() -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 3));
+    ranges.set_range_free(r(10, 12));
+    ranges.set_range_free(r(20, 30));
+    ranges
+}
+
+#[test]
+fn count_ranges_in_windows() {
+    let ranges = sample();
+    assert_eq!(ranges.range_count(), 3);
+    assert_eq!(ranges.count_ranges_in(r(4, 9)), 0);
+    assert_eq!(ranges.count_ranges_in(r(3, 9)), 1);
+    assert_eq!(ranges.count_ranges_in(r(12, 12)), 1);
+    assert_eq!(ranges.count_ranges_in(r(3, 20)), 3);
+    assert_eq!(ranges.count_ranges_in(r(0, MAX)), 3);
+    assert_eq!(ranges.count_ranges_in(r(31, MAX)), 0);
+}
+
+#[test]
+fn max_aligned_free_subtracts_padding() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    ranges.set_range_free(r(16, 20));
+    assert_eq!(ranges.max_aligned_free(1), 10);
+    assert_eq!(ranges.max_aligned_free(0), 10);
+    // 1..=10 only has 8..=10 past a multiple of 8, so 16..=20 wins
+    assert_eq!(ranges.max_aligned_free(8), 5);
+    assert_eq!(ranges.max_aligned_free(32), 0);
+}
+
+#[test]
+fn max_aligned_free_at_the_domain_edges() {
+    assert_eq!(FreeRanges::with_all_free().max_aligned_free(4), MAX);
+    let mut ranges = FreeRanges::new();
+    ranges.set_free(MAX);
+    assert_eq!(ranges.max_aligned_free(2), 0);
+    assert_eq!(ranges.max_aligned_free(1), 1);
+}
+
+#[test]
+fn free_indices_stride_steps_through_each_range() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    ranges.set_range_free(r(12, 13));
+    ranges.set_range_free(r(20, 25));
+    let stride = |modulus, remainder| -> Vec<usize> {
+        ranges.free_indices_stride(modulus, remainder).collect()
+    };
+    assert_eq!(stride(4, 1), vec![1, 5, 9, 13, 21, 25]);
+    // The remainder is reduced modulo the modulus
+    assert_eq!(stride(4, 5), vec![1, 5, 9, 13, 21, 25]);
+    assert_eq!(stride(1, 0).len(), 18);
+    // A modulus of 0 only matches the remainder itself
+    assert_eq!(stride(0, 12), vec![12]);
+    assert_eq!(stride(0, 11), Vec::<usize>::new());
+}
+
+#[test]
+fn free_indices_stride_near_the_top_of_the_domain() {
+    let top = FreeRanges::with_initial_range(r(MAX, MAX));
+    assert_eq!(top.free_indices_stride(10, 9).count(), 0);
+    assert_eq!(
+        top.free_indices_stride(10, 5).collect::<Vec<_>>(),
+        vec![MAX]
+    );
+    assert_eq!(
+        top.free_indices_stride(MAX, 0).collect::<Vec<_>>(),
+        vec![MAX]
+    );
+    assert_eq!(top.free_indices_stride(MAX, 1).count(), 0);
+
+    let tail = FreeRanges::with_initial_range(r(MAX - 5, MAX));
+    let found: Vec<usize> = tail.free_indices_stride(4, 3).collect();
+    assert_eq!(found, vec![MAX - 4, MAX]);
+    assert_eq!(tail.free_indices_stride(2, 0).count(), 3);
+}
+
+#[test]
+fn set_first_used_stride_allocates_in_the_pattern() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    assert_eq!(ranges.set_first_used_stride(4, 2), Some(2));
+    assert_eq!(ranges.set_first_used_stride(4, 2), Some(6));
+    assert_eq!(ranges.set_first_used_stride(4, 2), Some(10));
+    assert_eq!(ranges.set_first_used_stride(4, 2), None);
+    assert!(!ranges.is_free(6) && ranges.is_free(7));
+
+    let mut top = FreeRanges::with_initial_range(r(MAX, MAX));
+    assert_eq!(top.set_first_used_stride(10, 9), None);
+    assert_eq!(top.set_first_used_stride(10, 5), Some(MAX));
+}
+
+#[test]
+fn sorted_queries_match_is_free() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    ranges.set_range_free(r(12, 13));
+    ranges.set_range_free(r(20, 25));
+    let indices = [0, 1, 1, 10, 11, 12, 14, 19, 20, 25, 26, 1000, MAX];
+    let expected: Vec<bool> = indices.iter().map(|&i| ranges.is_free(i)).collect();
+    assert_eq!(ranges.are_free_sorted(&indices), expected);
+    assert!(!ranges.all_free_sorted(&indices));
+    assert!(ranges.any_free_sorted(&indices));
+
+    assert!(ranges.all_free_sorted(&[1, 5, 12, 25]));
+    assert!(!ranges.any_free_sorted(&[0, 11, 26]));
+}
+
+#[test]
+fn sorted_queries_with_no_indices() {
+    let ranges = sample();
+    assert_eq!(ranges.are_free_sorted(&[]), Vec::<bool>::new());
+    assert!(ranges.all_free_sorted(&[]));
+    assert!(!ranges.any_free_sorted(&[]));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "indices must be sorted")]
+fn sorted_queries_reject_unsorted_input() {
+    sample().are_free_sorted(&[11, 2]);
+}
+
+#[test]
+fn window_report_slices_ranges_at_both_edges() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    ranges.set_range_free(r(12, 13));
+    ranges.set_range_free(r(20, 25));
+
+    let report = ranges.window_report(r(5, 21));
+    assert_eq!((report.free, report.used, report.fragments), (10, 7, 3));
+    assert_eq!(report.largest_free.map(span), Some((5, 10)));
+
+    let report = ranges.window_report(r(14, 19));
+    assert_eq!((report.free, report.used, report.fragments), (0, 6, 0));
+    assert!(report.largest_free.is_none());
+}
+
+#[test]
+fn window_reports_match_single_reports() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    ranges.set_range_free(r(12, 13));
+    ranges.set_range_free(r(20, 25));
+
+    let sorted = [r(0, 3), r(5, 12), r(13, 22), r(24, MAX)];
+    let unsorted = [r(5, 12), r(0, 3), r(2, 21)];
+    for windows in &[&sorted[..], &unsorted[..]] {
+        let single: Vec<_> = windows.iter().map(|&w| ranges.window_report(w)).collect();
+        assert_eq!(ranges.window_reports(windows), single);
+    }
+}
+
+#[test]
+fn window_report_over_the_full_domain() {
+    let all = FreeRanges::with_all_free();
+    let report = all.window_report(r(0, MAX));
+    assert_eq!((report.free, report.used, report.fragments), (MAX, 0, 1));
+    let report = all.window_report(r(1, MAX));
+    assert_eq!((report.free, report.used), (MAX, 0));
+
+    let report = FreeRanges::new().window_report(r(0, MAX));
+    assert_eq!((report.free, report.used, report.fragments), (0, MAX, 0));
+}
+
+#[test]
+fn window_report_of_an_empty_window() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    let report = ranges.window_report(r(5, 4));
+    assert_eq!((report.free, report.used, report.fragments), (0, 0, 0));
+    assert!(report.largest_free.is_none());
+
+    let windows = [r(0, 2), r(5, 4), r(6, 8)];
+    let single: Vec<_> = windows.iter().map(|&w| ranges.window_report(w)).collect();
+    assert_eq!(ranges.window_reports(&windows), single);
+}
+
+fn spaced() -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    ranges.set_range_free(r(12, 13));
+    ranges.set_range_free(r(20, 25));
+    ranges
+}
+
+#[test]
+fn neighbors_of_an_index_between_ranges() {
+    let (before, after) = spaced().neighbors(11);
+    assert_eq!(before.map(span), Some((1, 10)));
+    assert_eq!(after.map(span), Some((12, 13)));
+
+    let (before, after) = spaced().neighbors(30);
+    assert_eq!(before.map(span), Some((20, 25)));
+    assert_eq!(after.map(span), None);
+}
+
+#[test]
+fn neighbors_of_an_index_at_the_edge_of_a_range() {
+    let (before, after) = spaced().neighbors(12);
+    assert_eq!(before.map(span), Some((1, 10)));
+    assert_eq!(after.map(span), Some((20, 25)));
+
+    let (before, after) = spaced().neighbors(25);
+    assert_eq!(before.map(span), Some((12, 13)));
+    assert_eq!(after.map(span), None);
+}
+
+#[test]
+fn neighbors_skip_the_range_containing_the_index() {
+    let (before, after) = spaced().neighbors(5);
+    assert_eq!(before.map(span), None);
+    assert_eq!(after.map(span), Some((12, 13)));
+
+    let (before, after) = FreeRanges::with_all_free().neighbors(MAX);
+    assert_eq!((before, after), (None, None));
+}
+
+#[test]
+fn largest_aligned_block_can_differ_from_the_longest_range() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 12));
+    ranges.set_range_free(r(16, 25));
+    ranges.set_range_free(r(32, 41));
+
+    assert_eq!(ranges.largest_aligned_block(1).map(span), Some((1, 12)));
+    assert_eq!(ranges.largest_aligned_block(0).map(span), Some((1, 12)));
+    assert_eq!(ranges.largest_aligned_block(3).map(span), Some((3, 12)));
+    // 16..=25 and 32..=41 tie, the lower one wins
+    assert_eq!(ranges.largest_aligned_block(8).map(span), Some((16, 25)));
+    assert_eq!(ranges.largest_aligned_block(16).map(span), Some((16, 25)));
+    assert_eq!(ranges.largest_aligned_block(32).map(span), Some((32, 41)));
+    assert_eq!(ranges.largest_aligned_block(64), None);
+}
+
+#[test]
+fn largest_aligned_block_at_the_top_of_the_domain() {
+    let ranges = FreeRanges::with_initial_range(r(MAX - 2, MAX));
+    assert_eq!(ranges.largest_aligned_block(4), None);
+    assert_eq!(
+        ranges.largest_aligned_block(2).map(span),
+        Some((MAX - 1, MAX))
+    );
+    assert_eq!(
+        ranges.largest_aligned_block(MAX).map(span),
+        Some((MAX, MAX))
+    );
+}
+
+#[test]
+fn free_ranges_between_includes_ranges_touching_the_ends() {
+    let ranges = spaced();
+    let between = |start, end| spans(ranges.free_ranges_between(start, end));
+    assert_eq!(between(10, 20), vec![(1, 10), (12, 13), (20, 25)]);
+    assert_eq!(between(13, 13), vec![(12, 13)]);
+    assert_eq!(between(5, 5), vec![(1, 10)]);
+    assert_eq!(between(14, 19), vec![]);
+    assert_eq!(between(11, 11), vec![]);
+    assert_eq!(between(0, MAX).len(), 3);
+}
+
+#[test]
+fn longest_used_run_of_one_range_in_a_bounded_domain() {
+    let ranges = FreeRanges::with_initial_range(r(40, 59));
+    assert_eq!(ranges.longest_used_run(), None);
+    assert_eq!(
+        ranges.longest_used_run_within(r(0, 109)).map(span),
+        Some((60, 109))
+    );
+    assert_eq!(
+        ranges.longest_used_run_within(r(0, 79)).map(span),
+        Some((0, 39))
+    );
+    // Ties go to the lowest address
+    assert_eq!(
+        ranges.longest_used_run_within(r(20, 79)).map(span),
+        Some((20, 39))
+    );
+    assert_eq!(ranges.longest_used_run_within(r(45, 55)), None);
+}
+
+#[test]
+fn longest_used_run_between_back_to_back_ranges() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 4));
+    ranges.set_range_free(r(6, 9));
+    ranges.set_range_free(r(11, 14));
+    assert_eq!(ranges.longest_used_run().map(span), Some((5, 5)));
+    assert_eq!(
+        ranges.longest_used_run_within(r(0, 14)).map(span),
+        Some((5, 5))
+    );
+    assert_eq!(
+        ranges.longest_used_run_within(r(0, 16)).map(span),
+        Some((15, 16))
+    );
+}
+
+#[test]
+fn longest_used_run_of_empty_and_full_sets() {
+    let empty = FreeRanges::new();
+    assert_eq!(empty.longest_used_run(), None);
+    assert_eq!(
+        empty.longest_used_run_within(r(0, MAX)).map(span),
+        Some((0, MAX))
+    );
+
+    let all = FreeRanges::with_all_free();
+    assert_eq!(all.longest_used_run_within(r(0, MAX)), None);
+
+    let top = FreeRanges::with_initial_range(r(MAX, MAX));
+    assert_eq!(
+        top.longest_used_run_within(r(0, MAX)).map(span),
+        Some((0, MAX - 1))
+    );
+}
+
+#[test]
+fn trailing_free_len_clips_at_the_domain_end() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 9));
+    ranges.set_range_free(r(20, 29));
+    // Inside a free range which extends past the end
+    assert_eq!(ranges.trailing_free_len(25), 6);
+    assert_eq!(ranges.trailing_free_len(29), 10);
+    assert_eq!(ranges.trailing_free_len(9), 10);
+    assert_eq!(ranges.trailing_free_len(0), 1);
+    assert_eq!(ranges.trailing_free_len(30), 0);
+    assert_eq!(ranges.trailing_free_len(MAX), 0);
+    assert_eq!(ranges.leading_free_len(), 10);
+
+    let mut gap = FreeRanges::with_initial_range(r(1, 9));
+    assert_eq!(gap.leading_free_len(), 0);
+    gap.set_free(0);
+    assert_eq!(gap.leading_free_len(), 10);
+}
+
+#[test]
+fn free_lens_of_the_whole_domain() {
+    let all = FreeRanges::with_all_free();
+    // usize::MAX + 1 indices saturate
+    assert_eq!(all.trailing_free_len(MAX), MAX);
+    assert_eq!(all.leading_free_len(), MAX);
+    assert_eq!(all.trailing_free_len(MAX - 1), MAX);
+    assert_eq!(all.trailing_free_len(0), 1);
+
+    let top = FreeRanges::with_initial_range(r(MAX - 1, MAX));
+    assert_eq!(top.trailing_free_len(MAX), 2);
+    assert_eq!(FreeRanges::new().leading_free_len(), 0);
+    assert_eq!(FreeRanges::new().trailing_free_len(MAX), 0);
+}
+
+#[test]
+fn nth_range_indexes_in_address_order() {
+    let ranges = sample();
+    assert_eq!(
+        ranges.nth_range(0).map(span),
+        ranges.first_range().map(span)
+    );
+    assert_eq!(ranges.nth_range(1).map(span), Some((10, 12)));
+    assert_eq!(ranges.nth_range(2).map(span), Some((20, 30)));
+    assert_eq!(ranges.nth_range(3), None);
+    assert_eq!(FreeRanges::new().nth_range(0), None);
+}
+
+#[test]
+fn as_inner_supports_custom_range_queries() {
+    let ranges = sample();
+    let from_12: Vec<_> = ranges.as_inner().range(r(12, 12)..).map(span).collect();
+    assert_eq!(from_12, vec![(10, 12), (20, 30)]);
+    let before_10: Vec<_> = ranges.as_inner().range(..r(10, 10)).map(span).collect();
+    assert_eq!(before_10, vec![(0, 3)]);
+}
+
+#[test]
+fn is_single_range_for_zero_one_and_many_ranges() {
+    assert_eq!(FreeRanges::new().is_single_range(), None);
+    assert_eq!(
+        FreeRanges::with_initial_range(r(3, 9))
+            .is_single_range()
+            .map(span),
+        Some((3, 9))
+    );
+    assert_eq!(
+        FreeRanges::with_all_free().is_single_range().map(span),
+        Some((0, MAX))
+    );
+    assert_eq!(sample().is_single_range(), None);
+}
+
+fn adjacent(ranges: &FreeRanges, min: usize, max: usize) -> (Option<Span>, Option<Span>) {
+    let (below, above) = ranges.adjacent_free(r(min, max));
+    (below.map(span), above.map(span))
+}
+
+#[test]
+fn adjacent_free_finds_the_touching_ranges() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 4));
+    ranges.set_range_free(r(10, 14));
+    ranges.set_range_free(r(20, MAX));
+    assert_eq!(adjacent(&ranges, 5, 9), (Some((0, 4)), Some((10, 14))));
+    assert_eq!(adjacent(&ranges, 15, 18), (Some((10, 14)), None));
+    assert_eq!(adjacent(&ranges, 16, 19), (None, Some((20, MAX))));
+    // Overlapping ranges aren't adjacent
+    assert_eq!(adjacent(&ranges, 12, 13), (None, None));
+}
+
+#[test]
+fn adjacent_free_at_the_ends_of_the_domain() {
+    let low = FreeRanges::with_initial_range(r(5, 9));
+    assert_eq!(adjacent(&low, 0, 4), (None, Some((5, 9))));
+    assert_eq!(adjacent(&low, 0, 3), (None, None));
+
+    let high = FreeRanges::with_initial_range(r(0, 9));
+    assert_eq!(adjacent(&high, 10, MAX), (Some((0, 9)), None));
+    assert_eq!(adjacent(&FreeRanges::with_all_free(), 0, MAX), (None, None));
+}
+
+#[test]
+fn first_alignment_of_a_few_first_indices() {
+    let first_at = |index| FreeRanges::with_initial_range(r(index, index + 8)).first_alignment();
+    assert_eq!(FreeRanges::new().first_alignment(), None);
+    assert_eq!(first_at(0), Some(1 << (usize::BITS - 1)));
+    assert_eq!(first_at(1), Some(1));
+    assert_eq!(first_at(12), Some(4));
+    assert_eq!(first_at(4096), Some(4096));
+    assert_eq!(
+        FreeRanges::with_initial_range(r(MAX, MAX)).first_alignment(),
+        Some(1)
+    );
+}
+
+#[test]
+fn which_free_and_are_free_match_is_free() {
+    let mut ranges = sample();
+    ranges.set_free(40);
+    ranges.set_range_free(r(50, MAX));
+    let mut rng = XorShift::new(0x5eed_f00d);
+    for _ in 0..500 {
+        let indices: Vec<usize> = (0..rng.below(20)).map(|_| rng.below(60)).collect();
+        let expected: Vec<bool> = indices.iter().map(|&i| ranges.is_free(i)).collect();
+        assert_eq!(ranges.which_free(&indices), expected);
+        assert_eq!(ranges.are_free(&indices), expected.iter().all(|&free| free));
+    }
+
+    assert!(ranges.are_free(&[MAX, 3, 40, 3]));
+    assert_eq!(
+        ranges.which_free(&[MAX, 4, 40, 4]),
+        vec![true, false, true, false]
+    );
+    assert!(ranges.are_free(&[]));
+}
+
+fn free_spans(spans: &[(usize, usize)]) -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for &(min, max) in spans {
+        ranges.set_range_free(r(min, max));
+    }
+    ranges
+}
+
+#[test]
+fn gap_count_at_each_end_combination() {
+    assert_eq!(FreeRanges::new().gap_count(10), 1);
+    // Free or used at 0 and at the bound
+    assert_eq!(free_spans(&[(0, 3), (6, 10)]).gap_count(10), 1);
+    assert_eq!(free_spans(&[(0, 3), (6, 8)]).gap_count(10), 2);
+    assert_eq!(free_spans(&[(2, 3), (6, 10)]).gap_count(10), 2);
+    assert_eq!(free_spans(&[(2, 3), (6, 8)]).gap_count(10), 3);
+    assert_eq!(free_spans(&[(0, 20)]).gap_count(10), 0);
+    assert_eq!(free_spans(&[(20, 30)]).gap_count(10), 1);
+}
+
+#[test]
+fn gap_count_over_the_full_domain() {
+    assert_eq!(free_spans(&[(5, MAX)]).gap_count(MAX), 1);
+    assert_eq!(free_spans(&[(0, 5)]).gap_count(MAX), 1);
+    assert_eq!(FreeRanges::with_all_free().gap_count(MAX), 0);
+
+    let mut split = free_spans(&[(0, 10)]);
+    split.add_barrier(4);
+    assert_eq!(split.gap_count(10), 0);
+}
+
+#[test]
+fn free_ranges_with_gap_after_reaches_the_bound() {
+    let ranges = free_spans(&[(0, 2), (5, 6), (10, 10), (40, 50)]);
+    let with_gaps = |bound| -> Vec<(Span, usize)> {
+        ranges
+            .free_ranges_with_gap_after(bound)
+            .map(|(range, gap)| (span(range), gap))
+            .collect()
+    };
+    assert_eq!(
+        with_gaps(20),
+        vec![((0, 2), 2), ((5, 6), 3), ((10, 10), 10)]
+    );
+    assert_eq!(with_gaps(45).last(), Some(&((40, 50), 0)));
+    assert_eq!(with_gaps(60).last(), Some(&((40, 50), 10)));
+    assert_eq!(with_gaps(0), vec![((0, 2), 0)]);
+
+    let mut split = free_spans(&[(0, 9)]);
+    split.add_barrier(4);
+    let gaps: Vec<_> = split
+        .free_ranges_with_gap_after(9)
+        .map(|(_, gap)| gap)
+        .collect();
+    assert_eq!(gaps, vec![0, 0]);
+}
+
+#[test]
+fn jaccard_of_identical_disjoint_and_overlapping_sets() {
+    assert_eq!(FreeRanges::new().jaccard(&FreeRanges::new()), 1.0);
+    let ranges = free_spans(&[(0, 9), (20, 29)]);
+    assert_eq!(ranges.jaccard(&ranges.clone()), 1.0);
+    assert_eq!(ranges.jaccard(&free_spans(&[(10, 19)])), 0.0);
+    assert_eq!(ranges.jaccard(&FreeRanges::new()), 0.0);
+
+    let half = free_spans(&[(0, 9)]).jaccard(&free_spans(&[(5, 14)]));
+    assert_eq!(half, 5.0 / 15.0);
+    let split = free_spans(&[(0, 3), (6, 9)]).jaccard(&free_spans(&[(2, 7)]));
+    assert_eq!(split, 4.0 / 10.0);
+}
+
+#[test]
+fn jaccard_over_the_full_domain() {
+    let all = FreeRanges::with_all_free();
+    assert_eq!(all.jaccard(&all.clone()), 1.0);
+    let upper = free_spans(&[(usize::MAX / 2 + 1, usize::MAX)]);
+    assert_eq!(all.jaccard(&upper), 0.5);
+    assert_eq!(upper.jaccard(&all), 0.5);
+}
+
+#[test]
+fn small_gaps_skips_the_large_ones() {
+    // Gaps of 1 (3), 4 (6...9), 2 (20...21) and 11 (30...40)
+    let ranges = free_spans(&[(0, 2), (4, 5), (10, 19), (22, 29)]);
+    assert_eq!(spans(ranges.small_gaps(40, 2)), vec![(3, 3), (20, 21)]);
+    assert_eq!(
+        spans(ranges.small_gaps(40, 4)),
+        vec![(3, 3), (6, 9), (20, 21)]
+    );
+    assert_eq!(spans(ranges.small_gaps(40, 0)), vec![]);
+    assert_eq!(
+        spans(ranges.small_gaps(40, 11)),
+        vec![(3, 3), (6, 9), (20, 21), (30, 40)]
+    );
+    // The bound cuts the last gap short
+    assert_eq!(
+        spans(ranges.small_gaps(31, 2)),
+        vec![(3, 3), (20, 21), (30, 31)]
+    );
+}
+
+#[test]
+fn small_gaps_over_the_full_domain() {
+    let max = usize::MAX;
+    // An empty set is one gap of usize::MAX + 1 indices, too long for any
+    // max_gap
+    assert_eq!(spans(FreeRanges::new().small_gaps(max, max)), vec![]);
+    assert_eq!(
+        spans(FreeRanges::with_all_free().small_gaps(max, 5)),
+        vec![]
+    );
+    let first = free_spans(&[(0, 0)]);
+    assert_eq!(spans(first.small_gaps(max, max)), vec![(1, max)]);
+    assert_eq!(spans(first.small_gaps(max, max - 1)), vec![]);
+    let ends = free_spans(&[(1, max - 1)]);
+    assert_eq!(spans(ends.small_gaps(max, 1)), vec![(0, 0), (max, max)]);
+}
+
+#[test]
+fn density_profile_of_whole_partial_and_empty_buckets() {
+    // Buckets of 10: 100...109, 110...119, 120...129, 130...139
+    let domain = r(100, 139);
+    let filling = free_spans(&[(110, 119)]);
+    assert_eq!(filling.density_profile(domain, 4), vec![0.0, 1.0, 0.0, 0.0]);
+    let straddling = free_spans(&[(105, 134)]);
+    assert_eq!(
+        straddling.density_profile(domain, 4),
+        vec![0.5, 1.0, 1.0, 0.5]
+    );
+    let outside = free_spans(&[(0, 101), (138, 200)]);
+    assert_eq!(outside.density_profile(domain, 4), vec![0.2, 0.0, 0.0, 0.2]);
+    assert_eq!(FreeRanges::new().density_profile(domain, 4), vec![0.0; 4]);
+}
+
+#[test]
+fn density_profile_bucket_sizes() {
+    let domain = r(100, 139);
+    // 40 indices in 3 buckets are 13, 13 and 14 wide
+    let tail = free_spans(&[(126, 139)]);
+    assert_eq!(tail.density_profile(domain, 3), vec![0.0, 0.0, 1.0]);
+    let last = free_spans(&[(126, 126)]);
+    assert_eq!(last.density_profile(domain, 3), vec![0.0, 0.0, 1.0 / 14.0]);
+
+    // More buckets than indices gives one per index
+    assert_eq!(tail.density_profile(r(138, 139), 5), vec![1.0, 1.0]);
+    assert_eq!(tail.density_profile(domain, 0), Vec::<f32>::new());
+    let (min, max) = (5, 4);
+    assert_eq!(tail.density_profile(r(min, max), 3), Vec::<f32>::new());
+
+    let all = r(0, usize::MAX);
+    assert_eq!(
+        FreeRanges::with_all_free().density_profile(all, 3),
+        vec![1.0; 3]
+    );
+    assert_eq!(FreeRanges::new().density_profile(all, 2), vec![0.0; 2]);
+    let upper = free_spans(&[(usize::MAX / 2 + 1, usize::MAX)]);
+    assert_eq!(upper.density_profile(all, 4), vec![0.0, 0.0, 1.0, 1.0]);
+}
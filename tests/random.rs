@@ -0,0 +1,180 @@
+#![cfg(feature = "rand")]
+
+#[macro_use]
+extern crate free_ranges;
+extern crate rand;
+
+mod common;
+
+use common::{r, span, spans, Span};
+use free_ranges::{FreeRanges, Range};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Allocates blocks of 3 at random until none fit, checking each one
+fn drain_randomly(seed: u64) -> Vec<Span> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 99));
+    ranges.set_range_free(r(200, 205));
+
+    let mut blocks = Vec::new();
+    while let Some(block) = ranges.allocate_random(3, &mut rng) {
+        assert_eq!(block.max - block.min, 2);
+        assert!((block.min..=block.max).all(|i| !ranges.is_free(i)));
+        assert!(block.max <= 99 || block.min >= 200 && block.max <= 205);
+        blocks.push(span(block));
+    }
+    blocks
+}
+
+#[test]
+fn allocate_random_is_deterministic_for_a_seed() {
+    let blocks = drain_randomly(7);
+    assert_eq!(blocks, drain_randomly(7));
+    assert!(blocks.len() > 10);
+}
+
+#[test]
+fn allocate_random_handles_the_full_domain() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut ranges = FreeRanges::with_all_free();
+    assert!(ranges.allocate_random(usize::MAX, &mut rng).is_some());
+    assert_eq!(ranges.range_count(), 1);
+    assert!(ranges.allocate_random(0, &mut rng).is_none());
+}
+
+fn used_fraction(ranges: &FreeRanges, len: usize) -> f64 {
+    let free: usize = ranges.free_ranges().map(|r| r.max - r.min + 1).sum();
+    (len - free) as f64 / len as f64
+}
+
+#[test]
+fn fill_random_hits_the_requested_fraction() {
+    let mut rng = StdRng::seed_from_u64(9);
+    for &(fraction, mean) in &[(0.3, 8), (0.7, 50), (0.5, 1), (0.1, 200)] {
+        let ranges = FreeRanges::fill_random(99_999, fraction, mean, &mut rng);
+        let got = used_fraction(&ranges, 100_000);
+        assert!(
+            (got - fraction).abs() < 0.05,
+            "{} instead of {}",
+            got,
+            fraction
+        );
+        assert!(ranges.last().unwrap() <= 99_999);
+    }
+}
+
+#[test]
+fn fill_random_run_lengths_follow_the_mean() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let short = FreeRanges::fill_random(99_999, 0.5, 4, &mut rng);
+    let long = FreeRanges::fill_random(99_999, 0.5, 400, &mut rng);
+    // Longer runs mean fewer, larger free ranges for the same fraction
+    assert!(short.range_count() > 10 * long.range_count());
+}
+
+#[test]
+fn fill_random_at_the_extremes() {
+    let mut rng = StdRng::seed_from_u64(1);
+    assert_eq!(
+        FreeRanges::fill_random(10, 1.0, 3, &mut rng).range_count(),
+        0
+    );
+    let none_used = FreeRanges::fill_random(10, 0.0, 3, &mut rng);
+    assert_eq!(spans(&none_used), vec![(0, 10)]);
+}
+
+#[test]
+fn allocate_random_skips_excluded_ranges() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.exclude(r(0, 89));
+    for _ in 0..10 {
+        let index = ranges.allocate_random(1, &mut rng).unwrap().min;
+        assert!(index >= 90, "{} is excluded", index);
+    }
+    assert_eq!(ranges.allocate_random(1, &mut rng), None);
+    assert_eq!(spans(&ranges), vec![(0, 89)]);
+}
+
+const TRIALS: usize = 100_000;
+
+/// How often each start index is chosen when `allocate` is run on a fresh
+/// copy of `base` `TRIALS` times
+fn placement_counts<F>(base: &FreeRanges, mut allocate: F) -> [usize; 20]
+where
+    F: FnMut(&mut FreeRanges) -> Option<Range>,
+{
+    let mut counts = [0; 20];
+    for _ in 0..TRIALS {
+        let mut ranges = base.clone();
+        counts[allocate(&mut ranges).unwrap().min] += 1;
+    }
+    counts
+}
+
+#[test]
+fn allocate_randomized_is_uniform_over_placements() {
+    let mut rng = StdRng::seed_from_u64(7);
+    // Blocks of 2 fit at 0 and at 10..=18: ten placements, so a
+    // uniform choice over ranges would pick 0 half the time
+    let base = free_ranges![0..=1, 10..=19];
+    let counts = placement_counts(&base, |ranges| {
+        let block = ranges.allocate_randomized(2, &mut rng)?;
+        assert_eq!(block.max - block.min, 1);
+        Some(block)
+    });
+    for (start, &count) in counts.iter().enumerate() {
+        if start == 0 || (10..=18).contains(&start) {
+            assert!((9_000..11_000).contains(&count), "{} at {}", count, start);
+        } else {
+            assert_eq!(count, 0, "{} is not a placement", start);
+        }
+    }
+}
+
+#[test]
+fn allocate_randomized_aligned_is_uniform_over_placements() {
+    let mut rng = StdRng::seed_from_u64(8);
+    let base = free_ranges![0..=1, 10..=19];
+    let counts = placement_counts(&base, |ranges| {
+        ranges.allocate_randomized_aligned(2, 4, &mut rng)
+    });
+    for (start, &count) in counts.iter().enumerate() {
+        if [0, 12, 16].contains(&start) {
+            assert!((31_000..35_700).contains(&count), "{} at {}", count, start);
+        } else {
+            assert_eq!(count, 0, "{} is not a placement", start);
+        }
+    }
+}
+
+#[test]
+fn allocate_randomized_without_a_placement() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut ranges = free_ranges![0..=1, 10..=19];
+    assert_eq!(ranges.allocate_randomized(11, &mut rng), None);
+    assert_eq!(ranges.allocate_randomized(0, &mut rng), None);
+    assert_eq!(ranges.allocate_randomized_aligned(4, 32, &mut rng), None);
+    assert_eq!(spans(&ranges), vec![(0, 1), (10, 19)]);
+}
+
+#[test]
+fn allocate_randomized_over_the_full_domain() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut ranges = FreeRanges::with_all_free();
+    assert!(ranges.allocate_randomized(usize::MAX, &mut rng).is_some());
+    assert_eq!(ranges.range_count(), 1);
+
+    let mut ranges = FreeRanges::with_all_free();
+    let half = 1 << (usize::BITS - 1);
+    let block = ranges
+        .allocate_randomized_aligned(1, half, &mut rng)
+        .unwrap();
+    assert!(
+        block.min == 0 || block.min == half,
+        "{} is unaligned",
+        block.min
+    );
+}
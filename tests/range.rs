@@ -0,0 +1,217 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+
+use common::{r, span, Span};
+use free_ranges::{ParseRangeError, Range, RangeRelation};
+
+const MAX: usize = usize::MAX;
+
+fn sides(split: (Option<Range>, Option<Range>)) -> (Option<Span>, Option<Span>) {
+    (split.0.map(span), split.1.map(span))
+}
+
+#[test]
+fn split_at_min_and_max() {
+    assert_eq!(r(5, 10).split_at(5).map(sides), Some((None, Some((6, 10)))));
+    assert_eq!(r(5, 10).split_at(10).map(sides), Some((Some((5, 9)), None)));
+    assert_eq!(
+        r(5, 10).split_at(7).map(sides),
+        Some((Some((5, 6)), Some((8, 10))))
+    );
+}
+
+#[test]
+fn split_at_domain_edges() {
+    assert_eq!(r(0, 0).split_at(0).map(sides), Some((None, None)));
+    assert_eq!(r(0, 5).split_at(0).map(sides), Some((None, Some((1, 5)))));
+    assert_eq!(r(MAX, MAX).split_at(MAX).map(sides), Some((None, None)));
+    assert_eq!(
+        r(0, MAX).split_at(MAX).map(sides),
+        Some((Some((0, MAX - 1)), None))
+    );
+}
+
+#[test]
+fn split_at_outside_the_range() {
+    assert!(r(5, 10).split_at(4).is_none());
+    assert!(r(5, 10).split_at(11).is_none());
+    assert!(r(5, 10).split_at(0).is_none());
+    assert!(r(5, 10).split_at(MAX).is_none());
+}
+
+#[test]
+fn split_at_many_skips_empty_pieces_and_outside_cuts() {
+    let pieces = r(0, 10).split_at_many(&[0, 3, 3, 4, 10, 20]);
+    assert_eq!(common::spans(pieces), vec![(1, 2), (5, 9)]);
+    assert_eq!(common::spans(r(0, 10).split_at_many(&[])), vec![(0, 10)]);
+    assert_eq!(
+        common::spans(r(0, MAX).split_at_many(&[MAX])),
+        vec![(0, MAX - 1)]
+    );
+}
+
+#[test]
+fn checked_push_and_pop_at_the_domain_edges() {
+    assert_eq!(r(0, 5).checked_push_front().map(span), None);
+    assert_eq!(r(1, 5).checked_push_front().map(span), Some((0, 5)));
+    assert_eq!(r(0, MAX).checked_push_back().map(span), None);
+    assert_eq!(r(0, MAX - 1).checked_push_back().map(span), Some((0, MAX)));
+    assert_eq!(r(MAX, MAX).checked_pop_front().map(span), None);
+    assert_eq!(
+        r(MAX - 1, MAX).checked_pop_front().map(span),
+        Some((MAX, MAX))
+    );
+    assert_eq!(r(0, 0).checked_pop_back().map(span), None);
+    assert_eq!(r(0, 1).checked_pop_back().map(span), Some((0, 0)));
+}
+
+#[test]
+fn saturating_grow_clamps_at_the_domain_edges() {
+    assert_eq!(span(r(1, MAX - 1).saturating_grow(5)), (0, MAX));
+    assert_eq!(span(r(10, 20).saturating_grow(5)), (5, 25));
+    assert_eq!(span(r(0, MAX).saturating_grow(MAX)), (0, MAX));
+}
+
+#[test]
+fn saturating_shrink_reports_an_empty_result() {
+    assert_eq!(r(3, 5).saturating_shrink(1).map(span), Some((4, 4)));
+    assert_eq!(r(3, 5).saturating_shrink(2).map(span), None);
+    assert_eq!(
+        r(0, MAX).saturating_shrink(MAX / 2).map(span),
+        Some((MAX / 2, MAX - MAX / 2))
+    );
+    assert_eq!(r(0, MAX).saturating_shrink(MAX).map(span), None);
+}
+
+#[test]
+fn from_len_rejects_empty_and_overflowing_lengths() {
+    assert_eq!(Range::from_len(5, 0).map(span), None);
+    assert_eq!(Range::from_len(5, 1).map(span), Some((5, 5)));
+    assert_eq!(Range::from_len(5, 10).map(span), Some((5, 14)));
+    assert_eq!(Range::from_len(0, MAX).map(span), Some((0, MAX - 1)));
+    assert_eq!(Range::from_len(1, MAX).map(span), Some((1, MAX)));
+    assert_eq!(Range::from_len(2, MAX).map(span), None);
+    assert_eq!(Range::from_len(MAX, 1).map(span), Some((MAX, MAX)));
+    assert_eq!(Range::from_len(MAX, 2).map(span), None);
+}
+
+/// The relation seen from the other range
+fn inverse(relation: RangeRelation) -> RangeRelation {
+    match relation {
+        RangeRelation::Before => RangeRelation::After,
+        RangeRelation::Meets => RangeRelation::MetBy,
+        RangeRelation::OverlapsStart => RangeRelation::OverlapsEnd,
+        RangeRelation::ContainedBy => RangeRelation::Contains,
+        RangeRelation::Equal => RangeRelation::Equal,
+        RangeRelation::Contains => RangeRelation::ContainedBy,
+        RangeRelation::OverlapsEnd => RangeRelation::OverlapsStart,
+        RangeRelation::MetBy => RangeRelation::Meets,
+        RangeRelation::After => RangeRelation::Before,
+    }
+}
+
+fn assert_relation(a: Range, b: Range, expected: RangeRelation) {
+    assert_eq!(a.relation(b), expected, "{:?} to {:?}", a, b);
+    assert_eq!(b.relation(a), inverse(expected), "{:?} to {:?}", b, a);
+}
+
+#[test]
+fn relation_covers_every_case() {
+    let other = r(10, 20);
+    assert_relation(r(0, 8), other, RangeRelation::Before);
+    assert_relation(r(0, 9), other, RangeRelation::Meets);
+    assert_relation(r(0, 10), other, RangeRelation::OverlapsStart);
+    assert_relation(r(10, 19), other, RangeRelation::ContainedBy);
+    assert_relation(r(11, 20), other, RangeRelation::ContainedBy);
+    assert_relation(r(12, 18), other, RangeRelation::ContainedBy);
+    assert_relation(r(10, 20), other, RangeRelation::Equal);
+    assert_relation(r(9, 20), other, RangeRelation::Contains);
+    assert_relation(r(10, 21), other, RangeRelation::Contains);
+    assert_relation(r(20, 25), other, RangeRelation::OverlapsEnd);
+    assert_relation(r(21, 25), other, RangeRelation::MetBy);
+    assert_relation(r(22, 25), other, RangeRelation::After);
+}
+
+#[test]
+fn relation_between_singletons() {
+    assert_relation(r(5, 5), r(5, 5), RangeRelation::Equal);
+    assert_relation(r(5, 5), r(6, 6), RangeRelation::Meets);
+    assert_relation(r(5, 5), r(7, 7), RangeRelation::Before);
+}
+
+#[test]
+fn relation_at_the_domain_edges() {
+    assert_relation(r(MAX, MAX), r(0, MAX - 1), RangeRelation::MetBy);
+    assert_relation(r(0, MAX), r(MAX, MAX), RangeRelation::Contains);
+    assert_relation(r(0, MAX), r(0, 0), RangeRelation::Contains);
+    assert_relation(r(0, 0), r(MAX, MAX), RangeRelation::Before);
+    assert_relation(r(0, MAX), r(0, MAX), RangeRelation::Equal);
+    assert_relation(r(MAX - 1, MAX), r(MAX, MAX), RangeRelation::Contains);
+}
+
+fn parse(s: &str) -> Result<Span, ParseRangeError> {
+    s.parse::<Range>().map(span)
+}
+
+#[test]
+fn parse_accepts_each_form() {
+    assert_eq!(parse("5"), Ok((5, 5)));
+    assert_eq!(parse(" 0..=9 "), Ok((0, 9)));
+    assert_eq!(parse("0-9"), Ok((0, 9)));
+    assert_eq!(parse("18446744073709551615"), Ok((usize::MAX, usize::MAX)));
+}
+
+#[test]
+fn parse_rejects_inverted_and_malformed_ranges() {
+    assert_eq!(
+        parse("9..=0"),
+        Err(ParseRangeError::Inverted { min: 9, max: 0 })
+    );
+    for s in &["0..9", "", "+5", "1-2-3", "0 - 9", "18446744073709551616"] {
+        assert_eq!(parse(s), Err(ParseRangeError::Malformed), "{:?}", s);
+    }
+}
+
+fn from_bounds(start: Bound<usize>, end: Bound<usize>) -> Option<Span> {
+    Range::from_bounds((start, end)).map(span)
+}
+
+#[test]
+fn from_bounds_at_the_low_edge() {
+    assert_eq!(from_bounds(Included(0), Included(0)), Some((0, 0)));
+    assert_eq!(from_bounds(Included(0), Excluded(0)), None);
+    assert_eq!(from_bounds(Included(0), Unbounded), Some((0, MAX)));
+    assert_eq!(from_bounds(Excluded(0), Included(0)), None);
+    assert_eq!(from_bounds(Excluded(0), Excluded(0)), None);
+    assert_eq!(from_bounds(Excluded(0), Unbounded), Some((1, MAX)));
+    assert_eq!(from_bounds(Unbounded, Included(0)), Some((0, 0)));
+    assert_eq!(from_bounds(Unbounded, Excluded(0)), None);
+    assert_eq!(from_bounds(Unbounded, Unbounded), Some((0, MAX)));
+}
+
+#[test]
+fn from_bounds_at_the_high_edge() {
+    assert_eq!(from_bounds(Included(MAX), Included(MAX)), Some((MAX, MAX)));
+    assert_eq!(from_bounds(Included(MAX), Excluded(MAX)), None);
+    assert_eq!(from_bounds(Included(MAX), Unbounded), Some((MAX, MAX)));
+    assert_eq!(from_bounds(Excluded(MAX), Included(MAX)), None);
+    assert_eq!(from_bounds(Excluded(MAX), Excluded(MAX)), None);
+    assert_eq!(from_bounds(Excluded(MAX), Unbounded), None);
+    assert_eq!(from_bounds(Unbounded, Included(MAX)), Some((0, MAX)));
+    assert_eq!(from_bounds(Unbounded, Excluded(MAX)), Some((0, MAX - 1)));
+    assert_eq!(from_bounds(Unbounded, Unbounded), Some((0, MAX)));
+}
+
+#[test]
+fn from_bounds_of_std_ranges() {
+    assert_eq!(Range::from_bounds(3..7).map(span), Some((3, 6)));
+    assert_eq!(Range::from_bounds(3..=7).map(span), Some((3, 7)));
+    assert_eq!(Range::from_bounds(..7).map(span), Some((0, 6)));
+    assert_eq!(Range::from_bounds(3..).map(span), Some((3, MAX)));
+    assert_eq!(Range::from_bounds(5..5).map(span), None);
+    assert_eq!(from_bounds(Included(5), Excluded(3)), None);
+    assert_eq!(from_bounds(Excluded(3), Included(4)), Some((4, 4)));
+}
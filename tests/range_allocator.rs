@@ -0,0 +1,109 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, spans};
+use free_ranges::{
+    BoundedFreeRanges, CowFreeRanges, FreeRanges, HoldingFreeRanges, IndexedFreeRanges,
+    LazyFreeRanges, RangeAllocator, TrackedFreeRanges,
+};
+
+fn ten_free() -> FreeRanges {
+    FreeRanges::with_initial_range(r(0, 9))
+}
+
+/// Runs the same calls against any allocator which starts with 0...9 free
+fn exercise<A: RangeAllocator + ?Sized>(pool: &mut A) {
+    assert_eq!(pool.first(), Some(0));
+    assert_eq!(pool.set_first_used(), Some(0));
+    assert_eq!(pool.set_first_used(), Some(1));
+    assert!(pool.set_used(5));
+    assert!(!pool.set_used(5));
+    assert!(!pool.is_free(5));
+    assert!(pool.set_free(5));
+    assert!(!pool.set_free(5));
+    assert!(pool.is_free(5));
+    assert!(pool.set_range_free(r(0, 1)));
+    assert!(!pool.set_range_free(r(0, 1)));
+    assert_eq!(spans(pool.free_ranges()), vec![(0, 9)]);
+
+    assert!(pool.set_used(3));
+    assert!(pool.set_used(7));
+    assert_eq!(spans(pool.free_ranges()), vec![(0, 2), (4, 6), (8, 9)]);
+    assert!(pool.set_free(3));
+    assert_eq!(spans(pool.free_ranges()), vec![(0, 6), (8, 9)]);
+    assert_eq!(pool.first(), Some(0));
+}
+
+#[test]
+fn every_implementation_behaves_the_same() {
+    exercise(&mut ten_free());
+    exercise(&mut BoundedFreeRanges::<9>::with_all_free());
+    exercise(&mut CowFreeRanges::new(ten_free()));
+    exercise(&mut HoldingFreeRanges::new(ten_free()));
+    exercise(&mut IndexedFreeRanges::new(ten_free()));
+    exercise(&mut LazyFreeRanges::new(ten_free(), 16));
+    exercise(&mut LazyFreeRanges::new(ten_free(), 1));
+    exercise(&mut TrackedFreeRanges::new(ten_free()));
+}
+
+#[test]
+fn every_implementation_works_as_a_trait_object() {
+    let mut pools: Vec<Box<dyn RangeAllocator>> = vec![
+        Box::new(ten_free()),
+        Box::new(BoundedFreeRanges::<9>::with_all_free()),
+        Box::new(CowFreeRanges::new(ten_free())),
+        Box::new(HoldingFreeRanges::new(ten_free())),
+        Box::new(IndexedFreeRanges::new(ten_free())),
+        Box::new(LazyFreeRanges::new(ten_free(), 16)),
+        Box::new(TrackedFreeRanges::new(ten_free())),
+    ];
+    for pool in &mut pools {
+        exercise(&mut **pool);
+    }
+}
+
+#[test]
+fn wrappers_keep_their_own_rules_behind_the_trait() {
+    // Writes to a shared copy-on-write set go to a private copy
+    let mut cow = CowFreeRanges::new(ten_free());
+    let snapshot = cow.snapshot();
+    RangeAllocator::set_used(&mut cow, 4);
+    assert!(snapshot.is_free(4));
+    assert!(!RangeAllocator::is_free(&cow, 4));
+
+    // Bounded sets refuse indices past their bound
+    let mut bounded = BoundedFreeRanges::<9>::new();
+    assert!(!RangeAllocator::set_free(&mut bounded, 10));
+    assert!(!RangeAllocator::set_range_free(&mut bounded, r(5, 10)));
+    assert_eq!(RangeAllocator::first(&bounded), None);
+
+    // Held indices stay used
+    let mut holding = HoldingFreeRanges::new(ten_free());
+    holding.hold(r(0, 2), 100).unwrap();
+    assert!(!RangeAllocator::set_free(&mut holding, 1));
+    assert!(!RangeAllocator::set_range_free(&mut holding, r(0, 2)));
+
+    // Buffered frees are merged into the iterator, but never across a
+    // barrier
+    let mut split = ten_free();
+    split.set_range_used(r(3, 6));
+    split.add_barrier(4);
+    let mut lazy = LazyFreeRanges::new(split, 16);
+    for i in 3..=6 {
+        assert!(RangeAllocator::set_free(&mut lazy, i));
+    }
+    assert_eq!(lazy.pending_len(), 4);
+    assert_eq!(
+        spans(RangeAllocator::free_ranges(&lazy)),
+        vec![(0, 4), (5, 9)]
+    );
+    assert_eq!(spans(lazy.ranges()), vec![(0, 4), (5, 9)]);
+
+    // Indices used through the trait are outstanding
+    let mut tracked = TrackedFreeRanges::new(ten_free());
+    RangeAllocator::set_used(&mut tracked, 2);
+    assert_eq!(tracked.outstanding_total(), 1);
+    RangeAllocator::set_free(&mut tracked, 2);
+    tracked.assert_no_outstanding();
+}
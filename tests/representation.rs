@@ -0,0 +1,222 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use common::{r, span, spans, XorShift};
+use free_ranges::FreeRanges;
+
+/// Counts every allocation made on this thread, so a test can check that
+/// a single-range set never builds a tree
+struct CountingGlobal;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingGlobal {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingGlobal = CountingGlobal;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn behaviour_is_unchanged_across_the_promotion_boundary() {
+    let mut ranges = FreeRanges::new();
+    assert_eq!(ranges.range_count(), 0);
+    assert!(ranges.as_inner().is_empty());
+
+    assert!(ranges.set_range_free(r(10, 19)));
+    assert_eq!(spans(&ranges), vec![(10, 19)]);
+    assert_eq!(spans(ranges.as_inner()), vec![(10, 19)]);
+    assert!(ranges.is_free(10) && !ranges.is_free(20));
+
+    // A second range moves the ranges into the tree
+    assert!(ranges.set_range_free(r(30, 39)));
+    assert_eq!(spans(&ranges), vec![(10, 19), (30, 39)]);
+    assert_eq!(spans(ranges.free_ranges_after(20)), vec![(30, 39)]);
+
+    // Filling the gap merges them back into one
+    assert!(ranges.set_range_free(r(20, 29)));
+    assert_eq!(spans(&ranges), vec![(10, 39)]);
+    assert_eq!(spans(ranges.free_ranges_before(15)), vec![(10, 39)]);
+
+    assert!(ranges.set_used(15));
+    assert_eq!(spans(&ranges), vec![(10, 14), (16, 39)]);
+    assert_eq!(ranges.allocate(5).map(span), Some((10, 14)));
+    assert_eq!(spans(&ranges), vec![(16, 39)]);
+    assert_eq!(ranges.allocate(24).map(span), Some((16, 39)));
+    assert_eq!(ranges.range_count(), 0);
+    assert!(ranges.free_ranges().next().is_none());
+    assert_eq!(ranges.allocate(1), None);
+}
+
+#[test]
+fn borrowed_trees_follow_later_changes() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 9));
+    assert_eq!(spans(ranges.free_ranges()), vec![(0, 9)]);
+
+    ranges.set_used(0);
+    assert_eq!(spans(ranges.free_ranges()), vec![(1, 9)]);
+    assert_eq!(spans(ranges.as_inner()), vec![(1, 9)]);
+
+    let copy = ranges.clone();
+    ranges.set_used(1);
+    assert_eq!(spans(copy.free_ranges()), vec![(1, 9)]);
+    assert_eq!(spans(ranges.free_ranges()), vec![(2, 9)]);
+}
+
+#[test]
+fn debug_output_matches_the_tree() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 9));
+    let single = format!("{:?}", ranges);
+    assert!(single.contains(&format!("free_list: {:?}", ranges.as_inner())));
+
+    ranges.set_range_free(r(20, 29));
+    let double = format!("{:?}", ranges);
+    assert!(double.contains(&format!("free_list: {:?}", ranges.as_inner())));
+}
+
+#[test]
+fn owned_iteration_and_conversions_of_a_single_range() {
+    let ranges = FreeRanges::with_initial_range(r(5, 8));
+    let iter = ranges.clone().into_iter();
+    assert_eq!(iter.len(), 1);
+    assert_eq!(spans(iter), vec![(5, 8)]);
+
+    let set = ranges.clone().into_inner();
+    assert_eq!(spans(&set), vec![(5, 8)]);
+    let rebuilt = FreeRanges::from_inner(set).unwrap();
+    assert_eq!(spans(&rebuilt), vec![(5, 8)]);
+    assert_eq!(
+        spans(FreeRanges::from_inner(Default::default()).unwrap()),
+        vec![]
+    );
+}
+
+#[test]
+fn split_and_join_across_the_boundary() {
+    let mut lower = FreeRanges::with_initial_range(r(0, 99));
+    let higher = lower.split_off(50);
+    assert_eq!(spans(&lower), vec![(0, 49)]);
+    assert_eq!(spans(&higher), vec![(50, 99)]);
+    lower.join(higher);
+    assert_eq!(spans(&lower), vec![(0, 99)]);
+
+    let mut lower = FreeRanges::with_initial_range(r(0, 9));
+    lower.set_range_free(r(20, 29));
+    let higher = lower.split_off(15);
+    assert_eq!(
+        (spans(&lower), spans(&higher)),
+        (vec![(0, 9)], vec![(20, 29)])
+    );
+    lower.join(higher);
+    assert_eq!(spans(&lower), vec![(0, 9), (20, 29)]);
+}
+
+/// Runs a long pseudo-random sequence of operations over a small domain,
+/// where the set keeps moving between zero, one and many ranges, and
+/// checks every step against a plain bitmap
+#[test]
+fn random_operations_match_a_bitmap() {
+    const SIZE: usize = 48;
+    let mut rng = XorShift::new(0x2545_f491);
+    let mut next = |bound| rng.below(bound);
+
+    let mut ranges = FreeRanges::new();
+    let mut model = [false; SIZE];
+    for _ in 0..20_000 {
+        let a = next(SIZE);
+        let b = (a + next(6)).min(SIZE - 1);
+        match next(5) {
+            0 => {
+                ranges.set_range_free(r(a, b));
+                model[a..=b].iter_mut().for_each(|free| *free = true);
+            }
+            1 => {
+                ranges.set_range_used(r(a, b));
+                model[a..=b].iter_mut().for_each(|free| *free = false);
+            }
+            2 => {
+                assert_eq!(ranges.set_free(a), !model[a]);
+                model[a] = true;
+            }
+            3 => {
+                assert_eq!(ranges.set_used(a), model[a]);
+                model[a] = false;
+            }
+            _ => {
+                let size = b - a + 1;
+                let expected =
+                    (0..=SIZE - size).find(|&at| model[at..at + size].iter().all(|&f| f));
+                let allocated = ranges.allocate(size);
+                assert_eq!(allocated.map(|range| range.min), expected);
+                if let Some(at) = expected {
+                    model[at..at + size]
+                        .iter_mut()
+                        .for_each(|free| *free = false);
+                }
+            }
+        }
+
+        let mut expected = Vec::new();
+        for (index, &free) in model.iter().enumerate() {
+            match expected.last_mut() {
+                Some(&mut (_, ref mut max)) if free && *max + 1 == index => *max = index,
+                _ if free => expected.push((index, index)),
+                _ => {}
+            }
+        }
+        assert_eq!(spans(&ranges), expected);
+        assert_eq!(ranges.range_count(), expected.len());
+    }
+}
+
+#[test]
+fn a_single_range_never_allocates() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    let before = allocations();
+    for _ in 0..100 {
+        let (block, _) = ranges.allocate_from(0, 10).unwrap();
+        assert_eq!(ranges.count_ranges_in(r(0, 99)), 1);
+        assert_eq!(ranges.window_report(r(0, 99)).free, 90);
+        assert_eq!(ranges.gap_count(99), 1);
+        ranges.set_range_free_strict(block).unwrap();
+    }
+    assert_eq!(allocations(), before, "allocated a tree");
+}
+
+#[test]
+fn removing_ranges_keeps_the_tree_until_shrunk() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.set_used(50);
+    // Going back and forth between one and two ranges reuses the tree
+    let before = allocations();
+    for _ in 0..100 {
+        ranges.set_free(50);
+        ranges.set_used(50);
+    }
+    assert!(allocations() - before < 10, "rebuilt the tree");
+
+    ranges.set_free(50);
+    ranges.shrink_to_fit();
+    assert_eq!(spans(&ranges), vec![(0, 99)]);
+    let before = allocations();
+    ranges.allocate_from(0, 10).unwrap();
+    assert_eq!(allocations(), before);
+    assert_eq!(spans(&ranges), vec![(10, 99)]);
+}
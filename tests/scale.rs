@@ -0,0 +1,114 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, XorShift};
+use free_ranges::{FreeRanges, Rounding};
+
+const MAX: usize = usize::MAX;
+
+fn free_spans(list: &[(usize, usize)]) -> FreeRanges {
+    let mut ranges = FreeRanges::new();
+    for &(min, max) in list {
+        ranges.set_range_free(r(min, max));
+    }
+    ranges
+}
+
+#[test]
+fn scale_up_multiplies_every_range() {
+    let ranges = free_spans(&[(1, 2), (5, 5)]);
+    assert_eq!(spans(ranges.scale_up(8).unwrap()), vec![(8, 23), (40, 47)]);
+    assert_eq!(spans(ranges.scale_up(1).unwrap()), vec![(1, 2), (5, 5)]);
+    let down = ranges.scale_up(8).unwrap().scale_down(8, Rounding::Inward);
+    assert_eq!(spans(down), spans(&ranges));
+}
+
+#[test]
+fn scale_up_reports_the_range_which_overflows() {
+    let all = FreeRanges::with_all_free();
+    assert_eq!(spans(all.scale_up(1).unwrap()), vec![(0, MAX)]);
+    assert_eq!(all.scale_up(2).err().map(|e| span(e.range)), Some((0, MAX)));
+
+    let ranges = free_spans(&[(0, 3), (MAX / 4, MAX / 4 + 1)]);
+    assert_eq!(
+        ranges.scale_up(4).err().map(|e| span(e.range)),
+        Some((MAX / 4, MAX / 4 + 1))
+    );
+}
+
+#[test]
+fn scale_down_of_unaligned_ranges() {
+    let ranges = free_spans(&[(5, 13)]);
+    assert_eq!(spans(ranges.scale_down(4, Rounding::Inward)), vec![(2, 2)]);
+    assert_eq!(spans(ranges.scale_down(4, Rounding::Outward)), vec![(1, 3)]);
+
+    // Covers no coarse unit entirely, but touches two
+    let ranges = free_spans(&[(5, 10)]);
+    assert_eq!(spans(ranges.scale_down(4, Rounding::Inward)), vec![]);
+    assert_eq!(spans(ranges.scale_down(4, Rounding::Outward)), vec![(1, 2)]);
+
+    let aligned = free_spans(&[(4, 11)]);
+    assert_eq!(spans(aligned.scale_down(4, Rounding::Inward)), vec![(1, 2)]);
+    assert_eq!(
+        spans(aligned.scale_down(4, Rounding::Outward)),
+        vec![(1, 2)]
+    );
+
+    // Partially covered units next to each other merge outward
+    let ranges = free_spans(&[(1, 2), (6, 6)]);
+    assert_eq!(spans(ranges.scale_down(4, Rounding::Outward)), vec![(0, 1)]);
+    assert_eq!(spans(ranges.scale_down(4, Rounding::Inward)), vec![]);
+}
+
+#[test]
+fn scale_down_over_the_full_domain() {
+    let all = FreeRanges::with_all_free();
+    for &rounding in &[Rounding::Inward, Rounding::Outward] {
+        assert_eq!(spans(all.scale_down(1, rounding)), vec![(0, MAX)]);
+        assert_eq!(spans(all.scale_down(4, rounding)), vec![(0, MAX / 4)]);
+    }
+    // Coarse unit 1 only holds usize::MAX itself
+    assert_eq!(spans(all.scale_down(MAX, Rounding::Inward)), vec![(0, 0)]);
+    assert_eq!(spans(all.scale_down(MAX, Rounding::Outward)), vec![(0, 1)]);
+
+    // usize::MAX is a multiple of 3, so the last coarse unit only holds it
+    let top = free_spans(&[(MAX - 9, MAX)]);
+    assert_eq!(
+        spans(top.scale_down(1, Rounding::Inward)),
+        vec![(MAX - 9, MAX)]
+    );
+    assert_eq!(
+        spans(top.scale_down(1, Rounding::Outward)),
+        vec![(MAX - 9, MAX)]
+    );
+    assert_eq!(
+        spans(top.scale_down(3, Rounding::Inward)),
+        vec![(MAX / 3 - 3, MAX / 3 - 1)]
+    );
+    assert_eq!(
+        spans(top.scale_down(3, Rounding::Outward)),
+        vec![(MAX / 3 - 3, MAX / 3)]
+    );
+}
+
+#[test]
+fn scale_down_matches_a_unit_by_unit_check() {
+    let mut rng = XorShift::new(0x5ca1e);
+    for _ in 0..200 {
+        let mut ranges = FreeRanges::new();
+        for _ in 0..6 {
+            let min = rng.below(200);
+            ranges.set_range_free(r(min, min + rng.below(15)));
+        }
+        let factor = rng.below(7) + 1;
+        let inward = ranges.scale_down(factor, Rounding::Inward);
+        let outward = ranges.scale_down(factor, Rounding::Outward);
+        for unit in 0..240 / factor {
+            let fine = r(unit * factor, (unit + 1) * factor - 1);
+            assert_eq!(inward.is_free(unit), ranges.is_range_free(fine));
+            let any = (fine.min..=fine.max).any(|i| ranges.is_free(i));
+            assert_eq!(outward.is_free(unit), any);
+        }
+    }
+}
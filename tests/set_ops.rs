@@ -0,0 +1,99 @@
+#[macro_use]
+extern crate free_ranges;
+
+mod common;
+
+use common::{spans, XorShift};
+use free_ranges::FreeRanges;
+
+const MAX: usize = usize::MAX;
+
+#[test]
+fn symmetric_difference_of_disjoint_inputs_is_their_union() {
+    let a = free_ranges![0..=4, 20..=29];
+    let b = free_ranges![5..=9, 40..=MAX];
+    let both = a.symmetric_difference(&b);
+    assert_eq!(spans(&both), vec![(0, 9), (20, 29), (40, MAX)]);
+    assert_eq!(spans(&both), spans(a.union(&b)));
+
+    let all = FreeRanges::with_all_free();
+    assert_eq!(
+        spans(all.symmetric_difference(&FreeRanges::new())),
+        vec![(0, MAX)]
+    );
+}
+
+#[test]
+fn symmetric_difference_of_identical_inputs_is_empty() {
+    let a = free_ranges![0..=4, 20..=29];
+    assert_eq!(spans(a.symmetric_difference(&a)), vec![]);
+    let all = FreeRanges::with_all_free();
+    assert_eq!(spans(all.symmetric_difference(&all)), vec![]);
+}
+
+#[test]
+fn symmetric_difference_of_overlapping_inputs() {
+    let a = free_ranges![0..=4, 20..=29];
+    let c = free_ranges![3..=22, 25..=26];
+    let differing = a.symmetric_difference(&c);
+    assert_eq!(spans(&differing), vec![(0, 2), (5, 19), (23, 24), (27, 29)]);
+    assert_eq!(
+        spans(&differing),
+        spans(a.union(&c).difference(&a.intersection(&c)))
+    );
+    assert!(differing.is_canonical());
+}
+
+#[test]
+fn symmetric_difference_keeps_barriers() {
+    let mut a = free_ranges![0..=9];
+    a.add_barrier(5);
+    assert_eq!(
+        spans(a.symmetric_difference(&free_ranges![5..=5])),
+        vec![(0, 4), (6, 9)]
+    );
+}
+
+#[test]
+fn symmetric_difference_matches_union_minus_intersection() {
+    const DOMAIN: usize = 64;
+    let mut rng = XorShift::new(197);
+    for _ in 0..200 {
+        let mut a = FreeRanges::new();
+        let mut b = FreeRanges::new();
+        for _ in 0..8 {
+            a.set_free(rng.below(DOMAIN));
+            b.set_free(rng.below(DOMAIN));
+        }
+        let differing = a.symmetric_difference(&b);
+        for i in 0..DOMAIN {
+            assert_eq!(differing.is_free(i), a.is_free(i) != b.is_free(i));
+        }
+        assert_eq!(
+            spans(&differing),
+            spans(a.union(&b).difference(&a.intersection(&b)))
+        );
+        assert!(differing.is_canonical());
+    }
+}
+
+#[test]
+fn operators_match_the_named_methods() {
+    let a = free_ranges![0..=4, 20..=29];
+    let b = free_ranges![3..=22, 25..=26];
+    assert_eq!(spans(&a | &b), spans(a.union(&b)));
+    assert_eq!(spans(&a & &b), spans(a.intersection(&b)));
+    assert_eq!(spans(&a - &b), spans(a.difference(&b)));
+    assert_eq!(spans(&a ^ &b), spans(a.symmetric_difference(&b)));
+    assert_eq!(spans(&b - &a), spans(b.difference(&a)));
+}
+
+#[test]
+fn operators_take_owned_and_borrowed_operands() {
+    let a = free_ranges![0..=4, 20..=29];
+    let b = free_ranges![3..=22, 25..=26];
+    assert_eq!(spans(a.clone() | b.clone()), vec![(0, 29)]);
+    assert_eq!(spans(a.clone() - &b), vec![(0, 2), (23, 24), (27, 29)]);
+    assert_eq!(spans(&a & b.clone()), vec![(3, 4), (20, 22), (25, 26)]);
+    assert_eq!(spans(&a ^ b.clone()), spans(a.symmetric_difference(&b)));
+}
@@ -0,0 +1,114 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use common::{r, span};
+use free_ranges::ShardedFreeRanges;
+
+#[test]
+fn allocations_steal_from_later_shards() {
+    let sharded = ShardedFreeRanges::new(r(0, 9), 3);
+    let bounds: Vec<_> = sharded.shard_bounds().map(span).collect();
+    assert_eq!(bounds, vec![(0, 3), (4, 6), (7, 9)]);
+    assert_eq!(sharded.shard_of(5), Some(1));
+    assert_eq!(sharded.shard_of(10), None);
+
+    assert_eq!(sharded.allocate(1, 3).map(span), Some((4, 6)));
+    assert_eq!(sharded.allocate(1, 3).map(span), Some((7, 9)));
+    assert_eq!(sharded.allocate(1, 3).map(span), Some((0, 2)));
+    // A block never spans two shards
+    assert_eq!(sharded.allocate(1, 2), None);
+    assert_eq!(sharded.shard_free(), vec![1, 0, 0]);
+
+    sharded.set_range_free(r(2, 8));
+    assert_eq!(sharded.total_free(), 7);
+    assert!(!sharded.set_free(5));
+    assert!(sharded.set_free(9));
+    assert!(!sharded.set_free(10));
+    assert_eq!(sharded.total_free(), 8);
+    assert_eq!(sharded.allocate(0, 7), None);
+}
+
+#[test]
+fn set_used_updates_the_owning_shard() {
+    let sharded = ShardedFreeRanges::new(r(0, 9), 2);
+    assert!(sharded.set_used(7));
+    assert!(!sharded.set_used(7));
+    assert!(!sharded.set_used(10));
+    assert!(!sharded.is_free(7));
+    assert_eq!(sharded.shard_free(), vec![5, 4]);
+    assert!(sharded.set_free(7));
+    assert_eq!(sharded.total_free(), 10);
+}
+
+#[test]
+fn shards_cover_the_full_domain() {
+    let sharded = ShardedFreeRanges::new(r(0, usize::MAX), 4);
+    assert_eq!(
+        sharded.shard_bounds().last().map(span),
+        Some((3 << 62, usize::MAX))
+    );
+    assert_eq!(sharded.total_free(), usize::MAX);
+    assert_eq!(sharded.shard_of(usize::MAX), Some(3));
+}
+
+#[test]
+fn concurrent_use_hands_out_unique_indices() {
+    const DOMAIN: usize = 10_000;
+    let sharded = Arc::new(ShardedFreeRanges::new(r(0, DOMAIN - 1), 8));
+    let handed_out = Arc::new(Mutex::new(HashSet::new()));
+
+    let threads: Vec<_> = (0..16)
+        .map(|thread| {
+            let sharded = sharded.clone();
+            let handed_out = handed_out.clone();
+            thread::spawn(move || {
+                let mut held = Vec::new();
+                for i in 0..2_000 {
+                    match i % 5 {
+                        // Free one back, releasing it from the set first so
+                        // another thread may be handed it straight away
+                        3 => {
+                            if let Some(index) = held.pop() {
+                                assert!(handed_out.lock().unwrap().remove(&index));
+                                assert!(sharded.set_free(index));
+                            }
+                        }
+                        // Claim a specific index, which may race another
+                        // thread's allocation of it
+                        4 => {
+                            let index = (thread * 7919 + i * 31) % DOMAIN;
+                            let mut handed_out = handed_out.lock().unwrap();
+                            if sharded.set_used(index) {
+                                assert!(handed_out.insert(index), "{} handed out twice", index);
+                                held.push(index);
+                            }
+                        }
+                        _ => {
+                            if let Some(index) = sharded.set_first_used(thread) {
+                                assert!(
+                                    handed_out.lock().unwrap().insert(index),
+                                    "{} handed out twice",
+                                    index
+                                );
+                                held.push(index);
+                            }
+                        }
+                    }
+                }
+                held.len()
+            })
+        })
+        .collect();
+
+    let held: usize = threads.into_iter().map(|t| t.join().unwrap()).sum();
+    assert_eq!(sharded.total_free() + held, DOMAIN);
+    assert_eq!(sharded.shard_free().iter().sum::<usize>(), DOMAIN - held);
+    let handed_out = handed_out.lock().unwrap();
+    assert_eq!(handed_out.len(), held);
+    assert!(handed_out.iter().all(|&index| !sharded.is_free(index)));
+}
@@ -0,0 +1,414 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::ops::RangeInclusive;
+
+use common::{r, span, spans, Span, XorShift};
+use free_ranges::{FreeRanges, SignedFreeRanges};
+
+fn free(ranges: &SignedFreeRanges) -> Vec<RangeInclusive<isize>> {
+    ranges.free_ranges().collect()
+}
+
+#[test]
+fn minus_one_and_zero_coalesce() {
+    let mut ranges = SignedFreeRanges::new();
+    assert!(ranges.set_free(-1));
+    assert!(ranges.set_free(0));
+    assert!(!ranges.set_free(0));
+    assert_eq!(free(&ranges), vec![-1..=0]);
+
+    ranges.set_range_free(-10..=-2);
+    ranges.set_range_free(1..=5);
+    assert_eq!(free(&ranges), vec![-10..=5]);
+    let (min, max) = (3, -3);
+    assert!(!ranges.set_range_free(min..=max));
+
+    assert!(ranges.set_used(0));
+    assert_eq!(free(&ranges), vec![-10..=-1, 1..=5]);
+    assert_eq!(ranges.first(), Some(-10));
+    assert_eq!(ranges.last(), Some(5));
+    assert_eq!(ranges.set_first_used(), Some(-10));
+    assert_eq!(ranges.allocate(3), Some(-9..=-7));
+    assert_eq!(ranges.allocate(0), None);
+}
+
+#[test]
+fn the_domain_extremes() {
+    let mut all = SignedFreeRanges::with_all_free();
+    assert_eq!(free(&all), vec![isize::MIN..=isize::MAX]);
+    assert_eq!(all.set_first_used(), Some(isize::MIN));
+    assert_eq!(all.set_last_used(), Some(isize::MAX));
+    assert!(all.set_free(isize::MIN));
+    assert_eq!(all.first(), Some(isize::MIN));
+    assert_eq!(all.range_count(), 1);
+
+    assert!(all.set_range_used(-5..=5));
+    assert!(!all.is_free(0) && all.is_free(-6) && all.is_free(6));
+    assert_eq!(free(&all), vec![isize::MIN..=-6, 6..=isize::MAX - 1]);
+
+    let low = SignedFreeRanges::with_initial_range(isize::MIN..=isize::MIN + 2);
+    assert_eq!(free(&low), vec![isize::MIN..=isize::MIN + 2]);
+    all.clear();
+    assert_eq!(all.range_count(), 0);
+}
+
+/// The shared tests work on fixtures of this many indices, given as
+/// offsets from 0
+const DOMAIN: usize = 128;
+
+/// The operations the shared tests use, with indices as fixture offsets.
+/// FreeRanges takes them as they are, and `Shifted` translates them to
+/// signed indices from an origin
+trait Pool {
+    fn is_free(&self, at: usize) -> bool;
+    fn set_free(&mut self, at: usize) -> bool;
+    fn set_used(&mut self, at: usize) -> bool;
+    fn set_range_free(&mut self, min: usize, max: usize) -> bool;
+    fn set_range_used(&mut self, min: usize, max: usize) -> bool;
+    fn first(&self) -> Option<usize>;
+    fn last(&self) -> Option<usize>;
+    fn set_first_used(&mut self) -> Option<usize>;
+    fn set_last_used(&mut self) -> Option<usize>;
+    fn allocate(&mut self, size: usize) -> Option<Span>;
+    fn range_count(&self) -> usize;
+    fn clear(&mut self);
+    fn spans(&self) -> Vec<Span>;
+}
+
+impl Pool for FreeRanges {
+    fn is_free(&self, at: usize) -> bool {
+        FreeRanges::is_free(self, at)
+    }
+
+    fn set_free(&mut self, at: usize) -> bool {
+        FreeRanges::set_free(self, at)
+    }
+
+    fn set_used(&mut self, at: usize) -> bool {
+        FreeRanges::set_used(self, at)
+    }
+
+    fn set_range_free(&mut self, min: usize, max: usize) -> bool {
+        FreeRanges::set_range_free(self, r(min, max))
+    }
+
+    fn set_range_used(&mut self, min: usize, max: usize) -> bool {
+        FreeRanges::set_range_used(self, r(min, max))
+    }
+
+    fn first(&self) -> Option<usize> {
+        FreeRanges::first(self)
+    }
+
+    fn last(&self) -> Option<usize> {
+        FreeRanges::last(self)
+    }
+
+    fn set_first_used(&mut self) -> Option<usize> {
+        FreeRanges::set_first_used(self)
+    }
+
+    fn set_last_used(&mut self) -> Option<usize> {
+        FreeRanges::set_last_used(self)
+    }
+
+    fn allocate(&mut self, size: usize) -> Option<Span> {
+        FreeRanges::allocate(self, size).map(span)
+    }
+
+    fn range_count(&self) -> usize {
+        FreeRanges::range_count(self)
+    }
+
+    fn clear(&mut self) {
+        FreeRanges::clear(self)
+    }
+
+    fn spans(&self) -> Vec<Span> {
+        spans(self)
+    }
+}
+
+/// A SignedFreeRanges with fixture offset 0 at `origin`
+struct Shifted {
+    ranges: SignedFreeRanges,
+    origin: isize,
+}
+
+impl Shifted {
+    fn new(origin: isize) -> Self {
+        Shifted {
+            ranges: SignedFreeRanges::new(),
+            origin,
+        }
+    }
+
+    fn index(&self, at: usize) -> isize {
+        self.origin
+            .checked_add_unsigned(at)
+            .expect("the fixture runs past isize::MAX")
+    }
+
+    fn offset(&self, index: isize) -> usize {
+        assert!(index >= self.origin, "{} is below the fixture", index);
+        index.abs_diff(self.origin)
+    }
+
+    fn range(&self, min: usize, max: usize) -> RangeInclusive<isize> {
+        self.index(min)..=self.index(max)
+    }
+}
+
+impl Pool for Shifted {
+    fn is_free(&self, at: usize) -> bool {
+        self.ranges.is_free(self.index(at))
+    }
+
+    fn set_free(&mut self, at: usize) -> bool {
+        let index = self.index(at);
+        self.ranges.set_free(index)
+    }
+
+    fn set_used(&mut self, at: usize) -> bool {
+        let index = self.index(at);
+        self.ranges.set_used(index)
+    }
+
+    fn set_range_free(&mut self, min: usize, max: usize) -> bool {
+        let range = self.range(min, max);
+        self.ranges.set_range_free(range)
+    }
+
+    fn set_range_used(&mut self, min: usize, max: usize) -> bool {
+        let range = self.range(min, max);
+        self.ranges.set_range_used(range)
+    }
+
+    fn first(&self) -> Option<usize> {
+        self.ranges.first().map(|index| self.offset(index))
+    }
+
+    fn last(&self) -> Option<usize> {
+        self.ranges.last().map(|index| self.offset(index))
+    }
+
+    fn set_first_used(&mut self) -> Option<usize> {
+        let index = self.ranges.set_first_used()?;
+        Some(self.offset(index))
+    }
+
+    fn set_last_used(&mut self) -> Option<usize> {
+        let index = self.ranges.set_last_used()?;
+        Some(self.offset(index))
+    }
+
+    fn allocate(&mut self, size: usize) -> Option<Span> {
+        let block = self.ranges.allocate(size)?;
+        Some((self.offset(*block.start()), self.offset(*block.end())))
+    }
+
+    fn range_count(&self) -> usize {
+        self.ranges.range_count()
+    }
+
+    fn clear(&mut self) {
+        self.ranges.clear()
+    }
+
+    fn spans(&self) -> Vec<Span> {
+        self.ranges
+            .free_ranges()
+            .map(|range| (self.offset(*range.start()), self.offset(*range.end())))
+            .collect()
+    }
+}
+
+/// Runs each shared test over FreeRanges and over SignedFreeRanges with the
+/// fixture straddling 0 (offsets 63 and 64 are -1 and 0), starting at
+/// isize::MIN, and ending at isize::MAX
+macro_rules! shared_tests {
+    ($($test:ident),* $(,)*) => {
+        mod unsigned {
+            use super::*;
+            $(
+                #[test]
+                fn $test() {
+                    super::$test(FreeRanges::new())
+                }
+            )*
+        }
+
+        mod across_zero {
+            use super::*;
+            $(
+                #[test]
+                fn $test() {
+                    super::$test(Shifted::new(-64))
+                }
+            )*
+        }
+
+        mod at_isize_min {
+            use super::*;
+            $(
+                #[test]
+                fn $test() {
+                    super::$test(Shifted::new(isize::MIN))
+                }
+            )*
+        }
+
+        mod at_isize_max {
+            use super::*;
+            $(
+                #[test]
+                fn $test() {
+                    super::$test(Shifted::new(isize::MAX - (DOMAIN as isize - 1)))
+                }
+            )*
+        }
+    };
+}
+
+shared_tests!(
+    set_used_splits_at_the_fixture_edges,
+    set_free_merges_at_the_fixture_edges,
+    neighbours_merge_in_the_middle,
+    set_last_used_empties_a_single_index,
+    allocate_takes_the_first_fit,
+    clear_uses_everything,
+    random_operations_match_a_bitmap,
+);
+
+fn set_used_splits_at_the_fixture_edges<P: Pool>(mut ranges: P) {
+    assert!(ranges.set_range_free(0, DOMAIN - 1));
+    assert_eq!(ranges.set_last_used(), Some(DOMAIN - 1));
+    assert_eq!(ranges.set_first_used(), Some(0));
+    assert!(ranges.set_used(5));
+    assert!(!ranges.set_used(5));
+    assert_eq!(ranges.spans(), vec![(1, 4), (6, DOMAIN - 2)]);
+}
+
+fn set_free_merges_at_the_fixture_edges<P: Pool>(mut ranges: P) {
+    let top = DOMAIN - 1;
+    ranges.set_free(top);
+    ranges.set_free(0);
+    ranges.set_free(top - 1);
+    ranges.set_free(1);
+    assert_eq!(ranges.spans(), vec![(0, 1), (top - 1, top)]);
+
+    ranges.set_range_free(10, 12);
+    ranges.set_range_free(20, 22);
+    ranges.set_range_free(2, 25);
+    assert_eq!(ranges.spans(), vec![(0, 25), (top - 1, top)]);
+
+    assert!(ranges.set_range_free(26, top - 2));
+    assert!(!ranges.set_range_free(0, top));
+    assert_eq!(ranges.spans(), vec![(0, top)]);
+}
+
+fn neighbours_merge_in_the_middle<P: Pool>(mut ranges: P) {
+    assert!(ranges.set_free(63));
+    assert!(ranges.set_free(64));
+    assert_eq!(ranges.spans(), vec![(63, 64)]);
+    ranges.set_range_free(60, 62);
+    ranges.set_range_free(65, 70);
+    assert_eq!(ranges.spans(), vec![(60, 70)]);
+
+    assert!(ranges.set_used(64));
+    assert_eq!(ranges.spans(), vec![(60, 63), (65, 70)]);
+    assert!(ranges.set_range_used(63, 65));
+    assert_eq!(ranges.spans(), vec![(60, 62), (66, 70)]);
+    assert!(!ranges.set_range_used(0, 10));
+    assert!(!ranges.is_free(63) && !ranges.is_free(64) && ranges.is_free(62));
+}
+
+fn set_last_used_empties_a_single_index<P: Pool>(mut ranges: P) {
+    for &at in &[0, 64, DOMAIN - 1] {
+        ranges.set_free(at);
+        assert_eq!(ranges.set_last_used(), Some(at));
+        assert_eq!(ranges.first(), None);
+        assert_eq!(ranges.last(), None);
+    }
+}
+
+fn allocate_takes_the_first_fit<P: Pool>(mut ranges: P) {
+    ranges.set_range_free(1, 3);
+    ranges.set_range_free(60, 79);
+    ranges.set_range_free(DOMAIN - 8, DOMAIN - 1);
+    assert_eq!(ranges.allocate(8), Some((60, 67)));
+    assert_eq!(ranges.allocate(4), Some((68, 71)));
+    assert_eq!(ranges.allocate(3), Some((1, 3)));
+    assert_eq!(ranges.allocate(0), None);
+    assert_eq!(ranges.allocate(9), None);
+    assert_eq!(ranges.allocate(8), Some((72, 79)));
+    assert_eq!(ranges.allocate(8), Some((DOMAIN - 8, DOMAIN - 1)));
+    assert_eq!(ranges.range_count(), 0);
+}
+
+fn clear_uses_everything<P: Pool>(mut ranges: P) {
+    ranges.set_range_free(0, 9);
+    ranges.set_range_free(60, 70);
+    assert_eq!(ranges.range_count(), 2);
+    ranges.clear();
+    assert_eq!(ranges.range_count(), 0);
+    assert_eq!(ranges.set_first_used(), None);
+    assert!(!ranges.is_free(64));
+}
+
+/// The same pseudo-random walk as the representation tests, checked
+/// against a bitmap of the fixture
+fn random_operations_match_a_bitmap<P: Pool>(mut ranges: P) {
+    let mut rng = XorShift::new(0x2545_f491);
+    let mut model = [false; DOMAIN];
+    for _ in 0..5_000 {
+        let a = rng.below(DOMAIN);
+        let b = (a + rng.below(6)).min(DOMAIN - 1);
+        match rng.below(5) {
+            0 => {
+                let changed = model[a..=b].iter().any(|&free| !free);
+                assert_eq!(ranges.set_range_free(a, b), changed);
+                model[a..=b].iter_mut().for_each(|free| *free = true);
+            }
+            1 => {
+                let changed = model[a..=b].iter().any(|&free| free);
+                assert_eq!(ranges.set_range_used(a, b), changed);
+                model[a..=b].iter_mut().for_each(|free| *free = false);
+            }
+            2 => {
+                assert_eq!(ranges.set_free(a), !model[a]);
+                model[a] = true;
+            }
+            3 => {
+                assert_eq!(ranges.set_used(a), model[a]);
+                model[a] = false;
+            }
+            _ => {
+                let size = b - a + 1;
+                let expected =
+                    (0..=DOMAIN - size).find(|&at| model[at..at + size].iter().all(|&f| f));
+                assert_eq!(ranges.allocate(size).map(|block| block.0), expected);
+                if let Some(at) = expected {
+                    model[at..at + size]
+                        .iter_mut()
+                        .for_each(|free| *free = false);
+                }
+            }
+        }
+
+        let mut expected: Vec<Span> = Vec::new();
+        for (at, &free) in model.iter().enumerate() {
+            match expected.last_mut() {
+                Some(&mut (_, ref mut max)) if free && *max + 1 == at => *max = at,
+                _ if free => expected.push((at, at)),
+                _ => {}
+            }
+        }
+        assert_eq!(ranges.spans(), expected);
+        assert_eq!(ranges.range_count(), expected.len());
+        assert_eq!(ranges.first(), expected.first().map(|&(min, _)| min));
+        assert_eq!(ranges.last(), expected.last().map(|&(_, max)| max));
+    }
+}
@@ -0,0 +1,84 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::spans;
+use free_ranges::SlotPool;
+
+fn contents<T: Copy>(pool: &SlotPool<T>) -> Vec<(usize, T)> {
+    pool.iter().map(|(i, &v)| (i, v)).collect()
+}
+
+#[test]
+fn insert_reuses_the_lowest_free_slot() {
+    let mut pool = SlotPool::new();
+    assert_eq!(
+        (pool.insert("a"), pool.insert("b"), pool.insert("c")),
+        (0, 1, 2)
+    );
+    assert_eq!(pool.capacity(), 4);
+
+    assert_eq!(pool.remove(1), Some("b"));
+    assert_eq!(pool.remove(1), None);
+    assert!(!pool.contains(1));
+    assert_eq!(pool.insert("d"), 1);
+    assert_eq!(contents(&pool), vec![(0, "a"), (1, "d"), (2, "c")]);
+
+    *pool.get_mut(2).unwrap() = "e";
+    assert_eq!(pool.get(2), Some(&"e"));
+    assert_eq!(pool.get(3), None);
+    assert_eq!(pool.get(100), None);
+    assert_eq!(pool.remove(100), None);
+}
+
+#[test]
+fn growth_keeps_the_free_slots_in_sync() {
+    let mut pool = SlotPool::new();
+    for i in 0..8 {
+        assert_eq!(pool.insert(i), i);
+    }
+    assert_eq!((pool.len(), pool.capacity()), (8, 8));
+    assert_eq!(pool.free_slots().range_count(), 0);
+
+    assert_eq!(pool.insert(8), 8);
+    assert_eq!(pool.capacity(), 16);
+    assert_eq!(spans(pool.free_slots()), vec![(9, 15)]);
+
+    pool.remove(3);
+    pool.remove(5);
+    assert_eq!(spans(pool.free_slots()), vec![(3, 3), (5, 5), (9, 15)]);
+    assert_eq!(pool.insert(30), 3);
+    assert_eq!(pool.len(), 8);
+}
+
+#[test]
+fn shrink_to_fit_drops_trailing_free_slots() {
+    let mut pool = SlotPool::new();
+    for i in 0..9 {
+        pool.insert(i);
+    }
+    for i in 3..9 {
+        pool.remove(i);
+    }
+    pool.remove(1);
+    pool.shrink_to_fit();
+    assert_eq!(pool.capacity(), 3);
+    assert_eq!(spans(pool.free_slots()), vec![(1, 1)]);
+    assert_eq!(contents(&pool), vec![(0, 0), (2, 2)]);
+
+    assert_eq!(pool.insert(10), 1);
+    assert_eq!(pool.insert(11), 3);
+    assert_eq!(pool.capacity(), 6);
+    assert_eq!(spans(pool.free_slots()), vec![(4, 5)]);
+}
+
+#[test]
+fn shrink_to_fit_an_empty_pool() {
+    let mut pool: SlotPool<u8> = SlotPool::with_capacity(2);
+    assert_eq!(pool.capacity(), 2);
+    pool.shrink_to_fit();
+    assert_eq!(pool.capacity(), 0);
+    assert!(pool.is_empty());
+    assert_eq!(pool.insert(1), 0);
+    assert_eq!(pool.len(), 1);
+}
@@ -0,0 +1,76 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use common::{r, span, spans};
+use free_ranges::{FreeRanges, InvariantViolation};
+
+#[test]
+fn split_off_then_join_reproduces_the_set() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(1, 10));
+    ranges.set_range_free(r(12, 13));
+    ranges.set_range_free(r(20, 25));
+    let original = spans(&ranges);
+
+    for &at in &[0, 1, 5, 10, 11, 12, 13, 20, 25, 26, 100] {
+        let mut lower = ranges.clone();
+        let higher = lower.split_off(at);
+        assert!(lower.free_ranges().all(|range| range.max < at));
+        assert!(higher.free_ranges().all(|range| range.min >= at));
+
+        lower.join(higher);
+        assert_eq!(spans(&lower), original, "split at {}", at);
+    }
+}
+
+#[test]
+fn split_off_then_join_the_whole_domain() {
+    let mut lower = FreeRanges::with_all_free();
+    let higher = lower.split_off(0);
+    assert_eq!(lower.range_count(), 0);
+    lower.join(higher);
+    assert_eq!(spans(&lower), vec![(0, usize::MAX)]);
+
+    let mut lower = FreeRanges::with_all_free();
+    let higher = lower.split_off(usize::MAX);
+    assert_eq!(spans(&lower), vec![(0, usize::MAX - 1)]);
+    assert_eq!(spans(&higher), vec![(usize::MAX, usize::MAX)]);
+    lower.join(higher);
+    assert_eq!(spans(&lower), vec![(0, usize::MAX)]);
+}
+
+#[test]
+fn into_inner_round_trips_through_from_inner() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 9));
+    ranges.set_range_free(r(20, 29));
+    assert_eq!(spans(ranges.as_inner()), vec![(0, 9), (20, 29)]);
+
+    let rebuilt = FreeRanges::from_inner(ranges.into_inner()).unwrap();
+    assert_eq!(spans(&rebuilt), vec![(0, 9), (20, 29)]);
+}
+
+#[test]
+fn from_inner_rejects_broken_sets() {
+    let mut set = BTreeSet::new();
+    set.insert(r(0, 9));
+    set.insert(r(10, 12));
+    match FreeRanges::from_inner(set.clone()) {
+        Err(InvariantViolation::Adjacent(a, b)) => {
+            assert_eq!((span(a), span(b)), ((0, 9), (10, 12)))
+        }
+        other => panic!("expected adjacent ranges, got {:?}", other),
+    }
+    // The unchecked version takes the set as it is
+    assert_eq!(FreeRanges::from_inner_unchecked(set).range_count(), 2);
+
+    let mut set = BTreeSet::new();
+    set.insert(r(5, 3));
+    match FreeRanges::from_inner(set) {
+        Err(InvariantViolation::EmptyRange(range)) => assert_eq!(span(range), (5, 3)),
+        other => panic!("expected an empty range, got {:?}", other),
+    }
+}
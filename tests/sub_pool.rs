@@ -0,0 +1,84 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, XorShift};
+use free_ranges::FreeRanges;
+
+fn total_free(ranges: &FreeRanges) -> usize {
+    ranges.free_ranges().map(|r| r.max - r.min + 1).sum()
+}
+
+#[test]
+fn nested_sub_pools_give_back_what_is_left() {
+    let mut root = FreeRanges::with_initial_range(r(0, 99));
+    let mut outer = root.carve(40).unwrap();
+    assert_eq!(span(outer.range()), (0, 39));
+    let mut inner = outer.carve(10).unwrap();
+    assert_eq!(span(inner.range()), (0, 9));
+    assert_eq!(
+        total_free(&root) + total_free(&outer) + total_free(&inner),
+        100
+    );
+
+    assert_eq!(inner.allocate(3).map(span), Some((0, 2)));
+
+    // Nothing of the block is used in another set, so it can't be the parent
+    let mut other = FreeRanges::with_initial_range(r(0, 99));
+    let inner = inner.close(&mut other).unwrap_err();
+    assert_eq!(spans(&other), vec![(0, 99)]);
+    inner.close(&mut outer).unwrap();
+    assert_eq!(spans(&*outer), vec![(3, 39)]);
+    assert_eq!(total_free(&root) + total_free(&outer) + 3, 100);
+
+    outer.set_range_free(r(0, 2));
+    outer.close(&mut root).unwrap();
+    assert_eq!(spans(&root), vec![(0, 99)]);
+    assert!(root.carve(0).is_none());
+    assert!(root.carve(101).is_none());
+}
+
+#[test]
+fn free_space_is_conserved_across_two_levels() {
+    let mut rng = XorShift::new(0x5eed_0185);
+    for _ in 0..50 {
+        let mut root = FreeRanges::with_initial_range(r(0, 999));
+        let mut outer = root.carve(rng.below(400) + 100).unwrap();
+        let mut inner = outer.carve(rng.below(50) + 10).unwrap();
+
+        let mut allocated = 0;
+        for _ in 0..20 {
+            let len = rng.below(5) + 1;
+            let pool = if rng.below(2) == 0 {
+                &mut *outer
+            } else {
+                &mut *inner
+            };
+            if pool.allocate(len).is_some() {
+                allocated += len;
+            }
+            let free = total_free(&root) + total_free(&outer) + total_free(&inner);
+            assert_eq!(free + allocated, 1_000);
+        }
+
+        inner.close(&mut outer).unwrap();
+        outer.close(&mut root).unwrap();
+        assert_eq!(total_free(&root) + allocated, 1_000);
+    }
+}
+
+#[test]
+fn closing_drops_indices_freed_outside_the_block() {
+    let mut root = FreeRanges::with_initial_range(r(0, 99));
+    assert_eq!(root.allocate(10).map(span), Some((0, 9)));
+    let mut pool = root.carve(10).unwrap();
+    assert_eq!(span(pool.range()), (10, 19));
+
+    pool.allocate(2);
+    pool.set_range_free(r(0, 14));
+    pool.set_free(60);
+    assert_eq!(spans(&*pool), vec![(0, 19), (60, 60)]);
+
+    pool.close(&mut root).unwrap();
+    assert_eq!(spans(&root), vec![(10, 99)]);
+}
@@ -0,0 +1,43 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans};
+use free_ranges::{FreeRanges, TaggedFreeRanges};
+
+#[test]
+fn tags_are_returned_on_free() {
+    let mut tagged = TaggedFreeRanges::new(FreeRanges::with_initial_range(r(0, 9)));
+    let a = tagged.alloc_tagged(4, "a").unwrap();
+    let b = tagged.alloc_tagged(4, "b").unwrap();
+    assert_eq!((span(a), span(b)), ((0, 3), (4, 7)));
+    assert_eq!(tagged.alloc_tagged(4, "c"), None);
+
+    assert_eq!(
+        tagged.get(4).map(|(range, &tag)| (span(range), tag)),
+        Some(((4, 7), "b"))
+    );
+    assert_eq!(tagged.get(5), None);
+
+    assert_eq!(tagged.free_tagged(0), Some("a"));
+    assert_eq!(tagged.free_tagged(0), None);
+    assert!(tagged.ranges().is_range_free(r(0, 3)));
+    let remaining: Vec<_> = tagged
+        .allocations()
+        .map(|(range, &tag)| (span(range), tag))
+        .collect();
+    assert_eq!(remaining, vec![((4, 7), "b")]);
+}
+
+#[test]
+fn freed_blocks_merge_back() {
+    let mut tagged = TaggedFreeRanges::new(FreeRanges::with_initial_range(r(0, 9)));
+    tagged.alloc_tagged(4, 1).unwrap();
+    tagged.alloc_tagged(4, 2).unwrap();
+    assert_eq!(tagged.alloc_tagged(6, 3), None);
+
+    assert_eq!(tagged.free_tagged(4), Some(2));
+    assert_eq!(tagged.free_tagged(0), Some(1));
+    assert_eq!(spans(tagged.ranges()), vec![(0, 9)]);
+    assert_eq!(tagged.alloc_tagged(10, 4).map(span), Some((0, 9)));
+}
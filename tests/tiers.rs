@@ -0,0 +1,115 @@
+extern crate free_ranges;
+
+mod common;
+
+use common::{r, span, spans, Span};
+use free_ranges::{FreeRanges, HistoryFreeRanges, Range};
+
+fn tiered(result: Option<(Range, Option<usize>)>) -> Option<(Span, Option<usize>)> {
+    result.map(|(range, tier)| (span(range), tier))
+}
+
+/// A set with one of each kind of allocation policy: a barrier, an
+/// excluded range and two preference tiers
+fn with_policy() -> FreeRanges {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.add_barrier(49);
+    ranges.exclude(r(0, 9));
+    ranges.set_preference_tiers(vec![r(50, 59), r(5, 19)]);
+    ranges
+}
+
+fn assert_policy(ranges: &FreeRanges) {
+    assert_eq!(ranges.barriers().collect::<Vec<_>>(), vec![49]);
+    assert_eq!(spans(ranges.excluded_ranges()), vec![(0, 9)]);
+    assert_eq!(spans(ranges.preference_tiers()), vec![(50, 59), (5, 19)]);
+}
+
+#[test]
+fn allocations_move_down_the_tiers_as_they_fill() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.set_preference_tiers(vec![r(50, 59), r(55, 69)]);
+    assert_eq!(tiered(ranges.allocate_tiered(4)), Some(((50, 53), Some(0))));
+    assert_eq!(tiered(ranges.allocate_tiered(4)), Some(((54, 57), Some(0))));
+    assert_eq!(tiered(ranges.allocate_tiered(4)), Some(((58, 61), Some(1))));
+    assert_eq!(ranges.set_first_used_tiered(), Some((62, Some(1))));
+    assert_eq!(tiered(ranges.allocate_tiered(7)), Some(((63, 69), Some(1))));
+    assert_eq!(tiered(ranges.allocate_tiered(1)), Some(((0, 0), None)));
+
+    // A free in a higher tier is used again before anything lower
+    ranges.set_range_free(r(50, 53));
+    assert_eq!(ranges.set_first_used_tiered(), Some((50, Some(0))));
+
+    ranges.set_preference_tiers(vec![r(90, 95)]);
+    assert_eq!(spans(ranges.preference_tiers()), vec![(90, 95)]);
+    assert_eq!(tiered(ranges.allocate_tiered(10)), Some(((1, 10), None)));
+    assert_eq!(tiered(ranges.allocate_tiered(6)), Some(((90, 95), Some(0))));
+    assert_eq!(tiered(ranges.allocate_tiered(100)), None);
+}
+
+#[test]
+fn tiers_skip_excluded_indices() {
+    let mut ranges = FreeRanges::with_initial_range(r(0, 99));
+    ranges.set_preference_tiers(vec![r(10, 19)]);
+    ranges.exclude(r(10, 14));
+    assert_eq!(tiered(ranges.allocate_tiered(5)), Some(((15, 19), Some(0))));
+    assert_eq!(tiered(ranges.allocate_tiered(5)), Some(((0, 4), None)));
+}
+
+#[test]
+fn clones_and_splits_keep_the_policy() {
+    let mut ranges = with_policy();
+    let mut copy = ranges.clone();
+    assert_policy(&copy);
+    assert_eq!(
+        tiered(copy.allocate_tiered(5)),
+        tiered(ranges.allocate_tiered(5))
+    );
+    assert_eq!(spans(&copy), spans(&ranges));
+
+    let higher = ranges.split_off(50);
+    assert_eq!(spans(higher.preference_tiers()), vec![(50, 59), (5, 19)]);
+    assert_eq!(higher.excluded_ranges().count(), 0);
+    assert_eq!(higher.barriers().count(), 0);
+}
+
+#[test]
+fn undo_leaves_the_policy_alone() {
+    let mut history = HistoryFreeRanges::new(with_policy());
+    history.enable_history(8);
+    // The excluded range is skipped and the block stops at the barrier
+    assert_eq!(history.allocate(40).map(span), Some((10, 49)));
+    history.set_range_free(r(40, 60));
+    assert_eq!(spans(history.ranges()), vec![(0, 9), (40, 49), (50, 99)]);
+
+    history.undo().unwrap();
+    history.undo().unwrap();
+    assert_policy(history.ranges());
+    assert_eq!(spans(history.ranges()), vec![(0, 49), (50, 99)]);
+    history.redo().unwrap();
+    assert_eq!(spans(history.ranges()), vec![(0, 9), (50, 99)]);
+    assert_policy(&history.into_inner());
+}
+
+#[test]
+fn set_operations_keep_the_policy_of_self() {
+    let ranges = with_policy();
+    let mut other = FreeRanges::with_initial_range(r(40, 149));
+    other.add_barrier(120);
+    other.exclude(r(100, 109));
+
+    let union = &ranges | &other;
+    assert_policy(&union);
+    assert_eq!(spans(&union), vec![(0, 49), (50, 149)]);
+    assert_policy(&(&ranges & &other));
+    assert_eq!(spans(&ranges & &other), vec![(40, 49), (50, 99)]);
+    assert_eq!(spans(&ranges - &other), vec![(0, 39)]);
+    assert_eq!(spans(&ranges ^ &other), vec![(0, 39), (100, 149)]);
+
+    // The other way around, only the barrier of `other` is kept
+    let union = &other | &ranges;
+    assert_eq!(union.barriers().collect::<Vec<_>>(), vec![120]);
+    assert_eq!(spans(union.excluded_ranges()), vec![(100, 109)]);
+    assert_eq!(union.preference_tiers().len(), 0);
+    assert_eq!(spans(&union), vec![(0, 120), (121, 149)]);
+}
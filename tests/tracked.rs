@@ -0,0 +1,125 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::panic;
+
+use common::r;
+#[cfg(feature = "tracked-ranges")]
+use common::spans;
+use free_ranges::{FreeRanges, TrackedFreeRanges};
+
+#[test]
+fn whole_frees_end_one_block_each() {
+    let mut tracked = TrackedFreeRanges::new(FreeRanges::with_initial_range(r(0, 99)));
+    let a = tracked.allocate(10).unwrap();
+    let b = tracked.allocate(10).unwrap();
+    assert_eq!(tracked.set_first_used(), Some(20));
+    assert_eq!(
+        (tracked.outstanding_count(), tracked.outstanding_total()),
+        (3, 21)
+    );
+
+    // Freeing indices which are already free releases nothing
+    tracked.set_free(50);
+    assert_eq!(
+        (tracked.outstanding_count(), tracked.outstanding_total()),
+        (3, 21)
+    );
+
+    tracked.set_range_free(b);
+    assert_eq!(
+        (tracked.outstanding_count(), tracked.outstanding_total()),
+        (2, 11)
+    );
+    tracked.set_free(20);
+    tracked.set_range_free(a);
+    assert_eq!(
+        (tracked.outstanding_count(), tracked.outstanding_total()),
+        (0, 0)
+    );
+    assert_eq!(tracked.high_water_mark(), 21);
+    tracked.assert_no_outstanding();
+}
+
+#[test]
+fn dropping_with_outstanding_blocks_panics_when_asked_to() {
+    let result = panic::catch_unwind(|| {
+        let mut tracked = TrackedFreeRanges::new(FreeRanges::with_initial_range(r(0, 9)));
+        tracked.set_panic_on_drop(true);
+        tracked.allocate(1);
+    });
+    assert!(result.is_err());
+
+    let mut tracked = TrackedFreeRanges::new(FreeRanges::with_initial_range(r(0, 9)));
+    tracked.allocate(1);
+    drop(tracked);
+}
+
+#[test]
+#[should_panic(expected = "still outstanding")]
+fn assert_no_outstanding_fails_with_a_block_left() {
+    let mut tracked = TrackedFreeRanges::new(FreeRanges::with_initial_range(r(0, 9)));
+    tracked.allocate(4);
+    tracked.assert_no_outstanding();
+}
+
+#[test]
+#[cfg(feature = "tracked-ranges")]
+fn partial_frees_split_the_outstanding_blocks() {
+    let mut tracked = TrackedFreeRanges::new(FreeRanges::with_initial_range(r(0, 99)));
+    tracked.allocate(10).unwrap();
+    tracked.allocate(10).unwrap();
+    tracked.set_first_used();
+
+    tracked.set_range_free(r(3, 5));
+    assert_eq!(
+        spans(tracked.outstanding_ranges()),
+        vec![(0, 2), (6, 9), (10, 19), (20, 20)]
+    );
+    assert_eq!(
+        (tracked.outstanding_count(), tracked.outstanding_total()),
+        (4, 18)
+    );
+
+    tracked.set_range_free(r(8, 12));
+    assert_eq!(
+        spans(tracked.outstanding_ranges()),
+        vec![(0, 2), (6, 7), (13, 19), (20, 20)]
+    );
+    assert_eq!(tracked.outstanding_total(), 3 + 2 + 7 + 1);
+
+    tracked.set_range_free(r(0, 20));
+    tracked.assert_no_outstanding();
+    assert_eq!(tracked.outstanding_total(), 0);
+}
+
+#[test]
+fn high_water_mark_rises_and_never_falls() {
+    let mut tracked = TrackedFreeRanges::new(FreeRanges::with_initial_range(r(0, 99)));
+    assert_eq!(tracked.high_water_mark(), 0);
+    tracked.allocate(10);
+    assert_eq!(tracked.high_water_mark(), 10);
+    tracked.allocate(5);
+    assert_eq!(tracked.high_water_mark(), 15);
+
+    tracked.set_range_free(r(0, 9));
+    assert_eq!(
+        (tracked.outstanding_total(), tracked.high_water_mark()),
+        (5, 15)
+    );
+    tracked.set_range_free(r(50, 60));
+    assert_eq!(tracked.outstanding_total(), 5);
+    tracked.allocate(8);
+    assert_eq!(tracked.high_water_mark(), 15);
+    for _ in 0..3 {
+        tracked.set_first_used();
+    }
+    assert_eq!(tracked.high_water_mark(), 16);
+
+    tracked.set_range_free(r(0, 99));
+    assert_eq!(
+        (tracked.outstanding_total(), tracked.high_water_mark()),
+        (0, 16)
+    );
+}
@@ -0,0 +1,63 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use common::{r, span};
+use free_ranges::{FreeRanges, Range};
+
+fn unchecked(ranges: &[Range]) -> FreeRanges {
+    FreeRanges::from_inner_unchecked(ranges.iter().cloned().collect::<BTreeSet<_>>())
+}
+
+#[test]
+fn first_overlap_on_a_clean_set() {
+    let mut ranges = FreeRanges::new();
+    assert_eq!(ranges.first_overlap(), None);
+    ranges.set_range_free(r(0, 9));
+    ranges.set_range_free(r(11, 19));
+    ranges.set_range_free(r(30, usize::MAX));
+    assert_eq!(ranges.first_overlap(), None);
+}
+
+#[test]
+fn first_overlap_finds_the_first_adjacent_pair() {
+    let corrupt = unchecked(&[r(0, 4), r(10, 12), r(13, 15), r(16, 20)]);
+    let (a, b) = corrupt.first_overlap().unwrap();
+    assert_eq!((span(a), span(b)), ((10, 12), (13, 15)));
+
+    let corrupt = unchecked(&[r(0, usize::MAX - 1), r(usize::MAX, usize::MAX)]);
+    let (a, b) = corrupt.first_overlap().unwrap();
+    assert_eq!(
+        (span(a), span(b)),
+        ((0, usize::MAX - 1), (usize::MAX, usize::MAX))
+    );
+}
+
+#[test]
+fn is_canonical_on_clean_sets() {
+    assert!(FreeRanges::new().is_canonical());
+    assert!(FreeRanges::with_all_free().is_canonical());
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 4));
+    ranges.set_range_free(r(6, 9));
+    ranges.set_free(usize::MAX);
+    assert!(ranges.is_canonical());
+
+    // Ranges kept apart by a barrier may touch
+    let mut split = FreeRanges::with_initial_range(r(0, 9));
+    split.add_barrier(4);
+    assert_eq!(split.range_count(), 2);
+    assert!(split.is_canonical());
+}
+
+#[test]
+fn is_canonical_rejects_adjacent_and_empty_ranges() {
+    assert!(!unchecked(&[r(0, 4), r(5, 9)]).is_canonical());
+    assert!(!unchecked(&[r(0, 4), r(10, 12), r(13, 15)]).is_canonical());
+    assert!(!unchecked(&[r(0, usize::MAX - 1), r(usize::MAX, usize::MAX)]).is_canonical());
+    let (min, max) = (5, 4);
+    assert!(!unchecked(&[r(min, max)]).is_canonical());
+    assert!(unchecked(&[r(0, 4), r(6, 9)]).is_canonical());
+}
@@ -0,0 +1,47 @@
+extern crate free_ranges;
+
+mod common;
+
+use std::thread;
+
+use common::{r, spans};
+use free_ranges::FreeRanges;
+
+#[test]
+fn snapshots_are_unaffected_by_later_writes() {
+    let mut ranges = FreeRanges::new();
+    ranges.set_range_free(r(0, 4));
+    ranges.set_range_free(r(10, 14));
+    let view = ranges.snapshot();
+    ranges.set_range_free(r(20, 29));
+
+    let reader = {
+        let view = view.clone();
+        thread::spawn(move || {
+            (
+                view.is_free(3),
+                view.is_free(20),
+                view.total_free(),
+                view.next_free(5),
+                view.next_free(12),
+                view.next_free(15),
+            )
+        })
+    };
+    assert_eq!(
+        reader.join().unwrap(),
+        (true, false, 10, Some(10), Some(12), None)
+    );
+    assert_eq!(view.free_ranges().count(), 2);
+    assert_eq!(view.range_count(), 2);
+    assert_eq!(spans(view.to_free_ranges()), vec![(0, 4), (10, 14)]);
+}
+
+#[test]
+fn snapshot_totals_saturate() {
+    assert_eq!(
+        FreeRanges::with_all_free().snapshot().total_free(),
+        usize::MAX
+    );
+    assert_eq!(FreeRanges::new().snapshot().next_free(0), None);
+}